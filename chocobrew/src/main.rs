@@ -1,8 +1,5 @@
 use choco::{
-    petgraph::{
-        graph::NodeIndex,
-        visit::{self, EdgeRef as _},
-    },
+    petgraph::{graph::NodeIndex, visit},
     Story,
 };
 use copypasta::{ClipboardContext, ClipboardProvider};
@@ -22,6 +19,9 @@ use std::{
     sync::Arc,
 };
 
+/// Preview keeps the indentation of code after leading signals, e.g. `@style{cp}@{}`
+const PREVIEW_CONFIG: choco::ParseConfig = choco::ParseConfig::new().keep_indent(true);
+
 fn main() -> eframe::Result<()> {
     env_logger::builder()
         .format_timestamp(None)
@@ -72,6 +72,13 @@ impl App {
                     if command_button(ui, RichText::new("Save as.."), shortcuts.save_as) {
                         State::save_file_as(self.state.clone());
                     }
+                    let mut state = self.state.lock();
+                    if ui
+                        .checkbox(&mut state.case_insensitive_prompts, "Any case prompts")
+                        .changed()
+                    {
+                        state.update_state();
+                    }
                 });
                 ui[1]
                     .with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
@@ -106,10 +113,20 @@ impl App {
 
         ui.horizontal_wrapped(|ui| {
             let mut state = self.state.lock();
-            let mut bookmarks: Vec<_> = state.guide.keys().map(String::to_owned).collect();
-            bookmarks.sort_unstable();
+            ui.checkbox(&mut state.sort_bookmarks, "A-Z");
+            // nodes are added in the order bookmarks are written
+            let mut bookmarks: Vec<_> = state
+                .guide
+                .iter()
+                .map(|(bookmark, index)| (*index, bookmark.to_owned()))
+                .collect();
+            if state.sort_bookmarks {
+                bookmarks.sort_unstable_by(|(_, a), (_, b)| a.cmp(b));
+            } else {
+                bookmarks.sort_unstable_by_key(|(index, _)| *index);
+            }
 
-            for bookmark in bookmarks {
+            for (_, bookmark) in bookmarks {
                 let mut text = RichText::new(&bookmark).monospace();
                 let was_selected = bookmark == state.starting_bookmark;
                 if was_selected {
@@ -121,40 +138,36 @@ impl App {
                     } else {
                         state.starting_bookmark = bookmark.clone();
                     }
+                    // problems include bookmarks unreachable from the starting one
+                    state.update_state();
                 }
             }
         });
+        for problem in &self.state.lock().problems {
+            ui.label(RichText::new(problem).color(ui.visuals().error_fg_color));
+        }
     }
 
-    fn show_events(&self, range: ops::Range<usize>, ui: &mut egui::Ui) {
-        let state = self.state.lock();
-        let events = choco::event_iter(state.content.get(range).unwrap_or_default());
-        for event in events {
+    fn show_events(state: &State, events: choco::EventIter, ui: &mut egui::Ui) {
+        for event in events.read_options(state.read_options()) {
             match event {
-                choco::Event::Signal(choco::Signal::Ping) => {
-                    ui.label(RichText::new('@').weak());
-                }
-                choco::Event::Signal(choco::Signal::Prompt(prompt)) => {
-                    ui.add(
-                        egui::Label::new(RichText::new(format!("@{}", prompt.slice)).weak())
-                            .truncate(true),
-                    );
-                }
-                choco::Event::Signal(choco::Signal::Param(param)) => {
-                    ui.add(
-                        egui::Label::new(RichText::new(format!("@{{{}}}", param.slice)).weak())
-                            .truncate(true),
-                    );
-                }
-                choco::Event::Signal(choco::Signal::Call { prompt, param }) => {
+                choco::Event::Signal(signal) => {
                     ui.add(
                         egui::Label::new(
-                            RichText::new(format!("@{}{{{}}}", prompt.slice, param.slice)).weak(),
+                            RichText::new(state.content.get(signal.span()).unwrap_or_default())
+                                .weak(),
                         )
                         .truncate(true),
                     );
                 }
-                choco::Event::Text { style, content } => {
+                choco::Event::Text {
+                    style,
+                    content,
+                    leading,
+                } => {
+                    if leading == choco::Spacing::Space {
+                        ui.label(" ");
+                    }
                     let mut text = RichText::new(content.slice);
                     if style.contains(choco::Style::BOLD) {
                         text = text.strong();
@@ -174,23 +187,51 @@ impl App {
                     if style.contains(choco::Style::PANEL) {
                         text = text.background_color(ui.style().visuals.extreme_bg_color);
                     }
+                    if let Some(level) = style.heading_level() {
+                        text = text.size(match level {
+                            1 => 28.0,
+                            2 => 22.0,
+                            _ => 18.0,
+                        });
+                    }
                     if style.contains(choco::Style::QUOTE) {
                         text = text.color(ui.style().visuals.hyperlink_color);
                     }
                     ui.add(egui::Label::new(text).truncate(true));
                 }
-                choco::Event::Break => {
+                choco::Event::Link { url, text, .. } => {
+                    ui.hyperlink_to(text.slice, url.slice);
+                }
+                choco::Event::Break(_) => (),
+                choco::Event::ParagraphBreak(_) => {
                     ui.separator();
                 }
+                choco::Event::Diagnostic(diagnostic) => {
+                    ui.label(
+                        RichText::new(diagnostic.to_string()).color(ui.visuals().error_fg_color),
+                    );
+                }
             }
         }
     }
 
+    fn show_status(&self, ui: &mut egui::Ui) {
+        let stats = &self.state.lock().stats;
+        ui.label(
+            RichText::new(format!(
+                "{} bookmarks, {} choices, {} words, longest path of {} choices",
+                stats.bookmarks.len(),
+                stats.choices,
+                stats.words,
+                stats.longest_path
+            ))
+            .weak(),
+        );
+    }
+
     fn show_preview(&self, ui: &mut egui::Ui) {
         let state = self.state.lock();
         if let Some(start) = state.guide.get(&state.starting_bookmark) {
-            let index_to_name: HashMap<_, _> =
-                state.guide.iter().map(|entry| (entry.1, entry.0)).collect();
             let mut bfs = visit::Bfs::new(&state.story, *start);
             while let Some(index) = bfs.next(&state.story) {
                 egui::Frame::default()
@@ -199,30 +240,51 @@ impl App {
                         ..Default::default()
                     })
                     .show(ui, |ui| {
-                        egui::CollapsingHeader::new(index_to_name[&index])
+                        egui::CollapsingHeader::new(&state.names[index.index()])
                             .default_open(true)
                             .show(ui, |ui| {
-                                self.show_events(state.story[index].clone(), ui);
-                                for edge in state.story.edges(index) {
+                                Self::show_events(
+                                    &state,
+                                    choco::node_events_with_config(
+                                        &state.story,
+                                        index,
+                                        &state.content,
+                                        PREVIEW_CONFIG,
+                                    ),
+                                    ui,
+                                );
+                                for (edge, _) in choco::story_choices(&state.story, index) {
+                                    let (_, target) = state.story.edge_endpoints(edge).unwrap();
+                                    let header = match choco::choice_label(
+                                        &state.story,
+                                        edge,
+                                        &state.content,
+                                    ) {
+                                        Some(label) => {
+                                            format!("{} | {label}", state.names[target.index()])
+                                        }
+                                        None => state.names[target.index()].clone(),
+                                    };
                                     egui::Frame::default()
                                         .outer_margin(egui::Margin {
                                             right: 16.0,
                                             ..Default::default()
                                         })
                                         .show(ui, |ui| {
-                                            egui::CollapsingHeader::new(
-                                                index_to_name[&edge.target()],
-                                            )
-                                            .default_open(true)
-                                            .show(
-                                                ui,
-                                                |ui| {
-                                                    self.show_events(
-                                                        state.story[edge.id()].clone(),
+                                            egui::CollapsingHeader::new(header)
+                                                .default_open(true)
+                                                .show(ui, |ui| {
+                                                    Self::show_events(
+                                                        &state,
+                                                        choco::edge_events_with_config(
+                                                            &state.story,
+                                                            edge,
+                                                            &state.content,
+                                                            PREVIEW_CONFIG,
+                                                        ),
                                                         ui,
                                                     );
-                                                },
-                                            );
+                                                });
                                         });
                                 }
                             });
@@ -330,6 +392,9 @@ impl eframe::App for App {
             .resizable(false)
             .show(ctx, |ui| self.show_menu(ui, &shortcuts))
             .inner;
+        egui::TopBottomPanel::new(egui::panel::TopBottomSide::Bottom, "status")
+            .resizable(false)
+            .show(ctx, |ui| self.show_status(ui));
         egui::SidePanel::new(egui::panel::Side::Left, "guide")
             .min_width(ctx.screen_rect().width() * 0.19)
             .default_width(ctx.screen_rect().width() * 0.1914)
@@ -369,7 +434,13 @@ struct State {
     content: String,
     story: Story,
     guide: HashMap<String, NodeIndex>,
+    /// Bookmark names indexed by their nodes, without aliases
+    names: Vec<String>,
     starting_bookmark: String,
+    case_insensitive_prompts: bool,
+    problems: Vec<String>,
+    sort_bookmarks: bool,
+    stats: choco::StoryStats,
 }
 
 impl Default for State {
@@ -382,7 +453,12 @@ impl Default for State {
             content: String::new(),
             story: Story::new(),
             guide: HashMap::new(),
+            names: Vec::new(),
             starting_bookmark: String::new(),
+            case_insensitive_prompts: false,
+            problems: Vec::new(),
+            sort_bookmarks: false,
+            stats: choco::StoryStats::default(),
         }
     }
 }
@@ -394,8 +470,67 @@ impl State {
         Ok(())
     }
 
+    fn read_options(&self) -> choco::ReadOptions {
+        choco::ReadOptions::new().case_insensitive_prompts(self.case_insensitive_prompts)
+    }
+
     fn update_state(&mut self) {
-        let (guide, story) = choco::read([self.content.as_str()]);
+        let (guide, story, warnings) =
+            choco::read_checked([self.content.as_str()], self.read_options());
+        self.problems = warnings
+            .into_iter()
+            .map(|warning| match warning {
+                choco::ReadWarning::DuplicateBookmark { name, .. } => {
+                    format!("`{name}` is bookmarked more than once")
+                }
+                choco::ReadWarning::DanglingChoice {
+                    from_bookmark,
+                    target_name,
+                    ..
+                } => format!("`{from_bookmark}` has a choice of missing `{target_name}`"),
+                choco::ReadWarning::ChoiceOutsideBookmark { target_name, .. } => {
+                    format!("choice of `{target_name}` is before any bookmark")
+                }
+                choco::ReadWarning::EmptyBookmarkName { .. } => {
+                    "bookmark has an empty name".to_owned()
+                }
+                choco::ReadWarning::InvalidChoiceWeight {
+                    target_name,
+                    weight,
+                    ..
+                } => format!("choice of `{target_name}` has weight `{weight}` that isn't a number"),
+                choco::ReadWarning::EmptyChoiceCondition { target_name, .. } => {
+                    format!("choice of `{target_name}` has an empty condition")
+                }
+                choco::ReadWarning::EmptyChoiceLabel { target_name, .. } => {
+                    format!("choice of `{target_name}` has an empty label")
+                }
+                choco::ReadWarning::IgnoredParams { target_name, .. } => {
+                    format!("params after `{target_name}` are left out")
+                }
+                choco::ReadWarning::TakenAlias { alias, .. } => {
+                    format!("`{alias}` is taken by another bookmark")
+                }
+                choco::ReadWarning::CollidingName {
+                    name, first_name, ..
+                } => format!("`{name}` is the same as `{first_name}` once normalized"),
+            })
+            .collect();
+        if !self.starting_bookmark.is_empty() {
+            for name in
+                choco::unreachable_from(&guide, &story, &self.starting_bookmark, &self.content)
+            {
+                self.problems.push(format!(
+                    "`{name}` can't be reached from `{}`",
+                    self.starting_bookmark
+                ));
+            }
+        }
+        self.stats = choco::stats(&guide, &story, &self.content);
+        self.names = choco::bookmark_names(&guide, &story, &self.content)
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
         let guide = guide
             .into_iter()
             .map(|(prompt, value)| (prompt.to_owned(), value))
@@ -0,0 +1,106 @@
+use crate::{EventIter, OwnedEvent};
+use alloc::{string::String, vec::Vec};
+
+/// Text decoded from bytes, with every invalid sequence replaced by `U+FFFD`,
+/// along with the way back to byte-indices of the original bytes
+pub(crate) struct LossyText {
+    pub text: String,
+    /// Byte-index pairs of decoded text and original bytes right after each replacement,
+    /// in between which both advance together
+    resyncs: Vec<(usize, usize)>,
+}
+
+impl LossyText {
+    pub fn new(bytes: &[u8]) -> Self {
+        let mut text = String::with_capacity(bytes.len());
+        let mut resyncs = Vec::new();
+        let mut original = 0;
+        for chunk in bytes.utf8_chunks() {
+            text.push_str(chunk.valid());
+            original += chunk.valid().len();
+            if !chunk.invalid().is_empty() {
+                text.push(char::REPLACEMENT_CHARACTER);
+                original += chunk.invalid().len();
+                resyncs.push((text.len(), original));
+            }
+        }
+        Self { text, resyncs }
+    }
+
+    /// Byte-index in original bytes for a char boundary of decoded text,
+    /// where a replacement stands for the whole invalid sequence
+    pub fn original_index(&self, index: usize) -> usize {
+        match self
+            .resyncs
+            .partition_point(|(decoded, _)| *decoded <= index)
+        {
+            0 => index,
+            resync_index => {
+                let (decoded, original) = self.resyncs[resync_index - 1];
+                original + index - decoded
+            }
+        }
+    }
+}
+
+/// Same as [`EventIter::new`] for bytes that may be invalid UTF-8, with each invalid
+/// sequence replaced by `U+FFFD`, as in [`String::from_utf8_lossy`].
+/// Slices are of the decoded text, while ranges are byte-indices of `bytes` themselves.
+/// As decoding may take a copy, events are owned and parsed all at once.
+pub fn event_iter_bytes(bytes: &[u8]) -> impl Iterator<Item = OwnedEvent> {
+    let lossy = LossyText::new(bytes);
+    EventIter::new(&lossy.text)
+        .map(|event| {
+            event
+                .map_indices(|index| lossy.original_index(index))
+                .into_owned()
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::event_iter_bytes;
+    use crate::{Event, EventIter, OwnedEvent};
+
+    #[test]
+    fn valid() {
+        const SAMPLE: &str = "@bookmark{greet} Hello, @name!\n\n@style{b}@{Bold}";
+        let events: Vec<_> = event_iter_bytes(SAMPLE.as_bytes()).collect();
+        let expected: Vec<_> = EventIter::new(SAMPLE).map(Event::into_owned).collect();
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn invalid() {
+        const SAMPLE: &[u8] = b"\xffHi @wave{\xe2\x82} ok\n@x{\xf0\x9f\x98} \xc0\xc1end";
+        let events: Vec<_> = event_iter_bytes(SAMPLE).collect();
+        let ranges: Vec<_> = events
+            .iter()
+            .map(|event| {
+                let event = Event::from(event);
+                (event.slice(), &SAMPLE[event.range()])
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            [
+                (Some("\u{fffd}Hi"), &b"\xffHi"[..]),
+                (None, b"@wave{\xe2\x82}"),
+                (Some(" ok"), b" ok"),
+                (Some("\n"), b"\n"),
+                (None, b"@x{\xf0\x9f\x98}"),
+                (Some("\u{fffd}\u{fffd}end"), b"\xc0\xc1end"),
+            ]
+        );
+        let Some(OwnedEvent::Signal(signal)) = events.get(1) else {
+            panic!("expected signal, got {:?}", events.get(1));
+        };
+        let crate::OwnedSignal::Call { param, .. } = signal else {
+            panic!("expected call, got {signal:?}");
+        };
+        assert_eq!(param.slice, "\u{fffd}");
+        assert_eq!(&SAMPLE[param.range.clone()], b"\xe2\x82");
+    }
+}
@@ -0,0 +1,389 @@
+use crate::{graph::DEFAULT_CHOICE_WEIGHT, OwnedStory, ParseConfig};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use petgraph::visit::EdgeRef;
+
+/// Writes `name` as the param of a signal, followed by the `once` flag, the `weight`,
+/// the `label` and the `condition` of a choice if they aren't the default ones,
+/// verbatim if it has brackets or backslashes in it.
+/// Labels with `?` in them are quoted, so that it isn't taken for the start of the condition.
+pub(crate) fn push_param(
+    out: &mut String,
+    name: &str,
+    weight: u32,
+    once: bool,
+    label: Option<&str>,
+    condition: Option<&str>,
+) {
+    let is_verbatim = [Some(name), label, condition]
+        .into_iter()
+        .flatten()
+        .any(|part| part.contains(['{', '}', '\\']));
+    out.push_str(if is_verbatim { "{{ " } else { "{" });
+    out.push_str(name);
+    if once {
+        out.push_str(" !once");
+    }
+    if weight != DEFAULT_CHOICE_WEIGHT {
+        out.push_str(" * ");
+        out.push_str(&weight.to_string());
+    }
+    if let Some(label) = label {
+        out.push_str(" | ");
+        if label.contains('?') {
+            out.push('"');
+            out.push_str(label);
+            out.push('"');
+        } else {
+            out.push_str(label);
+        }
+    }
+    if let Some(condition) = condition {
+        out.push_str(" ? ");
+        out.push_str(condition);
+    }
+    out.push_str(if is_verbatim { " }}" } else { "}" });
+}
+
+/// Writes a `meta` signal with `pairs` of keys and values, if there are any,
+/// quoting those with commas or `=` in them
+pub(crate) fn push_meta<'a>(
+    out: &mut String,
+    pairs: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+) {
+    let pairs: Vec<_> = pairs.into_iter().collect();
+    if pairs.is_empty() {
+        return;
+    }
+    let is_verbatim = pairs
+        .iter()
+        .flat_map(|(key, value)| [Some(*key), *value])
+        .flatten()
+        .any(|part| part.contains(['{', '}', '\\']));
+    out.push_str(if is_verbatim { "@meta{{ " } else { "@meta{" });
+    let push_part = |out: &mut String, part: &str| {
+        if part.contains([',', '=']) {
+            out.push('"');
+            out.push_str(part);
+            out.push('"');
+        } else {
+            out.push_str(part);
+        }
+    };
+    for (index, (key, value)) in pairs.into_iter().enumerate() {
+        if index != 0 {
+            out.push_str(", ");
+        }
+        push_part(out, key);
+        if let Some(value) = value {
+            out.push('=');
+            push_part(out, value);
+        }
+    }
+    out.push_str(if is_verbatim { " }}" } else { "}" });
+}
+
+/// Writes a `tag` signal with `tags`, if there are any, quoting those with commas in them
+pub(crate) fn push_tags<'a>(out: &mut String, tags: impl IntoIterator<Item = &'a str>) {
+    let tags: Vec<_> = tags.into_iter().collect();
+    if tags.is_empty() {
+        return;
+    }
+    let is_verbatim = tags.iter().any(|tag| tag.contains(['{', '}', '\\']));
+    out.push_str(if is_verbatim { "@tag{{ " } else { "@tag{" });
+    for (index, tag) in tags.into_iter().enumerate() {
+        if index != 0 {
+            out.push_str(", ");
+        }
+        if tag.contains(',') {
+            out.push('"');
+            out.push_str(tag);
+            out.push('"');
+        } else {
+            out.push_str(tag);
+        }
+    }
+    out.push_str(if is_verbatim { " }}" } else { "}" });
+}
+
+/// Writes `text` with every `@` doubled, so that none of it is taken for a signal
+pub(crate) fn push_text(out: &mut String, text: &str) {
+    for ch in text.chars() {
+        if ch == '@' {
+            out.push('@');
+        }
+        out.push(ch);
+    }
+}
+
+/// Writes source text that [`read`](crate::read) turns back into the same story,
+/// e.g. to lay out bookmarks and choices first and write the text later.
+/// Bookmarks are written in the order of their indices, each followed by its choices.
+///
+/// Names, labels, conditions, meta and tags are trimmed when read, so whitespace around them is lost,
+/// and those with `}}` in them can't be written at all, nor can names of choices with `|`, `?`, `*` or `"`,
+/// or labels, meta and tags with `"`.
+#[must_use]
+pub fn compose(story: &OwnedStory) -> String {
+    let config = ParseConfig::new();
+    let mut out = String::new();
+    for node in story.node_indices() {
+        if node.index() != 0 {
+            out.push('\n');
+        }
+        out.push_str("@bookmark");
+        push_param(
+            &mut out,
+            &story[node].name,
+            DEFAULT_CHOICE_WEIGHT,
+            false,
+            None,
+            None,
+        );
+        push_meta(
+            &mut out,
+            story[node]
+                .meta
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_deref())),
+        );
+        push_tags(&mut out, story[node].tags.iter().map(String::as_str));
+        out.push('\n');
+        push_text(&mut out, &story[node].text);
+        out.push('\n');
+        let mut edges: Vec<_> = story.edges(node).collect();
+        edges.sort_by_key(EdgeRef::id);
+        for edge in edges {
+            out.push_str("@choice");
+            push_param(
+                &mut out,
+                &story[edge.target()].name,
+                edge.weight().weight,
+                edge.weight().once,
+                edge.weight().label.as_deref(),
+                edge.weight().condition.as_deref(),
+            );
+            let text = &edge.weight().text;
+            // otherwise the text would be taken for another param of the choice
+            if text.starts_with(|ch| config.is_left_bracket(ch)) {
+                out.push(' ');
+            }
+            push_text(&mut out, text);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compose;
+    use crate::{OwnedBookmark, OwnedChoice, OwnedStory};
+    use petgraph::graph::{DiGraph, NodeIndex};
+    use quickcheck::quickcheck;
+
+    const NAMES: [&str; 6] = ["greet", "bye", "a b", "act{1}", "x}", "ё"];
+
+    const PIECES: [&str; 10] = [
+        "Hello",
+        " ",
+        "@",
+        "@@",
+        "{",
+        "}",
+        "\n",
+        "@choice{bye}",
+        "(",
+        "@bookmark{greet}",
+    ];
+
+    fn story(names: &[&str], choices: &[(usize, usize, Option<&str>, &str)]) -> OwnedStory {
+        let mut graph = DiGraph::new();
+        let nodes: Vec<_> = names
+            .iter()
+            .map(|name| {
+                graph.add_node(OwnedBookmark {
+                    name: (*name).to_owned(),
+                    meta: Vec::new(),
+                    tags: Vec::new(),
+                    text: format!("Text of {name} @here"),
+                })
+            })
+            .collect();
+        for (from, to, label, text) in choices {
+            graph.add_edge(
+                nodes[*from],
+                nodes[*to],
+                OwnedChoice {
+                    label: label.map(str::to_owned),
+                    condition: None,
+                    weight: 1,
+                    once: false,
+                    text: (*text).to_owned(),
+                },
+            );
+        }
+        graph.into()
+    }
+
+    /// Whether `story` read back from its composed text has the same names and choices
+    fn is_same(story: &OwnedStory) -> bool {
+        let text = compose(story);
+        let (guide, composed) = crate::read([text.as_str()]);
+        let mut expected: Vec<_> = story
+            .edge_indices()
+            .map(|edge| {
+                let (from, to) = story.edge_endpoints(edge).unwrap();
+                let (label, condition) = (&story[edge].label, &story[edge].condition);
+                (
+                    story[from].name.as_str(),
+                    story[to].name.as_str(),
+                    label.as_deref(),
+                    condition.as_deref(),
+                    story[edge].weight,
+                    story[edge].once,
+                )
+            })
+            .collect();
+        let composed_names = crate::graph::names_by_index(&guide, &composed, &text);
+        let mut actual: Vec<_> = composed
+            .edge_indices()
+            .map(|edge| {
+                let (from, to) = composed.edge_endpoints(edge).unwrap();
+                (
+                    composed_names[from.index()],
+                    composed_names[to.index()],
+                    crate::choice_label(&composed, edge, &text),
+                    crate::choice_condition(&composed, edge, &text),
+                    composed[edge].weight,
+                    composed[edge].once,
+                )
+            })
+            .collect();
+        expected.sort_unstable();
+        actual.sort_unstable();
+        guide.len() == story.node_count()
+            && story
+                .node_weights()
+                .all(|bookmark| guide.contains_key(bookmark.name.as_str()))
+            && expected == actual
+    }
+
+    #[test]
+    fn readme() {
+        let story = story(
+            &["greet", "bye"],
+            &[(0, 0, None, "– Come again?"), (0, 1, None, "– Hi!")],
+        );
+        assert_eq!(
+            compose(&story),
+            "@bookmark{greet}\nText of greet @@here\n@choice{greet}– Come again?\n\
+            @choice{bye}– Hi!\n\n@bookmark{bye}\nText of bye @@here\n"
+        );
+        assert!(is_same(&story));
+    }
+
+    #[test]
+    fn escaped() {
+        let story = story(
+            &NAMES,
+            &[
+                (3, 4, None, "{not a param}"),
+                (4, 3, None, "@choice{greet}"),
+                (0, 1, Some("Go {on}"), "On"),
+            ],
+        );
+        let text = compose(&story);
+        assert!(text.contains("@bookmark{{ act{1} }}"));
+        assert!(text.contains("@choice{{ x} }} {not a param}"));
+        assert!(text.contains("@choice{{ bye | Go {on} }}On"));
+        assert!(is_same(&story));
+    }
+
+    #[test]
+    fn labels() {
+        let story = story(&["greet", "bye"], &[(0, 1, Some("Leave"), "– Bye!")]);
+        assert!(compose(&story).contains("@choice{bye | Leave}– Bye!"));
+        assert!(is_same(&story));
+    }
+
+    #[test]
+    fn meta() {
+        let mut graph = story(&["greet"], &[]).into_graph();
+        graph[NodeIndex::new(0)].meta = vec![
+            ("author".to_owned(), Some("Kai".to_owned())),
+            ("music".to_owned(), Some("rain, soft".to_owned())),
+            ("draft".to_owned(), None),
+        ];
+        graph[NodeIndex::new(0)].tags = vec!["act1".to_owned(), "a, b".to_owned()];
+        let story = OwnedStory::from(graph);
+        let text = compose(&story);
+        assert!(text.starts_with(
+            "@bookmark{greet}@meta{author=Kai, music=\"rain, soft\", draft}@tag{act1, \"a, b\"}\n"
+        ));
+        let (guide, composed) = crate::read([text.as_str()]);
+        assert_eq!(
+            crate::node_meta(&composed, guide["greet"], &text),
+            [
+                ("author", Some("Kai")),
+                ("music", Some("rain, soft")),
+                ("draft", None)
+            ]
+        );
+        assert_eq!(
+            crate::node_tags(&composed, guide["greet"], &text),
+            ["act1", "a, b"]
+        );
+    }
+
+    #[test]
+    fn conditions() {
+        let mut graph = story(&["greet", "bye"], &[]).into_graph();
+        let (greet, bye) = (NodeIndex::new(0), NodeIndex::new(1));
+        graph.add_edge(
+            greet,
+            bye,
+            OwnedChoice {
+                label: Some("Ready?".to_owned()),
+                condition: Some("has_key and not tired".to_owned()),
+                weight: 3,
+                once: true,
+                text: "– Bye!".to_owned(),
+            },
+        );
+        let story = OwnedStory::from(graph);
+        assert!(compose(&story)
+            .contains("@choice{bye !once * 3 | \"Ready?\" ? has_key and not tired}– Bye!"));
+        assert!(is_same(&story));
+    }
+
+    quickcheck! {
+        fn arbitrary_stories(node_count: u8, choices: Vec<(u8, u8, bool, Vec<u8>)>) -> bool {
+            let names = &NAMES[..=usize::from(node_count) % NAMES.len()];
+            let texts: Vec<String> = choices
+                .iter()
+                .map(|(_, _, _, pieces)| {
+                    pieces
+                        .iter()
+                        .map(|piece| PIECES[usize::from(*piece) % PIECES.len()])
+                        .collect()
+                })
+                .collect();
+            let choices: Vec<_> = choices
+                .iter()
+                .zip(&texts)
+                .map(|((from, to, has_label, _), text)| {
+                    (
+                        usize::from(*from) % names.len(),
+                        usize::from(*to) % names.len(),
+                        has_label.then_some("Pick"),
+                        text.as_str(),
+                    )
+                })
+                .collect();
+            is_same(&story(names, &choices))
+        }
+    }
+}
@@ -1,6 +1,12 @@
+mod config;
+mod diagnostic;
 mod event;
 mod lines;
+mod position;
 mod raw;
 mod trim;
 
-pub use event::{Event, Iter, Signal, StrRange};
+pub use config::{Limit, ParseConfig, ParseLimits};
+pub use diagnostic::Diagnostic;
+pub use event::{Event, Iter, Signal, Spacing, StrRange};
+pub use position::LineIndex;
@@ -0,0 +1,103 @@
+const DEFAULT_BRACKETS: &[(char, char)] = &[('{', '}'), ('[', ']'), ('(', ')')];
+
+/// Limit of [`ParseLimits`] that got exceeded, see [`Diagnostic::LimitExceeded`](super::Diagnostic::LimitExceeded)
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Limit {
+    PromptLen,
+    ParamLen,
+    SignalsPerLine,
+}
+
+/// Bounds on how much a single construct may take, so that pathological text
+/// can't make parsing scan unboundedly. Each is unlimited by default.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ParseLimits {
+    /// Byte-length of a prompt, after which it is cut short, with the rest going as text.
+    /// A prompt is always at least a char long.
+    pub max_prompt_len: usize,
+    /// Byte-length of a param, after which it is cut short, with the rest going as text
+    pub max_param_len: usize,
+    /// Number of signals and comments on a line, after which the rest of it goes as text
+    pub max_signals_per_line: usize,
+}
+
+impl ParseLimits {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_prompt_len: usize::MAX,
+            max_param_len: usize::MAX,
+            max_signals_per_line: usize::MAX,
+        }
+    }
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parsing options shared by all the iterators
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ParseConfig {
+    pub(super) brackets: &'static [(char, char)],
+    pub(super) trim: bool,
+    pub(super) keep_indent: bool,
+    pub(super) limits: ParseLimits,
+}
+
+impl ParseConfig {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            brackets: DEFAULT_BRACKETS,
+            trim: true,
+            keep_indent: false,
+            limits: ParseLimits::new(),
+        }
+    }
+
+    /// Left and right bracket pairs that open and close a param,
+    /// by default these are `{}`, `[]` and `()`
+    #[must_use]
+    pub const fn brackets(self, brackets: &'static [(char, char)]) -> Self {
+        Self { brackets, ..self }
+    }
+
+    /// Whether `ch` opens a param
+    pub(crate) fn is_left_bracket(&self, ch: char) -> bool {
+        self.brackets.iter().any(|(left, _)| *left == ch)
+    }
+
+    /// Whether to trim whitespace around signals and drop empty text, which is the default
+    #[must_use]
+    pub const fn trim(self, trim: bool) -> Self {
+        Self { trim, ..self }
+    }
+
+    /// Whether to keep whitespace between the signals a line starts with and the text
+    /// after them when trimming, e.g. the indentation of code after `@style{cp}@{}`,
+    /// which is off by default
+    #[must_use]
+    pub const fn keep_indent(self, keep_indent: bool) -> Self {
+        Self {
+            keep_indent,
+            ..self
+        }
+    }
+
+    /// Bounds on prompts, params and signals, exceeding which is reported
+    /// with a diagnostic, see [`ParseLimits`]
+    #[must_use]
+    pub const fn limits(self, limits: ParseLimits) -> Self {
+        Self { limits, ..self }
+    }
+}
+
+impl Default for ParseConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
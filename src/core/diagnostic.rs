@@ -0,0 +1,77 @@
+use super::{Limit, StrRange};
+use core::{fmt, ops};
+
+/// Problem in the source text, which doesn't stop parsing but is likely a mistake
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Diagnostic<'a> {
+    /// Param bracket is never closed, so the param runs until the end of line
+    UnclosedParam {
+        /// the left bracket, which is left unclosed
+        bracket: StrRange<'a>,
+    },
+    /// Construct is cut short because of [`ParseLimits`](super::ParseLimits),
+    /// and whatever goes after the cut is parsed anew
+    LimitExceeded {
+        limit: Limit,
+        /// empty range where the construct is cut
+        at: StrRange<'a>,
+    },
+    /// Char of a `style` param that stands for no style, which is only reported
+    /// when asked for, see [`StyleConfig::strict`](crate::StyleConfig::strict)
+    UnknownStyle { ch: StrRange<'a> },
+}
+
+impl<'a> Diagnostic<'a> {
+    /// Byte-index range in original text the diagnostic points at
+    #[must_use]
+    pub fn range(&self) -> ops::Range<usize> {
+        match self {
+            Self::UnclosedParam { bracket } => bracket.range.clone(),
+            Self::LimitExceeded { at, .. } => at.range.clone(),
+            Self::UnknownStyle { ch } => ch.range.clone(),
+        }
+    }
+
+    /// Compares variants and slices, but not ranges
+    #[must_use]
+    pub fn eq_ignoring_ranges(&self, other: &Diagnostic) -> bool {
+        match (self, other) {
+            (Self::UnclosedParam { bracket }, Diagnostic::UnclosedParam { bracket: other }) => {
+                bracket.eq_ignoring_ranges(other)
+            }
+            (
+                Self::LimitExceeded { limit, .. },
+                Diagnostic::LimitExceeded {
+                    limit: other_limit, ..
+                },
+            ) => limit == other_limit,
+            (Self::UnknownStyle { ch }, Diagnostic::UnknownStyle { ch: other }) => {
+                ch.eq_ignoring_ranges(other)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Message about the problem, without its position
+impl<'a> fmt::Display for Diagnostic<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedParam { bracket } => write!(f, "unclosed `{}`", bracket.slice),
+            Self::LimitExceeded {
+                limit: Limit::PromptLen,
+                ..
+            } => f.write_str("prompt is too long"),
+            Self::LimitExceeded {
+                limit: Limit::ParamLen,
+                ..
+            } => f.write_str("param is too long"),
+            Self::LimitExceeded {
+                limit: Limit::SignalsPerLine,
+                ..
+            } => f.write_str("too many signals on a line"),
+            Self::UnknownStyle { ch } => write!(f, "unknown style `{}`", ch.slice),
+        }
+    }
+}
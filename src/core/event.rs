@@ -1,7 +1,37 @@
-use super::{lines, raw::Range, trim};
-use core::ops;
+use super::{
+    lines,
+    raw::{Range, PARAM_ESCAPE_CHAR, SIGNAL_CHAR},
+    trim, Diagnostic, LineIndex, ParseConfig,
+};
+use alloc::{borrow::Cow, collections::VecDeque, string::String, vec::Vec};
+use core::{
+    iter::{self, FusedIterator},
+    ops,
+};
+
+const ARG_SEPARATOR: char = ',';
+const ARG_QUOTE: char = '"';
+const PAIR_SEPARATOR: char = '=';
+
+/// Byte-index of the first `separator` in `text`, that is neither quoted nor escaped
+fn find_unquoted(text: &str, separator: char) -> Option<usize> {
+    let mut is_quoted = false;
+    let mut chars = text.char_indices();
+    while let Some((index, ch)) = chars.next() {
+        match ch {
+            PARAM_ESCAPE_CHAR => {
+                chars.next();
+            }
+            ARG_QUOTE => is_quoted = !is_quoted,
+            _ if ch == separator && !is_quoted => return Some(index),
+            _ => (),
+        }
+    }
+    None
+}
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct StrRange<'a> {
     /// original text sliced by `self.range`
     pub slice: &'a str,
@@ -9,27 +39,324 @@ pub struct StrRange<'a> {
     pub range: ops::Range<usize>,
 }
 
-#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+impl<'a> StrRange<'a> {
+    /// Compares slices only, which is handy when the same text is at different positions
+    #[must_use]
+    pub fn eq_ignoring_ranges(&self, other: &StrRange) -> bool {
+        self.slice == other.slice
+    }
+
+    /// Zero-based line and column of the range start, see [`LineIndex::position`]
+    #[must_use]
+    pub fn position_in(&self, index: &LineIndex) -> (usize, usize) {
+        index.position(self.range.start)
+    }
+
+    /// Logical value of a param, where backslash escapes are replaced:
+    /// `\n` and `\t` with a newline and a tab, and a backslash followed by
+    /// any other char (e.g. `\}` or `\\`) with that char.
+    /// Borrows the slice as is when there are no backslashes.
+    #[must_use]
+    pub fn unescaped(&self) -> Cow<'a, str> {
+        if !self.slice.contains(PARAM_ESCAPE_CHAR) {
+            return Cow::Borrowed(self.slice);
+        }
+        let mut unescaped = String::with_capacity(self.slice.len());
+        let mut chars = self.slice.chars();
+        while let Some(ch) = chars.next() {
+            if ch != PARAM_ESCAPE_CHAR {
+                unescaped.push(ch);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some(escaped) => unescaped.push(escaped),
+                None => unescaped.push(PARAM_ESCAPE_CHAR),
+            }
+        }
+        Cow::Owned(unescaped)
+    }
+
+    /// Same range without whitespace at either end, e.g. `intro` out of `@bookmark{ intro }`,
+    /// while whitespace in between is kept
+    #[must_use]
+    pub fn trimmed(&self) -> Self {
+        self.trimmed_within(0..self.slice.len())
+    }
+
+    /// Part of the slice, with `relative` range being relative to the slice start,
+    /// or `None` if it's out of bounds or doesn't lie on char boundaries
+    #[must_use]
+    pub fn slice(&self, relative: ops::Range<usize>) -> Option<Self> {
+        self.slice.get(relative.clone())?;
+        Some(self.within(relative))
+    }
+
+    /// Parts before and after `relative` byte-index, or `None` if it's not a char boundary
+    #[must_use]
+    pub fn split_at(&self, relative: usize) -> Option<(Self, Self)> {
+        let (left, right) = self.slice.split_at_checked(relative)?;
+        Some((
+            self.within(0..left.len()),
+            self.within(relative..relative + right.len()),
+        ))
+    }
+
+    /// Part after `prefix`, or `None` if the slice doesn't start with it
+    #[must_use]
+    pub fn strip_prefix(&self, prefix: &str) -> Option<Self> {
+        let rest = self.slice.strip_prefix(prefix)?;
+        Some(self.within(prefix.len()..prefix.len() + rest.len()))
+    }
+
+    /// Part before `suffix`, or `None` if the slice doesn't end with it
+    #[must_use]
+    pub fn strip_suffix(&self, suffix: &str) -> Option<Self> {
+        let rest = self.slice.strip_suffix(suffix)?;
+        Some(self.within(0..rest.len()))
+    }
+
+    /// First occurrence of `needle`, or `None` if there is none
+    #[must_use]
+    pub fn find(&self, needle: &str) -> Option<Self> {
+        let start = self.slice.find(needle)?;
+        Some(self.within(start..start + needle.len()))
+    }
+
+    /// Parts before and after the first `separator` that is neither quoted nor escaped,
+    /// same as arguments are split by [`StrRange::args`], or `None` if there is none
+    #[must_use]
+    pub fn split_unquoted(&self, separator: char) -> Option<(Self, Self)> {
+        let index = find_unquoted(self.slice, separator)?;
+        Some((
+            self.within(0..index),
+            self.within(index + separator.len_utf8()..self.slice.len()),
+        ))
+    }
+
+    /// Byte-index in the source for `relative` byte-index of the slice,
+    /// e.g. to locate a match found within trimmed text
+    ///
+    /// # Panics
+    ///
+    /// Panics if `relative` is out of bounds or not a char boundary of the slice
+    #[must_use]
+    pub fn abs(&self, relative: usize) -> usize {
+        assert!(
+            self.slice.is_char_boundary(relative),
+            "byte-index {relative} is not a char boundary of {:?}",
+            self.slice
+        );
+        self.range.start + relative
+    }
+
+    /// Byte-index of the slice for `absolute` byte-index in the source,
+    /// or `None` if it's out of the range or not a char boundary
+    #[must_use]
+    pub fn rel(&self, absolute: usize) -> Option<usize> {
+        let relative = absolute.checked_sub(self.range.start)?;
+        self.slice.is_char_boundary(relative).then_some(relative)
+    }
+
+    /// Part of the slice, with `relative` range being relative to the slice start
+    fn within(&self, relative: ops::Range<usize>) -> Self {
+        Self {
+            slice: &self.slice[relative.clone()],
+            range: self.abs(relative.start)..self.abs(relative.end),
+        }
+    }
+
+    fn trimmed_within(&self, relative: ops::Range<usize>) -> Self {
+        let piece = &self.slice[relative.clone()];
+        let start = relative.start + piece.len() - piece.trim_start().len();
+        let end = relative.start + piece.trim_end().len();
+        self.within(start..end.max(start))
+    }
+
+    /// Inside of the slice if it's wrapped in double quotes, or the slice itself otherwise
+    pub(crate) fn unquoted(self) -> Self {
+        let len = self.slice.len();
+        if len >= 2 * ARG_QUOTE.len_utf8()
+            && self.slice.starts_with(ARG_QUOTE)
+            && self.slice.ends_with(ARG_QUOTE)
+        {
+            self.within(ARG_QUOTE.len_utf8()..len - ARG_QUOTE.len_utf8())
+        } else {
+            self
+        }
+    }
+
+    /// Same as [`StrRange::args`], but keeping the quotes
+    fn quoted_args(&self) -> impl Iterator<Item = StrRange<'a>> {
+        let this = self.clone();
+        let mut start = 0;
+        let mut finished = this.slice.trim().is_empty();
+        iter::from_fn(move || {
+            if finished {
+                return None;
+            }
+            let end = find_unquoted(&this.slice[start..], ARG_SEPARATOR)
+                .map_or(this.slice.len(), |index| start + index);
+            let arg = this.trimmed_within(start..end);
+            if end == this.slice.len() {
+                finished = true;
+            } else {
+                start = end + ARG_SEPARATOR.len_utf8();
+            }
+            Some(arg)
+        })
+    }
+
+    /// Comma-separated arguments of a param (e.g. `@portrait{alice, happy, left}`), trimmed.
+    /// Arguments in double quotes may contain commas and have their quotes removed,
+    /// and backslash-escaped chars never separate arguments.
+    /// A blank param has no arguments, otherwise arguments between commas may be empty.
+    pub fn args(&self) -> impl Iterator<Item = StrRange<'a>> {
+        self.quoted_args().map(Self::unquoted)
+    }
+
+    /// Arguments split into keys and values on the first `=` (e.g. `@meta{author=Kai, draft}`),
+    /// where a value is absent for bare flags. Both are trimmed and unquoted,
+    /// same as [`StrRange::args`], so quoted keys and values may contain `=`.
+    pub fn pairs(&self) -> impl Iterator<Item = (StrRange<'a>, Option<StrRange<'a>>)> {
+        self.quoted_args()
+            .map(|arg| match find_unquoted(arg.slice, PAIR_SEPARATOR) {
+                Some(index) => (
+                    arg.trimmed_within(0..index).unquoted(),
+                    Some(
+                        arg.trimmed_within(index + PAIR_SEPARATOR.len_utf8()..arg.slice.len())
+                            .unquoted(),
+                    ),
+                ),
+                None => (arg.unquoted(), None),
+            })
+    }
+}
+
+/// Each signal has a `span` of the whole construct, from the `@`-char up to and including
+/// the right bracket (unless it's unclosed), which is what [`Signal::span`] returns
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Signal<'a> {
-    #[default]
     /// Just an `@`-char
-    Ping,
+    Ping { span: ops::Range<usize> },
     /// `@`-char suffixed with name
-    Prompt(StrRange<'a>),
-    /// `@`-char suffixed braces
-    Param(StrRange<'a>),
-    /// `@`-char suffixed with name and then braces
+    Prompt {
+        prompt: StrRange<'a>,
+        span: ops::Range<usize>,
+    },
+    /// `@`-char suffixed braces, which may be empty (e.g. `@{}`)
+    Param {
+        param: StrRange<'a>,
+        span: ops::Range<usize>,
+    },
+    /// `@`-char suffixed with name and then braces, which may be empty (e.g. `@bookmark{}`)
     Call {
         prompt: StrRange<'a>,
         param: StrRange<'a>,
+        /// Params in brackets right after the first one (e.g. `{Bold}` of `@style{b}{Bold}`)
+        extra_params: Vec<StrRange<'a>>,
+        span: ops::Range<usize>,
     },
 }
 
+impl<'a> Signal<'a> {
+    /// Byte-index range of the whole signal in original text, including `@`-char and brackets
+    #[must_use]
+    pub fn span(&self) -> ops::Range<usize> {
+        match self {
+            Self::Ping { span }
+            | Self::Prompt { span, .. }
+            | Self::Param { span, .. }
+            | Self::Call { span, .. } => span.clone(),
+        }
+    }
+
+    /// Compares variants and slices, but neither ranges nor spans
+    #[must_use]
+    pub fn eq_ignoring_ranges(&self, other: &Signal) -> bool {
+        match (self, other) {
+            (Self::Ping { .. }, Signal::Ping { .. }) => true,
+            (Self::Prompt { prompt, .. }, Signal::Prompt { prompt: other, .. }) => {
+                prompt.eq_ignoring_ranges(other)
+            }
+            (Self::Param { param, .. }, Signal::Param { param: other, .. }) => {
+                param.eq_ignoring_ranges(other)
+            }
+            (
+                Self::Call {
+                    prompt,
+                    param,
+                    extra_params,
+                    ..
+                },
+                Signal::Call {
+                    prompt: other_prompt,
+                    param: other_param,
+                    extra_params: other_extra_params,
+                    ..
+                },
+            ) => {
+                prompt.eq_ignoring_ranges(other_prompt)
+                    && param.eq_ignoring_ranges(other_param)
+                    && extra_params.len() == other_extra_params.len()
+                    && extra_params
+                        .iter()
+                        .zip(other_extra_params)
+                        .all(|(param, other)| param.eq_ignoring_ranges(other))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<'a> Default for Signal<'a> {
+    fn default() -> Self {
+        Self::Ping {
+            span: 0..SIGNAL_CHAR.len_utf8(),
+        }
+    }
+}
+
+/// Whitespace between a signal and the text following it, which is trimmed off
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Spacing {
+    /// Either nothing was trimmed, or there was no whitespace to begin with
+    #[default]
+    None,
+    Space,
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 pub enum Event<'a> {
     Signal(Signal<'a>),
-    Text(StrRange<'a>),
-    Break,
+    Text {
+        content: StrRange<'a>,
+        /// Whether whitespace was trimmed off right before the content
+        leading: Spacing,
+    },
+    /// Line terminator, which is either of `\n`, `\r\n` or lone `\r`.
+    /// There is no break after the last line, even if it's empty.
+    Break(StrRange<'a>),
+    /// Terminators of two or more lines in a row, with nothing but whitespace in between.
+    /// Only emitted when trimming, otherwise every line ends with its own break.
+    ParagraphBreak(StrRange<'a>),
+    /// Follows the event it's about
+    Diagnostic(Diagnostic<'a>),
+}
+
+impl<'a> Event<'a> {
+    /// Byte-index in original text where the event starts
+    pub(crate) fn start(&self) -> usize {
+        match self {
+            Self::Signal(signal) => signal.span().start,
+            Self::Text { content, .. } => content.range.start,
+            Self::Break(line_break) | Self::ParagraphBreak(line_break) => line_break.range.start,
+            Self::Diagnostic(diagnostic) => diagnostic.range().start,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -48,20 +375,253 @@ impl Offset {
     }
 }
 
+/// Events of a single line, excluding breaks
+#[derive(Clone, Debug)]
+struct LineEvents<'a> {
+    inner: trim::Iter<'a>,
+    offset: Offset,
+    diagnostic: Option<Diagnostic<'a>>,
+}
+
+impl<'a> LineEvents<'a> {
+    /// Comments are skipped, so there is no event for them
+    fn event(&mut self, range: Range) -> Option<Event<'a>> {
+        let full = self.inner.as_full_str();
+        Some(match range {
+            Range::Text(range) | Range::Escape(range) => Event::Text {
+                content: self.offset.slice(full, range),
+                leading: self.inner.leading(),
+            },
+            Range::Comment(_) => return None,
+            Range::LimitExceeded { limit, at } => Event::Diagnostic(Diagnostic::LimitExceeded {
+                limit,
+                at: self.offset.slice(full, at..at),
+            }),
+            Range::Signal {
+                span,
+                prompt,
+                param,
+                extra_params,
+                unclosed,
+            } => {
+                self.diagnostic = unclosed.map(|bracket| Diagnostic::UnclosedParam {
+                    bracket: self.offset.slice(full, bracket),
+                });
+                let span = self.offset.offset_range(span);
+                Event::Signal(match param {
+                    None if prompt.is_empty() => Signal::Ping { span },
+                    Some(param) if prompt.is_empty() => Signal::Param {
+                        param: self.offset.slice(full, param),
+                        span,
+                    },
+                    None => Signal::Prompt {
+                        prompt: self.offset.slice(full, prompt),
+                        span,
+                    },
+                    Some(param) => Signal::Call {
+                        prompt: self.offset.slice(full, prompt),
+                        param: self.offset.slice(full, param),
+                        extra_params: extra_params
+                            .into_iter()
+                            .map(|param| self.offset.slice(full, param))
+                            .collect(),
+                        span,
+                    },
+                })
+            }
+        })
+    }
+}
+
+impl<'a> Iterator for LineEvents<'a> {
+    type Item = Event<'a>;
+
+    /// Every range may be followed by a diagnostic
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = usize::from(self.diagnostic.is_some());
+        let upper = self.inner.size_hint().1;
+        (pending, upper.map(|upper| 2 * upper + pending))
+    }
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(diagnostic) = self.diagnostic.take() {
+            return Some(Event::Diagnostic(diagnostic));
+        }
+        loop {
+            let range = self.inner.next()?;
+            if let Some(event) = self.event(range) {
+                return Some(event);
+            }
+        }
+    }
+}
+
+/// Line events preceded by a break, unless it's the first line.
+/// Trimming needs to go forward, so the remaining events are buffered
+/// the first time one is taken from the back.
+#[derive(Clone, Debug)]
+struct Line<'a> {
+    preceding_break: Option<Event<'a>>,
+    events: LineEvents<'a>,
+    buffered: Option<VecDeque<Event<'a>>>,
+}
+
+impl<'a> Line<'a> {
+    fn new(
+        (terminator, start, inner): (&'a str, usize, trim::Iter<'a>),
+        base_offset: usize,
+    ) -> Self {
+        let offset = Offset(base_offset + start);
+        let line_break = (!terminator.is_empty()).then(|| {
+            let line_break = StrRange {
+                slice: terminator,
+                range: offset.0 - terminator.len()..offset.0,
+            };
+            if matches!(terminator, "\n" | "\r\n" | "\r") {
+                Event::Break(line_break)
+            } else {
+                Event::ParagraphBreak(line_break)
+            }
+        });
+        let events = LineEvents {
+            inner,
+            offset,
+            diagnostic: None,
+        };
+        Self {
+            preceding_break: line_break,
+            events,
+            buffered: None,
+        }
+    }
+}
+
+impl<'a> Line<'a> {
+    /// Skips events starting before `offset`, telling whether any are left
+    fn seek(&mut self, offset: usize) -> bool {
+        if let Some(buffered) = &mut self.buffered {
+            if self
+                .preceding_break
+                .as_ref()
+                .is_some_and(|event| event.start() >= offset)
+            {
+                return true;
+            }
+            self.preceding_break = None;
+            while buffered.front().is_some_and(|event| event.start() < offset) {
+                buffered.pop_front();
+            }
+            return !buffered.is_empty();
+        }
+        loop {
+            let mut rest = self.clone();
+            match rest.next() {
+                Some(event) if event.start() < offset => *self = rest,
+                Some(_) => return true,
+                None => return false,
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Line<'a> {
+    type Item = Event<'a>;
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let line_break = usize::from(self.preceding_break.is_some());
+        let (lower, upper) = if let Some(buffered) = &self.buffered {
+            (buffered.len(), Some(buffered.len()))
+        } else {
+            self.events.size_hint()
+        };
+        (lower + line_break, upper.map(|upper| upper + line_break))
+    }
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(line_break) = self.preceding_break.take() {
+            return Some(line_break);
+        }
+        match &mut self.buffered {
+            Some(buffered) => buffered.pop_front(),
+            None => self.events.next(),
+        }
+    }
+}
+
+impl<'a> DoubleEndedIterator for Line<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let events = &mut self.events;
+        self.buffered
+            .get_or_insert_with(|| events.collect())
+            .pop_back()
+            .or_else(|| self.preceding_break.take())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Iter<'a> {
-    current: Option<trim::Iter<'a>>,
+    front: Option<Line<'a>>,
     remainder: lines::Iter<'a>,
-    offset: Offset,
+    back: Option<Line<'a>>,
+    base_offset: usize,
 }
 
 impl<'a> Iter<'a> {
     #[must_use]
     pub fn new(text: &'a str) -> Self {
+        Self::with_config(text, ParseConfig::new())
+    }
+
+    /// Same as [`Iter::new`], but text ranges are exactly as they appear in `text`,
+    /// including whitespace around signals and whitespace-only lines.
+    #[must_use]
+    pub fn raw(text: &'a str) -> Self {
+        Self::with_config(text, ParseConfig::new().trim(false))
+    }
+
+    #[must_use]
+    pub fn with_config(text: &'a str, config: ParseConfig) -> Self {
         Self {
-            current: None,
-            remainder: lines::Iter::new(text),
-            offset: Offset(0),
+            front: None,
+            remainder: lines::Iter::with_config(text, config),
+            back: None,
+            base_offset: 0,
+        }
+    }
+
+    /// Same as [`Iter::new`], but for `text` that starts at `base_offset` of some larger text.
+    /// Emitted ranges are shifted by `base_offset`, while slices still borrow from `text`.
+    #[must_use]
+    pub fn with_offset(text: &'a str, base_offset: usize) -> Self {
+        Self::with_config_and_offset(text, ParseConfig::new(), base_offset)
+    }
+
+    /// Same as [`Iter::with_offset`], but with `config` to parse by
+    #[must_use]
+    pub fn with_config_and_offset(text: &'a str, config: ParseConfig, base_offset: usize) -> Self {
+        Self {
+            base_offset,
+            ..Self::with_config(text, config)
+        }
+    }
+
+    /// Skips events starting before `offset`, so that the next one starts at or after it.
+    /// The lines before the one `offset` is on are jumped over without being parsed.
+    pub fn seek(&mut self, offset: usize) {
+        if self.front.as_mut().is_some_and(|front| front.seek(offset)) {
+            return;
+        }
+        self.front = None;
+        self.remainder.seek(offset.saturating_sub(self.base_offset));
+        for line in self.remainder.by_ref() {
+            let mut front = Line::new(line, self.base_offset);
+            if front.seek(offset) {
+                self.front = Some(front);
+                return;
+            }
+        }
+        if self.back.as_mut().is_some_and(|back| !back.seek(offset)) {
+            self.back = None;
         }
     }
 }
@@ -70,59 +630,189 @@ impl<'a> Iterator for Iter<'a> {
     type Item = Event<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(current) = &mut self.current {
-            if let Some(range) = current.next() {
-                return Some(match range {
-                    Range::Text(range) => {
-                        Event::Text(self.offset.slice(current.as_full_str(), range))
-                    }
-                    Range::Signal { prompt, param } if param.is_empty() && prompt.is_empty() => {
-                        Event::Signal(Signal::Ping)
-                    }
-                    Range::Signal { prompt, param } if prompt.is_empty() => Event::Signal(
-                        Signal::Param(self.offset.slice(current.as_full_str(), param)),
-                    ),
-                    Range::Signal { prompt, param } if param.is_empty() => Event::Signal(
-                        Signal::Prompt(self.offset.slice(current.as_full_str(), prompt)),
-                    ),
-                    Range::Signal { prompt, param } => Event::Signal(Signal::Call {
-                        prompt: self.offset.slice(current.as_full_str(), prompt),
-                        param: self.offset.slice(current.as_full_str(), param),
-                    }),
-                });
+        loop {
+            if let Some(event) = self.front.as_mut().and_then(Iterator::next) {
+                return Some(event);
+            }
+            match self.remainder.next() {
+                Some(line) => self.front = Some(Line::new(line, self.base_offset)),
+                None => return self.back.as_mut()?.next(),
             }
-            self.offset.0 = self.remainder.offset();
-            self.current = self.remainder.next();
-            return if self.current.is_some() {
-                Some(Event::Break)
-            } else {
-                None
-            };
         }
-        self.offset.0 = self.remainder.offset();
-        self.current = self.remainder.next();
-        if self.current.is_some() {
-            self.next()
-        } else {
-            None
+    }
+
+    /// Events of the lines that are not reached yet are bounded by their byte-length:
+    /// every event but a diagnostic covers at least a byte, and there are no more
+    /// diagnostics than signals, with an extra break for a trailing empty line
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (front_lower, front_upper) = self.front.as_ref().map_or((0, Some(0)), Line::size_hint);
+        let (back_lower, back_upper) = self.back.as_ref().map_or((0, Some(0)), Line::size_hint);
+        let remainder_upper = 2 * self.remainder.remaining_len() + 1;
+        let upper = front_upper
+            .zip(back_upper)
+            .and_then(|(front, back)| front.checked_add(back)?.checked_add(remainder_upper));
+        (front_lower + back_lower, upper)
+    }
+}
+
+impl<'a> FusedIterator for Iter<'a> {}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.back.as_mut().and_then(DoubleEndedIterator::next_back) {
+                return Some(event);
+            }
+            match self.remainder.next_back() {
+                Some(line) => self.back = Some(Line::new(line, self.base_offset)),
+                None => return self.front.as_mut()?.next_back(),
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Event, Iter, Signal, StrRange};
+    use super::{Diagnostic, Event, Iter, ParseConfig, Signal, Spacing, StrRange};
+    use crate::core::{Limit, ParseLimits};
+    use std::borrow::Cow;
+    use std::ops::Range;
 
     #[test]
     fn full() {
         const SAMPLE: &str = "- Hello! @wave\n@c{1}@{i<4}- Hi!\n@c{2}@{s>7}- Howdy!@\n";
+        let summary: Vec<_> = Iter::new(SAMPLE)
+            .map(|event| match event {
+                Event::Text { content, .. } => ("text", "", content.slice),
+                Event::Signal(Signal::Prompt { prompt, .. }) => ("prompt", prompt.slice, ""),
+                Event::Signal(Signal::Call { prompt, param, .. }) => {
+                    ("call", prompt.slice, param.slice)
+                }
+                Event::Signal(Signal::Param { param, .. }) => ("param", "", param.slice),
+                Event::Signal(Signal::Ping { .. }) => ("ping", "", ""),
+                Event::Break(_) => ("break", "", ""),
+                event => panic!("unexpected {event:?}"),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("text", "", "- Hello!"),
+                ("prompt", "wave", ""),
+                ("break", "", ""),
+                ("call", "c", "1"),
+                ("param", "", "i<4"),
+                ("text", "", "- Hi!"),
+                ("break", "", ""),
+                ("call", "c", "2"),
+                ("param", "", "s>7"),
+                ("text", "", "- Howdy!"),
+                ("ping", "", ""),
+                ("break", "", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        const SAMPLE: &str = "@@start\nmid@@dle\nend @@";
+        let slices: Vec<_> = Iter::new(SAMPLE)
+            .map(|event| match event {
+                Event::Text {
+                    content: StrRange { slice, .. },
+                    ..
+                } => slice,
+                Event::Break(line_break) | Event::ParagraphBreak(line_break) => line_break.slice,
+                event => panic!("unexpected {event:?}"),
+            })
+            .collect();
+        assert_eq!(
+            slices,
+            ["@", "start", "\n", "mid", "@", "dle", "\n", "end ", "@"]
+        );
+    }
+
+    #[test]
+    fn raw() {
+        const SAMPLE: &str = "  @bookmark{greet} Hello, @@you! \n\n\t@choice{bye}  Bye@\n";
+        let mut source = String::new();
+        for event in Iter::raw(SAMPLE) {
+            match event {
+                Event::Text {
+                    content: StrRange { slice, .. },
+                    ..
+                } => source.push_str(slice),
+                Event::Signal(signal) => source.push_str(&SAMPLE[signal.span()]),
+                Event::Break(line_break) => source.push_str(line_break.slice),
+                Event::ParagraphBreak(line_break) => panic!("unexpected {line_break:?}"),
+                Event::Diagnostic(diagnostic) => panic!("unexpected {diagnostic:?}"),
+            }
+        }
+        assert_eq!(source, SAMPLE);
+    }
+
+    #[test]
+    fn line_endings() {
+        const LF: &str = "@bookmark{greet}Hello!\n\n@choice{bye} Bye! \n@wave\n";
+        const CRLF: &str = "@bookmark{greet}Hello!\r\n\r\n@choice{bye} Bye! \r\n@wave\r\n";
+        const CR: &str = "@bookmark{greet}Hello!\r\r@choice{bye} Bye! \r@wave\r";
+        let lf: Vec<_> = Iter::new(LF).collect();
+        for sample in [CRLF, CR] {
+            let events: Vec<_> = Iter::new(sample).collect();
+            assert_eq!(events.len(), lf.len());
+            for (event, lf_event) in events.iter().zip(&lf) {
+                match (event, lf_event) {
+                    (
+                        Event::Text { content: text, .. },
+                        Event::Text {
+                            content: lf_text, ..
+                        },
+                    ) => {
+                        assert_eq!(text.slice, lf_text.slice);
+                        assert_eq!(&sample[text.range.clone()], text.slice);
+                    }
+                    (
+                        Event::Signal(Signal::Call { prompt, param, .. }),
+                        Event::Signal(Signal::Call {
+                            prompt: lf_prompt,
+                            param: lf_param,
+                            ..
+                        }),
+                    ) => {
+                        assert_eq!(prompt.slice, lf_prompt.slice);
+                        assert_eq!(param.slice, lf_param.slice);
+                        assert_eq!(&sample[param.range.clone()], param.slice);
+                    }
+                    (
+                        Event::Signal(Signal::Prompt { prompt, .. }),
+                        Event::Signal(Signal::Prompt {
+                            prompt: lf_prompt, ..
+                        }),
+                    ) => {
+                        assert_eq!(prompt.slice, lf_prompt.slice);
+                        assert_eq!(&sample[prompt.range.clone()], prompt.slice);
+                    }
+                    (Event::Break(line_break), Event::Break(_))
+                    | (Event::ParagraphBreak(line_break), Event::ParagraphBreak(_)) => {
+                        assert_eq!(&sample[line_break.range.clone()], line_break.slice);
+                    }
+                    _ => panic!("expected {lf_event:?}, got {event:?}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_params() {
+        const SAMPLE: &str = "@x{}@{} tail";
         let mut iter = Iter::new(SAMPLE);
         let event = iter.next().unwrap();
         assert!(
             matches!(
                 event,
-                Event::Text(StrRange {
-                    slice: "- Hello!",
+                Event::Signal(Signal::Call {
+                    prompt: StrRange { slice: "x", .. },
+                    param: StrRange { slice: "", .. },
                     ..
                 })
             ),
@@ -132,19 +822,9 @@ mod tests {
         assert!(
             matches!(
                 event,
-                Event::Signal(Signal::Prompt(StrRange { slice: "wave", .. }))
-            ),
-            "{event:?}"
-        );
-        let event = iter.next().unwrap();
-        assert!(matches!(event, Event::Break), "{event:?}");
-        let event = iter.next().unwrap();
-        assert!(
-            matches!(
-                event,
-                Event::Signal(Signal::Call {
-                    prompt: StrRange { slice: "c", .. },
-                    param: StrRange { slice: "1", .. },
+                Event::Signal(Signal::Param {
+                    param: StrRange { slice: "", .. },
+                    ..
                 })
             ),
             "{event:?}"
@@ -153,51 +833,492 @@ mod tests {
         assert!(
             matches!(
                 event,
-                Event::Signal(Signal::Param(StrRange { slice: "i<4", .. }))
+                Event::Text {
+                    content: StrRange { slice: "tail", .. },
+                    ..
+                }
             ),
             "{event:?}"
         );
-        let event = iter.next().unwrap();
-        assert!(
-            matches!(event, Event::Text(StrRange { slice: "- Hi!", .. })),
-            "{event:?}"
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn unclosed_params() {
+        const SAMPLE: &str = "@bookmark{intro\n@choice{end}Bye @{oops";
+        let events: Vec<_> = Iter::new(SAMPLE).collect();
+        let Event::Diagnostic(Diagnostic::UnclosedParam { bracket }) = &events[1] else {
+            panic!("expected diagnostic, got {:?}", events[1]);
+        };
+        assert_eq!(bracket.range, 9..10);
+        assert!(matches!(events[2], Event::Break(_)), "{:?}", events[2]);
+        let Event::Signal(Signal::Param { param, .. }) = &events[5] else {
+            panic!("expected param, got {:?}", events[5]);
+        };
+        assert_eq!(param.slice, "oops");
+        let Event::Diagnostic(Diagnostic::UnclosedParam { bracket }) = &events[6] else {
+            panic!("expected diagnostic, got {:?}", events[6]);
+        };
+        assert_eq!(&SAMPLE[bracket.range.clone()], "{");
+        assert_eq!(events.len(), 7);
+        assert!(!Iter::new("@bookmark{intro}@{x} @y{(}")
+            .any(|event| matches!(event, Event::Diagnostic(_))));
+    }
+
+    #[test]
+    fn comments() {
+        const SAMPLE: &str = "Hello, @--{ note } world! @-- to self\n@-- whole line\n@wave @--{x}";
+        let events: Vec<_> = Iter::new(SAMPLE).collect();
+        assert_eq!(
+            events,
+            [
+                Event::Text {
+                    content: StrRange {
+                        slice: "Hello,",
+                        range: 0..6
+                    },
+                    leading: Spacing::None
+                },
+                Event::Text {
+                    content: StrRange {
+                        slice: " world!",
+                        range: 18..25
+                    },
+                    leading: Spacing::None
+                },
+                Event::Break(StrRange {
+                    slice: "\n",
+                    range: 37..38
+                }),
+                Event::Break(StrRange {
+                    slice: "\n",
+                    range: 52..53
+                }),
+                Event::Signal(Signal::Prompt {
+                    prompt: StrRange {
+                        slice: "wave",
+                        range: 54..58
+                    },
+                    span: 53..58,
+                }),
+            ]
         );
-        let event = iter.next().unwrap();
-        assert!(matches!(event, Event::Break), "{event:?}");
-        let event = iter.next().unwrap();
-        assert!(
-            matches!(
-                event,
-                Event::Signal(Signal::Call {
-                    prompt: StrRange { slice: "c", .. },
-                    param: StrRange { slice: "2", .. },
-                })
-            ),
-            "{event:?}"
+    }
+
+    #[test]
+    fn double_ended() {
+        const SAMPLE: &str =
+            "@bookmark{greet} Hello! @@ @wave\r\n\n@choice{bye}Bye @{oops\n@-- note\nEnd.\n";
+        let forward: Vec<_> = Iter::new(SAMPLE).collect();
+        let mut backward: Vec<_> = Iter::new(SAMPLE).rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        for split in 0..=forward.len() {
+            let mut iter = Iter::new(SAMPLE);
+            let mut mixed: Vec<_> = iter.by_ref().take(split).collect();
+            let mut back: Vec<_> = iter.rev().collect();
+            back.reverse();
+            mixed.extend(back);
+            assert_eq!(forward, mixed, "split at {split}");
+        }
+    }
+
+    #[test]
+    fn with_offset() {
+        const SAMPLE: &str = "Skipped.\n@bookmark{greet}Hello!\n@wave{x";
+        let base_offset = SAMPLE.find('\n').unwrap() + 1;
+        let chunk = &SAMPLE[base_offset..];
+        let whole: Vec<_> = Iter::new(SAMPLE).skip(2).collect();
+        let shifted: Vec<_> = Iter::with_offset(chunk, base_offset).collect();
+        assert_eq!(whole, shifted);
+    }
+
+    #[test]
+    fn break_ranges() {
+        const SAMPLE: &str = "a\r\n\rb\n \r\n";
+        fn breaks<'a>(iter: impl Iterator<Item = Event<'a>>) -> Vec<(bool, Range<usize>)> {
+            iter.filter_map(|event| match event {
+                Event::Break(line_break) => Some((false, line_break.range)),
+                Event::ParagraphBreak(line_break) => Some((true, line_break.range)),
+                _ => None,
+            })
+            .collect()
+        }
+        assert_eq!(
+            breaks(Iter::raw(SAMPLE)),
+            [(false, 1..3), (false, 3..4), (false, 5..6), (false, 7..9)]
         );
-        let event = iter.next().unwrap();
-        assert!(
-            matches!(
-                event,
-                Event::Signal(Signal::Param(StrRange { slice: "s>7", .. }))
-            ),
-            "{event:?}"
+        assert_eq!(breaks(Iter::new(SAMPLE)), [(true, 1..4), (true, 5..9)]);
+        let mut back_breaks = breaks(Iter::new(SAMPLE).rev());
+        back_breaks.reverse();
+        assert_eq!(back_breaks, breaks(Iter::new(SAMPLE)));
+    }
+
+    #[test]
+    fn paragraph_breaks() {
+        const SAMPLE: &str = "\nOne\ntwo\n  \n\t\nThree\n\n";
+        let events: Vec<_> = Iter::new(SAMPLE).collect();
+        assert_eq!(
+            events,
+            [
+                Event::Break(StrRange {
+                    slice: "\n",
+                    range: 0..1
+                }),
+                Event::Text {
+                    content: StrRange {
+                        slice: "One",
+                        range: 1..4
+                    },
+                    leading: Spacing::None
+                },
+                Event::Break(StrRange {
+                    slice: "\n",
+                    range: 4..5
+                }),
+                Event::Text {
+                    content: StrRange {
+                        slice: "two",
+                        range: 5..8
+                    },
+                    leading: Spacing::None
+                },
+                Event::ParagraphBreak(StrRange {
+                    slice: "\n  \n\t\n",
+                    range: 8..14
+                }),
+                Event::Text {
+                    content: StrRange {
+                        slice: "Three",
+                        range: 14..19
+                    },
+                    leading: Spacing::None
+                },
+                Event::ParagraphBreak(StrRange {
+                    slice: "\n\n",
+                    range: 19..21
+                }),
+            ]
         );
-        let event = iter.next().unwrap();
-        assert!(
-            matches!(
-                event,
-                Event::Text(StrRange {
-                    slice: "- Howdy!",
-                    ..
-                })
-            ),
-            "{event:?}"
+    }
+
+    #[test]
+    fn signal_spans() {
+        const SAMPLE: &str = "@ @wave @{x} @style{b}
+«@c«1»» @x«unclosed";
+        let config = ParseConfig::new().brackets(&[('{', '}'), ('«', '»')]);
+        let spans: Vec<_> = Iter::with_config(SAMPLE, config)
+            .filter_map(|event| match event {
+                Event::Signal(signal) => Some(&SAMPLE[signal.span()]),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            ["@", "@wave", "@{x}", "@style{b}", "@c«1»", "@x«unclosed"]
         );
-        let event = iter.next().unwrap();
-        assert!(matches!(event, Event::Signal(Signal::Ping)), "{event:?}");
-        let event = iter.next().unwrap();
-        assert!(matches!(event, Event::Break), "{event:?}");
-        assert_eq!(iter.next(), None);
+        let Some(Event::Signal(signal)) = Iter::with_offset("@x{y}", 3).next() else {
+            panic!("expected signal");
+        };
+        assert_eq!(signal.span(), 3..8);
+    }
+
+    #[test]
+    fn unescaped_params() {
+        const SAMPLE: &str = r"@note{see fig. \}3\}} @{a\nb\tc \\ \@ end\}";
+        let params: Vec<_> = Iter::new(SAMPLE)
+            .filter_map(|event| match event {
+                Event::Signal(Signal::Call { param, .. } | Signal::Param { param, .. }) => {
+                    Some(param)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(params[0].unescaped(), "see fig. }3}");
+        assert_eq!(params[1].slice, r"a\nb\tc \\ \@ end\}");
+        assert_eq!(params[1].unescaped(), "a\nb\tc \\ @ end}");
+        let plain = StrRange {
+            slice: "plain",
+            range: 0..5,
+        };
+        assert!(matches!(plain.unescaped(), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn trimmed() {
+        const SAMPLE: &str = "@bookmark{ the  end\t} @{  } @x{ё }";
+        let trimmed: Vec<_> = Iter::new(SAMPLE)
+            .filter_map(|event| match event {
+                Event::Signal(Signal::Call { param, .. } | Signal::Param { param, .. }) => {
+                    Some(param.trimmed())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            trimmed,
+            [
+                StrRange {
+                    slice: "the  end",
+                    range: 11..19
+                },
+                StrRange {
+                    slice: "",
+                    range: 26..26
+                },
+                StrRange {
+                    slice: "ё",
+                    range: 31..33
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn args() {
+        const SAMPLE: &str = r#"@portrait{alice, happy ,left} @x{ "a, b",\,c,, } @y{  } @z{"}"#;
+        let params: Vec<_> = Iter::new(SAMPLE)
+            .filter_map(|event| match event {
+                Event::Signal(Signal::Call { param, .. }) => Some(param),
+                _ => None,
+            })
+            .collect();
+        let args = |index: usize| -> Vec<_> {
+            params[index]
+                .args()
+                .inspect(|arg| assert_eq!(&SAMPLE[arg.range.clone()], arg.slice))
+                .map(|arg| arg.slice)
+                .collect()
+        };
+        assert_eq!(args(0), ["alice", "happy", "left"]);
+        assert_eq!(args(1), ["a, b", r"\,c", "", ""]);
+        assert!(args(2).is_empty());
+        assert_eq!(args(3), [r#"""#]);
+    }
+
+    #[test]
+    fn pairs() {
+        const SAMPLE: &str = r#"@meta{author = Kai, draft, title="a=b, c", "x=y"=1, empty=}"#;
+        let Some(Event::Signal(Signal::Call { param, .. })) = Iter::new(SAMPLE).next() else {
+            panic!("expected call");
+        };
+        let pairs: Vec<_> = param
+            .pairs()
+            .map(|(key, value)| {
+                assert_eq!(&SAMPLE[key.range.clone()], key.slice);
+                if let Some(value) = &value {
+                    assert_eq!(&SAMPLE[value.range.clone()], value.slice);
+                }
+                (key.slice, value.map(|value| value.slice))
+            })
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("author", Some("Kai")),
+                ("draft", None),
+                ("title", Some("a=b, c")),
+                ("x=y", Some("1")),
+                ("empty", Some("")),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_line_end() {
+        const SAMPLE: &str = "@bookmark{greet} Hello!\n@wave{x} Bye @";
+        let without: Vec<_> = Iter::new(SAMPLE).collect();
+        let with = format!("{SAMPLE}\n");
+        let mut with: Vec<_> = Iter::new(&with).collect();
+        assert_eq!(
+            with.pop(),
+            Some(Event::Break(StrRange {
+                slice: "\n",
+                range: SAMPLE.len()..SAMPLE.len() + 1
+            }))
+        );
+        assert_eq!(with, without);
+    }
+
+    #[test]
+    fn leading_spacing() {
+        const SAMPLE: &str = "@wave world @x{y}@{z}glued\n@wave{x}world\nHello @wave world";
+        let leading: Vec<_> = Iter::new(SAMPLE)
+            .filter_map(|event| match event {
+                Event::Text { content, leading } => Some((content.slice, leading)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            leading,
+            [
+                ("world", Spacing::Space),
+                ("glued", Spacing::None),
+                ("world", Spacing::None),
+                ("Hello", Spacing::None),
+                (" world", Spacing::None),
+            ]
+        );
+        assert!(Iter::raw(SAMPLE).all(|event| !matches!(
+            event,
+            Event::Text {
+                leading: Spacing::Space,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn size_hints() {
+        const SAMPLE: &str = "@bookmark{greet} Hi! @@ @{a}{b\r\n\n \n@x\n";
+        let len = Iter::new(SAMPLE).count();
+        for split in 0..=len {
+            let mut iter = Iter::new(SAMPLE);
+            iter.by_ref().take(split).for_each(drop);
+            for remaining in (0..=len - split).rev() {
+                let (lower, upper) = iter.size_hint();
+                assert!(
+                    lower <= remaining && upper.unwrap() >= remaining,
+                    "split at {split}"
+                );
+                iter.next_back();
+            }
+        }
+    }
+
+    #[test]
+    fn kept_indent() {
+        const SAMPLE: &str =
+            "@style{cp}@{}fn main() {\n@{}    if ok {\n@{}        run();\n@{}    }\n@{}}";
+        let config = ParseConfig::new().keep_indent(true);
+        let lines: Vec<_> = Iter::with_config(SAMPLE, config)
+            .filter_map(|event| match event {
+                Event::Text { content, leading } => {
+                    assert_eq!(leading, Spacing::None);
+                    Some(content.slice)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            ["fn main() {", "    if ok {", "        run();", "    }", "}"]
+        );
+        let trimmed = Iter::new(SAMPLE).filter_map(|event| match event {
+            Event::Text { content, .. } => Some(content.slice),
+            _ => None,
+        });
+        assert!(trimmed.eq(["fn main() {", "if ok {", "run();", "}", "}"]));
+    }
+
+    #[test]
+    fn sub_slices() {
+        const SAMPLE: &str = "Say @portrait{ёлка happy} now";
+        let Some(Event::Signal(Signal::Call { param, .. })) = Iter::new(SAMPLE).nth(1) else {
+            panic!("expected call");
+        };
+        let check = |str_range: Option<StrRange>, expected: &str| {
+            let str_range = str_range.expect(expected);
+            assert_eq!(str_range.slice, expected);
+            assert_eq!(&SAMPLE[str_range.range], expected);
+        };
+        check(param.slice(0..4), "ёл");
+        check(param.slice(9..14), "happy");
+        assert_eq!(param.slice(0..1), None);
+        assert_eq!(param.slice(9..15), None);
+        let (left, right) = param.split_at(8).expect("split");
+        check(Some(left), "ёлка");
+        check(Some(right), " happy");
+        assert_eq!(param.split_at(3), None);
+        assert_eq!(param.split_at(15), None);
+        check(param.strip_prefix("ёл"), "ка happy");
+        check(param.strip_suffix("py"), "ёлка hap");
+        assert_eq!(param.strip_prefix("happy"), None);
+        check(param.find("ка"), "ка");
+        check(param.find(""), "");
+        assert_eq!(param.find("sad"), None);
+    }
+
+    #[test]
+    fn limit_diagnostics() {
+        const SAMPLE: &str = "@bookmark{greeting} Hi!\n@a @b @c";
+        let limits = ParseLimits {
+            max_param_len: 5,
+            max_signals_per_line: 2,
+            ..ParseLimits::new()
+        };
+        let config = ParseConfig::new().limits(limits);
+        let events: Vec<_> = Iter::with_config(SAMPLE, config).collect();
+        let diagnostics: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Diagnostic(Diagnostic::LimitExceeded { limit, at }) => {
+                    Some((*limit, at.range.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            diagnostics,
+            [(Limit::ParamLen, 15..15), (Limit::SignalsPerLine, 30..30)]
+        );
+        let mut backward: Vec<_> = Iter::with_config(SAMPLE, config).rev().collect();
+        backward.reverse();
+        assert_eq!(events, backward);
+        assert_eq!(Iter::new(SAMPLE).count(), 6);
+    }
+
+    #[test]
+    fn abs_rel() {
+        const SAMPLE: &str = "@bookmark{ x }   Привет, @name!  \n  ёж";
+        for event in Iter::new(SAMPLE) {
+            let Event::Text { content, .. } = event else {
+                continue;
+            };
+            for (relative, _) in content.slice.char_indices() {
+                let absolute = content.abs(relative);
+                assert!(SAMPLE[absolute..].starts_with(&content.slice[relative..]));
+                assert_eq!(content.rel(absolute), Some(relative));
+            }
+            assert_eq!(content.abs(content.slice.len()), content.range.end);
+            assert_eq!(content.rel(content.range.end + 1), None);
+            assert_eq!(content.rel(content.range.start.wrapping_sub(1)), None);
+        }
+        let text = Iter::new(SAMPLE)
+            .find_map(|event| match event {
+                Event::Text { content, .. } => Some(content),
+                _ => None,
+            })
+            .expect("text");
+        assert_eq!(text.slice, "Привет,");
+        assert_eq!(text.rel(text.range.start + 1), None);
+        assert!(std::panic::catch_unwind(|| text.abs(1)).is_err());
+    }
+
+    #[test]
+    fn seek() {
+        const SAMPLE: &str = "Hi @wave{ok}\r\n\n  \r\n@x{ё} end\r@{oops\nЖ @@ @-- note\n";
+        for base_offset in [0, 5] {
+            let events: Vec<_> = Iter::with_offset(SAMPLE, base_offset).collect();
+            for offset in 0..=SAMPLE.len() + base_offset + 1 {
+                let mut iter = Iter::with_offset(SAMPLE, base_offset);
+                iter.seek(offset);
+                let expected = events.iter().filter(|event| event.start() >= offset);
+                assert!(iter.eq(expected.cloned()), "{offset}");
+            }
+        }
+        // lines taken from the back are not seeked past
+        let events: Vec<_> = Iter::new(SAMPLE).collect();
+        for offset in 0..=SAMPLE.len() {
+            let mut iter = Iter::new(SAMPLE);
+            let (Some(_), Some(_)) = (iter.next_back(), iter.next_back()) else {
+                panic!("expected events");
+            };
+            iter.seek(offset);
+            let expected = events[..events.len() - 2]
+                .iter()
+                .filter(|event| event.start() >= offset);
+            assert!(iter.eq(expected.cloned()), "{offset}");
+        }
     }
 }
@@ -1,44 +1,242 @@
-use super::trim;
-use ::core::{iter::FusedIterator, str::Split};
+use super::{trim, ParseConfig};
+use ::core::{iter::FusedIterator, ops};
 
+fn is_line_end(ch: char) -> bool {
+    ch == '\n' || ch == '\r'
+}
+
+/// Splits text into lines terminated by either of `\n`, `\r\n` or lone `\r`.
+/// When trimming, whitespace-only lines in between other lines are skipped,
+/// with their terminators joining the terminator before the next line.
 #[derive(Clone, Debug)]
 pub(super) struct Iter<'a> {
-    lines: Split<'a, char>,
-    offset: usize,
+    text: &'a str,
+    start: usize,
+    end: usize,
+    finished: bool,
+    config: ParseConfig,
 }
 
 impl<'a> Iter<'a> {
-    pub fn new(text: &'a str) -> Self {
+    pub fn with_config(text: &'a str, config: ParseConfig) -> Self {
         Self {
-            lines: text.split('\n'),
-            offset: 0,
+            text,
+            start: 0,
+            end: text.len(),
+            finished: false,
+            config,
+        }
+    }
+
+    fn terminator_len_before(&self, line_start: usize) -> usize {
+        let before = &self.text[..line_start];
+        if before.ends_with("\r\n") {
+            2
+        } else {
+            usize::from(before.ends_with(is_line_end))
+        }
+    }
+
+    fn is_skipped(&self, line: ops::Range<usize>) -> bool {
+        self.config.trim
+            && line.start != 0
+            && line.end != self.text.len()
+            && self.text[line].chars().all(char::is_whitespace)
+    }
+
+    /// Terminators of the previous lines, which is empty for the first line
+    fn line_break_before(&self, line_start: usize) -> &'a str {
+        let mut start = line_start - self.terminator_len_before(line_start);
+        if start == line_start {
+            return "";
+        }
+        loop {
+            let prev_start = self.text[..start]
+                .rfind(is_line_end)
+                .map_or(0, |index| index + 1);
+            if !self.is_skipped(prev_start..start) {
+                return &self.text[start..line_start];
+            }
+            start = prev_start - self.terminator_len_before(prev_start);
+        }
+    }
+
+    fn item(&self, line: ops::Range<usize>) -> <Self as Iterator>::Item {
+        (
+            self.line_break_before(line.start),
+            line.start,
+            trim::Iter::with_config(&self.text[line], self.config),
+        )
+    }
+
+    /// Byte-index in text up to which lines were taken from the front, terminators included,
+    /// which never exceeds text length
+    pub fn offset(&self) -> usize {
+        self.start
+    }
+
+    /// Byte-length of the text that is yet to be split into lines, terminators included
+    pub fn remaining_len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Jumps to the start of the line that `offset` is on, without splitting
+    /// the lines before it one by one, unless lines past it were taken already
+    pub fn seek(&mut self, offset: usize) {
+        if self.finished || offset <= self.start {
+            return;
+        }
+        let offset = offset.min(self.end);
+        let Some(index) = self.text.as_bytes()[self.start..offset]
+            .iter()
+            .rposition(|byte| is_line_end(char::from(*byte)))
+        else {
+            return;
+        };
+        let mut line_start = self.start + index + 1;
+        // `offset` may be right in between `\r` and `\n`
+        if self.text[..line_start].ends_with('\r') && self.text[line_start..].starts_with('\n') {
+            line_start += 1;
+        }
+        self.start = line_start;
+    }
+
+    fn next_line(&mut self) -> Option<ops::Range<usize>> {
+        if self.finished {
+            return None;
         }
+        let start = self.start;
+        let Some(index) = self.text[start..self.end].find(is_line_end) else {
+            // the last line has no terminator to step over
+            self.finished = true;
+            self.start = self.end;
+            return Some(start..self.end);
+        };
+        let line_end = start + index;
+        self.start = if self.text[line_end..self.end].starts_with("\r\n") {
+            line_end + 2
+        } else {
+            line_end + 1
+        };
+        Some(start..line_end)
     }
 
-    pub(crate) fn offset(&self) -> usize {
-        self.offset
+    fn next_back_line(&mut self) -> Option<ops::Range<usize>> {
+        if self.finished {
+            return None;
+        }
+        let end = self.end;
+        let Some(index) = self.text[self.start..end].rfind(is_line_end) else {
+            self.finished = true;
+            self.end = self.start;
+            return Some(self.start..end);
+        };
+        let line_start = self.start + index + 1;
+        self.end = line_start - self.terminator_len_before(line_start);
+        Some(line_start..end)
     }
 }
 
 impl<'a> Iterator for Iter<'a> {
-    type Item = trim::Iter<'a>;
+    /// Terminators of the previous lines, which is empty for the first line,
+    /// then line start offset and the line itself
+    type Item = (&'a str, usize, trim::Iter<'a>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let next = self.lines.next()?;
-        // One added for new-line char
-        self.offset += next.len() + 1;
-        Some(trim::Iter::new(next))
+        loop {
+            let line = self.next_line()?;
+            if !self.is_skipped(line.clone()) {
+                return Some(self.item(line));
+            }
+        }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.lines.size_hint()
+        if self.finished {
+            (0, Some(0))
+        } else {
+            (0, Some(self.end - self.offset() + 1))
+        }
     }
 }
 
 impl<'a> DoubleEndedIterator for Iter<'a> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        self.lines.next_back().map(trim::Iter::new)
+        loop {
+            let line = self.next_back_line()?;
+            if !self.is_skipped(line.clone()) {
+                return Some(self.item(line));
+            }
+        }
     }
 }
 
 impl<'a> FusedIterator for Iter<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Iter, ParseConfig};
+
+    fn lines(iter: impl Iterator<Item = (&'static str, usize)>) -> Vec<(&'static str, usize)> {
+        iter.collect()
+    }
+
+    #[test]
+    fn mixed_ends() {
+        const SAMPLE: &str = "One\r\ntwo\n \n\rthree\n\nfour\rfive\n";
+        let config = ParseConfig::new();
+        let strip = |(line_break, start, _)| (line_break, start);
+        let forward = lines(Iter::with_config(SAMPLE, config).map(strip));
+        assert_eq!(
+            forward,
+            [
+                ("", 0),
+                ("\r\n", 5),
+                ("\n \n\r", 12),
+                ("\n\n", 19),
+                ("\r", 24),
+                ("\n", 29),
+            ]
+        );
+        let mut backward = lines(Iter::with_config(SAMPLE, config).rev().map(strip));
+        backward.reverse();
+        assert_eq!(forward, backward);
+        for split in 0..=forward.len() {
+            let mut iter = Iter::with_config(SAMPLE, config).map(strip);
+            let mut mixed = lines(iter.by_ref().take(split));
+            let mut back = lines(iter.rev());
+            back.reverse();
+            mixed.extend(back);
+            assert_eq!(forward, mixed, "split at {split}");
+        }
+        let mut iter = Iter::with_config(SAMPLE, config).map(strip);
+        assert_eq!(iter.next_back(), Some(("\n", 29)));
+        assert_eq!(iter.next(), Some(("", 0)));
+        assert_eq!(iter.next_back(), Some(("\r", 24)));
+        assert_eq!(iter.next(), Some(("\r\n", 5)));
+        assert_eq!(iter.next_back(), Some(("\n\n", 19)));
+        assert_eq!(iter.next(), Some(("\n \n\r", 12)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn offsets() {
+        for (sample, offsets) in [
+            ("", &[0][..]),
+            ("One", &[3]),
+            ("One\n", &[4, 4]),
+            ("One\r\ntwo", &[5, 8]),
+            ("One\r\ntwo\r", &[5, 9, 9]),
+        ] {
+            let mut iter = Iter::with_config(sample, ParseConfig::new());
+            assert_eq!(iter.offset(), 0);
+            for &offset in offsets {
+                assert!(iter.next().is_some());
+                assert_eq!(iter.offset(), offset, "{sample:?}");
+            }
+            assert!(iter.next().is_none());
+            assert_eq!(iter.offset(), sample.len());
+        }
+    }
+}
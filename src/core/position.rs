@@ -0,0 +1,81 @@
+use ::core::iter;
+use alloc::vec::Vec;
+
+/// Byte offsets of line starts in a text, turning byte offsets into lines and columns.
+/// Lines are terminated the same way as for parsing, by `\n`, `\r\n` or lone `\r`.
+#[derive(Clone, Debug)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    #[must_use]
+    pub fn new(text: &'a str) -> Self {
+        let bytes = text.as_bytes();
+        let starts = iter::once(0)
+            .chain(bytes.iter().enumerate().filter_map(|(index, byte)| {
+                let is_line_end = *byte == b'\n'
+                    || (*byte == b'\r' && bytes.get(index + 1).copied() != Some(b'\n'));
+                is_line_end.then_some(index + 1)
+            }))
+            .collect();
+        Self { text, starts }
+    }
+
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Zero-based line and column of the byte offset, where column is counted in chars.
+    /// Offsets past the end of text are clamped to its length.
+    #[must_use]
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.text.len());
+        let line = self.starts.partition_point(|start| *start <= offset) - 1;
+        let column = self.text.as_bytes()[self.starts[line]..offset]
+            .iter()
+            .filter(|byte| !is_continuation_byte(**byte))
+            .count();
+        (line, column)
+    }
+}
+
+const fn is_continuation_byte(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineIndex;
+    use crate::core::Iter;
+
+    #[test]
+    fn positions() {
+        const SAMPLE: &str = "Hello!\r\n– Привет, @wave\rBye\n\n@end";
+        let index = LineIndex::new(SAMPLE);
+        assert_eq!(index.line_count(), 5);
+        assert_eq!(index.position(0), (0, 0));
+        assert_eq!(index.position(5), (0, 5));
+        assert_eq!(index.position(6), (0, 6));
+        assert_eq!(index.position(8), (1, 0));
+        assert_eq!(index.position(SAMPLE.find("wave").unwrap()), (1, 11));
+        assert_eq!(index.position(SAMPLE.find("Bye").unwrap()), (2, 0));
+        assert_eq!(index.position(SAMPLE.find("end").unwrap()), (4, 1));
+        assert_eq!(index.position(SAMPLE.len() + 10), (4, 4));
+    }
+
+    #[test]
+    fn str_range_positions() {
+        const SAMPLE: &str = "@bookmark{greet}\r\n  Hello, @wave{😀}!";
+        let index = LineIndex::new(SAMPLE);
+        let positions: Vec<_> = Iter::new(SAMPLE)
+            .filter_map(|event| match event {
+                crate::core::Event::Text { content, .. } => Some(content.position_in(&index)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(positions, [(1, 0), (1, 17)]);
+    }
+}
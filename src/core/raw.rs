@@ -1,42 +1,73 @@
+use super::{Limit, ParseConfig};
 use ::core::{
     iter::{FusedIterator, Peekable},
     str::CharIndices,
 };
+use alloc::vec::Vec;
 
-const SIGNAL_CHAR: char = '@';
-const LEFT_BRACKET_CHARS: [char; 3] = ['{', '[', '('];
-const RIGHT_BRACKET_CHARS: [char; 3] = ['}', ']', ')'];
+pub(super) const SIGNAL_CHAR: char = '@';
+pub(super) const PARAM_ESCAPE_CHAR: char = '\\';
+const COMMENT_PREFIX: &str = "--";
 
 use ::core::ops;
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 pub(super) enum Range {
     Text(ops::Range<usize>),
+    /// Doubled `@@`, standing for a literal `@` in text
+    Escape(ops::Range<usize>),
+    /// Either `@--` with the rest of the line or `@--` followed by a param, as a whole
+    Comment(ops::Range<usize>),
     Signal {
+        /// Whole signal, from the `@`-char up to and including the right bracket, if any
+        span: ops::Range<usize>,
         prompt: ops::Range<usize>,
-        param: ops::Range<usize>,
+        /// Absent when there are no brackets, present and possibly empty otherwise
+        param: Option<ops::Range<usize>>,
+        /// Params right after the first one, which only a prompted signal may have
+        extra_params: Vec<ops::Range<usize>>,
+        /// Left bracket of a param that runs until the end without being closed
+        unclosed: Option<ops::Range<usize>>,
+    },
+    /// Follows the construct that is cut short at `at`
+    LimitExceeded {
+        limit: Limit,
+        at: usize,
     },
 }
 
 impl Range {
     const fn empty_signal(index: usize) -> Self {
         Self::Signal {
+            span: index..index + SIGNAL_CHAR.len_utf8(),
             prompt: index..index,
-            param: index..index,
+            param: None,
+            extra_params: Vec::new(),
+            unclosed: None,
         }
     }
 
-    const fn nameless_signal(param_range: ops::Range<usize>) -> Self {
+    const fn nameless_signal(
+        span: ops::Range<usize>,
+        param_range: ops::Range<usize>,
+        unclosed: Option<ops::Range<usize>>,
+    ) -> Self {
         Self::Signal {
+            span,
             prompt: param_range.start..param_range.start,
-            param: param_range,
+            param: Some(param_range),
+            extra_params: Vec::new(),
+            unclosed,
         }
     }
 
-    const fn paramless_signal(name_range: ops::Range<usize>) -> Self {
+    const fn paramless_signal(signal_index: usize, name_range: ops::Range<usize>) -> Self {
         Self::Signal {
-            param: name_range.end..name_range.end,
+            span: signal_index..name_range.end,
             prompt: name_range,
+            param: None,
+            extra_params: Vec::new(),
+            unclosed: None,
         }
     }
 }
@@ -45,74 +76,206 @@ impl Range {
 pub(super) struct Iter<'a> {
     indices: Peekable<CharIndices<'a>>,
     text: &'a str,
+    config: ParseConfig,
+    signal_count: usize,
+    limit_exceeded: Option<(Limit, usize)>,
 }
 
 impl<'a> Iter<'a> {
+    #[cfg(test)]
     pub fn new(text: &'a str) -> Self {
+        Self::with_config(text, ParseConfig::new())
+    }
+
+    pub fn with_config(text: &'a str, config: ParseConfig) -> Self {
         Self {
             indices: text.char_indices().peekable(),
             text,
+            config,
+            signal_count: 0,
+            limit_exceeded: None,
         }
     }
 
-    pub fn as_full_str(&self) -> &'a str {
-        self.text
+    /// Index of the next char, or the text length once everything is consumed
+    fn position(&mut self) -> usize {
+        self.indices
+            .peek()
+            .map_or(self.text.len(), |(index, _)| *index)
     }
-}
 
-impl<'a> Iterator for Iter<'a> {
-    type Item = Range;
+    fn left_bracket_index(&self, ch: char) -> Option<usize> {
+        self.config
+            .brackets
+            .iter()
+            .position(|(left, _)| *left == ch)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Whether a param from `start` would exceed its limit by including `ch` at `index`,
+    /// in which case the limit is reported after the signal
+    fn exceeds_param_len(&mut self, start: usize, index: usize, ch: char) -> bool {
+        let exceeds = index + ch.len_utf8() - start > self.config.limits.max_param_len;
+        if exceeds {
+            self.limit_exceeded = Some((Limit::ParamLen, index));
+        }
+        exceeds
+    }
+
+    /// Consumes param up to the right bracket balancing already consumed left one.
+    /// Only brackets of the same kind are counted, unbalanced param runs until the end,
+    /// in which case the range of the left bracket is returned too.
+    /// A char following a backslash is never counted as a bracket.
+    /// If the left bracket is doubled, the param is verbatim instead.
+    /// A param exceeding its limit ends right there, as if it was closed.
+    fn param(
+        &mut self,
+        bracket_index: usize,
+        bracket_start: usize,
+    ) -> (ops::Range<usize>, Option<ops::Range<usize>>) {
+        let (left, right) = self.config.brackets[bracket_index];
+        if self.indices.next_if(|(_, ch)| *ch == left).is_some() {
+            return self.verbatim_param(right, bracket_start);
+        }
+        let start = self.position();
+        let mut depth = 0_usize;
+        let mut is_escaped = false;
+        while let Some((index, ch)) = self.indices.peek().copied() {
+            let is_closing = !is_escaped && ch == right && depth == 0;
+            if !is_closing && self.exceeds_param_len(start, index, ch) {
+                return (start..index, None);
+            }
+            self.indices.next();
+            if is_escaped {
+                is_escaped = false;
+            } else if is_closing {
+                return (start..index, None);
+            } else if ch == PARAM_ESCAPE_CHAR {
+                is_escaped = true;
+            } else if ch == left {
+                depth += 1;
+            } else if ch == right {
+                depth -= 1;
+            }
+        }
+        (start..self.text.len(), Some(bracket_start..start))
+    }
+
+    /// Consumes param after a doubled left bracket up to a doubled `right` bracket,
+    /// without counting brackets or escapes in between
+    fn verbatim_param(
+        &mut self,
+        right: char,
+        bracket_start: usize,
+    ) -> (ops::Range<usize>, Option<ops::Range<usize>>) {
+        let start = self.position();
+        while let Some((index, ch)) = self.indices.peek().copied() {
+            let mut rest = self.text[index..].chars();
+            let is_closing = rest.next() == Some(right) && rest.next() == Some(right);
+            if !is_closing && self.exceeds_param_len(start, index, ch) {
+                return (start..index, None);
+            }
+            self.indices.next();
+            if is_closing {
+                self.indices.next();
+                return (start..index, None);
+            }
+        }
+        (start..self.text.len(), Some(bracket_start..start))
+    }
+
+    /// Consumes params that immediately follow a closed one, until there is
+    /// either no left bracket next, an unclosed param or one exceeding its limit
+    fn extra_params(&mut self, unclosed: &mut Option<ops::Range<usize>>) -> Vec<ops::Range<usize>> {
+        let mut extra_params = Vec::new();
+        while unclosed.is_none() && self.limit_exceeded.is_none() {
+            let Some((bracket_start, bracket_ch)) = self.indices.peek().copied() else {
+                break;
+            };
+            let Some(bracket_index) = self.left_bracket_index(bracket_ch) else {
+                break;
+            };
+            self.indices.next();
+            let (param, param_unclosed) = self.param(bracket_index, bracket_start);
+            extra_params.push(param);
+            *unclosed = param_unclosed;
+        }
+        extra_params
+    }
+
+    /// Consumes comment that goes after the signal char
+    fn comment(&mut self, signal_index: usize) -> Range {
+        for _ in COMMENT_PREFIX.chars() {
+            self.indices.next();
+        }
+        if let Some((bracket_start, bracket_ch)) = self.indices.peek().copied() {
+            if let Some(bracket_index) = self.left_bracket_index(bracket_ch) {
+                self.indices.next();
+                self.param(bracket_index, bracket_start);
+                return Range::Comment(signal_index..self.position());
+            }
+        }
+        while self.indices.next().is_some() {}
+        Range::Comment(signal_index..self.text.len())
+    }
+}
+
+impl<'a> Iter<'a> {
+    /// Next range, regardless of how many signals there are already
+    fn range(&mut self) -> Option<Range> {
         let (maybe_signal_index, maybe_signal_ch) = self.indices.next()?;
         if maybe_signal_ch == SIGNAL_CHAR {
             let Some((first_signal_index, first_signal_ch)) = self.indices.peek().copied() else {
                 return Some(Range::empty_signal(maybe_signal_index));
             };
-            if first_signal_ch.is_whitespace() {
+            if first_signal_ch == SIGNAL_CHAR {
+                self.indices.next();
+                return Some(Range::Escape(
+                    maybe_signal_index..first_signal_index + SIGNAL_CHAR.len_utf8(),
+                ));
+            } else if first_signal_ch.is_whitespace() {
                 return Some(Range::empty_signal(maybe_signal_index));
-            } else if let Some(bracket_index) = LEFT_BRACKET_CHARS
-                .iter()
-                .position(|ch| *ch == first_signal_ch)
-            {
+            } else if self.text[first_signal_index..].starts_with(COMMENT_PREFIX) {
+                return Some(self.comment(maybe_signal_index));
+            } else if let Some(bracket_index) = self.left_bracket_index(first_signal_ch) {
                 self.indices.next();
-                let Some((param_start, _)) = self.indices.next() else {
-                    return Some(Range::empty_signal(maybe_signal_index));
-                };
-                for (param_index, param_ch) in &mut self.indices {
-                    if param_ch == RIGHT_BRACKET_CHARS[bracket_index] {
-                        return Some(Range::nameless_signal(param_start..param_index));
-                    }
-                }
-                return Some(Range::nameless_signal(param_start..self.text.len()));
+                let (param, unclosed) = self.param(bracket_index, first_signal_index);
+                let span = maybe_signal_index..self.position();
+                return Some(Range::nameless_signal(span, param, unclosed));
             }
             self.indices.next();
             while let Some((name_index, name_ch)) = self.indices.peek().copied() {
-                if name_ch.is_whitespace() {
-                    return Some(Range::paramless_signal(first_signal_index..name_index));
-                } else if let Some(bracket_index) =
-                    LEFT_BRACKET_CHARS.iter().position(|ch| *ch == name_ch)
-                {
+                if name_ch.is_whitespace() || name_ch == SIGNAL_CHAR {
+                    return Some(Range::paramless_signal(
+                        maybe_signal_index,
+                        first_signal_index..name_index,
+                    ));
+                } else if let Some(bracket_index) = self.left_bracket_index(name_ch) {
                     self.indices.next();
-                    let Some((param_start, _)) = self.indices.next() else {
-                        return Some(Range::paramless_signal(first_signal_index..name_index));
-                    };
-                    for (param_index, param_ch) in &mut self.indices {
-                        if param_ch == RIGHT_BRACKET_CHARS[bracket_index] {
-                            return Some(Range::Signal {
-                                prompt: first_signal_index..name_index,
-                                param: param_start..param_index,
-                            });
-                        }
-                    }
+                    let (param, mut unclosed) = self.param(bracket_index, name_index);
+                    let extra_params = self.extra_params(&mut unclosed);
                     return Some(Range::Signal {
+                        span: maybe_signal_index..self.position(),
                         prompt: first_signal_index..name_index,
-                        param: param_start..self.text.len(),
+                        param: Some(param),
+                        extra_params,
+                        unclosed,
                     });
                 }
+                if name_index + name_ch.len_utf8() - first_signal_index
+                    > self.config.limits.max_prompt_len
+                {
+                    self.limit_exceeded = Some((Limit::PromptLen, name_index));
+                    return Some(Range::paramless_signal(
+                        maybe_signal_index,
+                        first_signal_index..name_index,
+                    ));
+                }
                 self.indices.next();
             }
-            return Some(Range::paramless_signal(first_signal_index..self.text.len()));
+            return Some(Range::paramless_signal(
+                maybe_signal_index,
+                first_signal_index..self.text.len(),
+            ));
         }
         while let Some((text_index, text_ch)) = self.indices.peek().copied() {
             if text_ch == SIGNAL_CHAR {
@@ -123,10 +286,38 @@ impl<'a> Iterator for Iter<'a> {
         self.indices.next();
         Some(Range::Text(maybe_signal_index..self.text.len()))
     }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Range;
 
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((limit, at)) = self.limit_exceeded.take() {
+            return Some(Range::LimitExceeded { limit, at });
+        }
+        let (index, ch) = self.indices.peek().copied()?;
+        if ch == SIGNAL_CHAR && self.signal_count == self.config.limits.max_signals_per_line {
+            while self.indices.next().is_some() {}
+            self.limit_exceeded = Some((Limit::SignalsPerLine, index));
+            return Some(Range::Text(index..self.text.len()));
+        }
+        let range = self.range()?;
+        if let Range::Signal { .. } | Range::Comment(_) = range {
+            self.signal_count += 1;
+        }
+        Some(range)
+    }
+
+    /// There is a range for at least one remaining char, and at most for every one of them,
+    /// unless the last one exceeded a limit
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.indices.size_hint()
+        let limit_exceeded = usize::from(self.limit_exceeded.is_some());
+        let (lower, upper) = self.indices.size_hint();
+        (
+            lower.min(1) + limit_exceeded,
+            upper.map(|upper| upper + limit_exceeded),
+        )
     }
 }
 
@@ -134,7 +325,8 @@ impl<'a> FusedIterator for Iter<'a> {}
 
 #[cfg(test)]
 mod tests {
-    use super::{Iter, Range};
+    use super::{Iter, ParseConfig, Range};
+    use crate::core::ParseLimits;
 
     #[test]
     fn just_text() {
@@ -161,12 +353,13 @@ mod tests {
         let Range::Signal {
             prompt: name,
             param,
+            ..
         } = &range_event1
         else {
             panic!("expected signal range, got {range_event1:?}");
         };
         assert!(name.is_empty());
-        assert!(param.is_empty());
+        assert!(param.is_none());
         let range_event2 = iter.next().expect("third range event");
         let Range::Text(range2) = &range_event2 else {
             panic!("expected text range, got {range_event2:?}");
@@ -176,12 +369,13 @@ mod tests {
         let Range::Signal {
             prompt: name,
             param,
+            ..
         } = &range_event3
         else {
             panic!("expected signal range, got {range_event3:?}");
         };
         assert!(name.is_empty());
-        assert!(param.is_empty());
+        assert!(param.is_none());
         assert_eq!(iter.next(), None);
     }
 
@@ -193,12 +387,13 @@ mod tests {
         let Range::Signal {
             prompt: name,
             param,
+            ..
         } = &range_event0
         else {
             panic!("expected signal range, got {range_event0:?}");
         };
         assert_eq!(&SAMPLE[name.clone()], "first_signal");
-        assert!(param.is_empty());
+        assert!(param.is_none());
         let range_event1 = iter.next().expect("second range event");
         let Range::Text(range1) = &range_event1 else {
             panic!("expected text range, got {range_event1:?}");
@@ -208,12 +403,13 @@ mod tests {
         let Range::Signal {
             prompt: name,
             param,
+            ..
         } = &range_event2
         else {
             panic!("expected signal range, got {range_event2:?}");
         };
         assert_eq!(&SAMPLE[name.clone()], "second_signal");
-        assert!(param.is_empty());
+        assert!(param.is_none());
         let range_event3 = iter.next().expect("fourth range event");
         let Range::Text(range3) = &range_event3 else {
             panic!("expected text range, got {range_event3:?}");
@@ -235,22 +431,24 @@ mod tests {
         let Range::Signal {
             prompt: name,
             param,
+            ..
         } = &range_event1
         else {
             panic!("expected signal range, got {range_event1:?}");
         };
         assert_eq!(&SAMPLE[name.clone()], "first_signal");
-        assert_eq!(&SAMPLE[param.clone()], " 20 84 ");
+        assert_eq!(&SAMPLE[param.clone().unwrap()], " 20 84 ");
         let range_event2 = iter.next().expect("second range event");
         let Range::Signal {
             prompt: name,
             param,
+            ..
         } = &range_event2
         else {
             panic!("expected signal range, got {range_event2:?}");
         };
         assert_eq!(&SAMPLE[name.clone()], "second_signal");
-        assert_eq!(&SAMPLE[param.clone()], " #e13f3f ");
+        assert_eq!(&SAMPLE[param.clone().unwrap()], " #e13f3f ");
         let range_event3 = iter.next().expect("fourth range event");
         let Range::Text(range3) = &range_event3 else {
             panic!("expected text range, got {range_event3:?}");
@@ -258,4 +456,400 @@ mod tests {
         assert_eq!(&SAMPLE[range3.clone()], " world!");
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn escaped_signals() {
+        const SAMPLE: &str = "@@home, mail@@me @@";
+        let mut iter = Iter::new(SAMPLE);
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Escape(range0) = &range_event0 else {
+            panic!("expected escape range, got {range_event0:?}");
+        };
+        assert_eq!(range0, &(0..2));
+        let range_event1 = iter.next().expect("second range event");
+        let Range::Text(range1) = &range_event1 else {
+            panic!("expected text range, got {range_event1:?}");
+        };
+        assert_eq!(&SAMPLE[range1.clone()], "home, mail");
+        let range_event2 = iter.next().expect("third range event");
+        let Range::Escape(range2) = &range_event2 else {
+            panic!("expected escape range, got {range_event2:?}");
+        };
+        assert_eq!(&SAMPLE[range2.clone()], "@@");
+        let range_event3 = iter.next().expect("fourth range event");
+        let Range::Text(range3) = &range_event3 else {
+            panic!("expected text range, got {range_event3:?}");
+        };
+        assert_eq!(&SAMPLE[range3.clone()], "me ");
+        let range_event4 = iter.next().expect("fifth range event");
+        let Range::Escape(range4) = &range_event4 else {
+            panic!("expected escape range, got {range_event4:?}");
+        };
+        assert_eq!(range4, &(SAMPLE.len() - 2..SAMPLE.len()));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn nested_params() {
+        const SAMPLE: &str = "@bookmark{act{1}}@{a{b{c}}d}@x{a(b)c} tail";
+        let mut iter = Iter::new(SAMPLE);
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Signal { prompt, param, .. } = &range_event0 else {
+            panic!("expected signal range, got {range_event0:?}");
+        };
+        assert_eq!(&SAMPLE[prompt.clone()], "bookmark");
+        assert_eq!(&SAMPLE[param.clone().unwrap()], "act{1}");
+        let range_event1 = iter.next().expect("second range event");
+        let Range::Signal { prompt, param, .. } = &range_event1 else {
+            panic!("expected signal range, got {range_event1:?}");
+        };
+        assert!(prompt.is_empty());
+        assert_eq!(&SAMPLE[param.clone().unwrap()], "a{b{c}}d");
+        let range_event2 = iter.next().expect("third range event");
+        let Range::Signal { prompt, param, .. } = &range_event2 else {
+            panic!("expected signal range, got {range_event2:?}");
+        };
+        assert_eq!(&SAMPLE[prompt.clone()], "x");
+        assert_eq!(&SAMPLE[param.clone().unwrap()], "a(b)c");
+        let range_event3 = iter.next().expect("fourth range event");
+        let Range::Text(range3) = &range_event3 else {
+            panic!("expected text range, got {range_event3:?}");
+        };
+        assert_eq!(&SAMPLE[range3.clone()], " tail");
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn unbalanced_params() {
+        const SAMPLE: &str = "@x{a{b} tail";
+        let mut iter = Iter::new(SAMPLE);
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Signal {
+            span,
+            prompt,
+            param,
+            unclosed,
+            ..
+        } = &range_event0
+        else {
+            panic!("expected signal range, got {range_event0:?}");
+        };
+        assert_eq!(&SAMPLE[span.clone()], SAMPLE);
+        assert_eq!(&SAMPLE[prompt.clone()], "x");
+        assert_eq!(&SAMPLE[param.clone().unwrap()], "a{b} tail");
+        assert_eq!(unclosed, &Some(2..3));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn empty_params() {
+        const SAMPLE: &str = "@x{}@{} tail";
+        let mut iter = Iter::new(SAMPLE);
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Signal { prompt, param, .. } = &range_event0 else {
+            panic!("expected signal range, got {range_event0:?}");
+        };
+        assert_eq!(&SAMPLE[prompt.clone()], "x");
+        assert_eq!(param, &Some(3..3));
+        let range_event1 = iter.next().expect("second range event");
+        let Range::Signal { prompt, param, .. } = &range_event1 else {
+            panic!("expected signal range, got {range_event1:?}");
+        };
+        assert!(prompt.is_empty());
+        assert_eq!(param, &Some(6..6));
+        let range_event2 = iter.next().expect("third range event");
+        let Range::Text(range2) = &range_event2 else {
+            panic!("expected text range, got {range_event2:?}");
+        };
+        assert_eq!(&SAMPLE[range2.clone()], " tail");
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn configured_brackets() {
+        const SAMPLE: &str = "@wave<3 @ping(see below) @x{y}";
+        let mut iter = Iter::with_config(SAMPLE, ParseConfig::new().brackets(&[('{', '}')]));
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Signal { prompt, param, .. } = &range_event0 else {
+            panic!("expected signal range, got {range_event0:?}");
+        };
+        assert_eq!(&SAMPLE[prompt.clone()], "wave<3");
+        assert!(param.is_none());
+        assert!(matches!(iter.next(), Some(Range::Text(_))));
+        let range_event2 = iter.next().expect("third range event");
+        let Range::Signal { prompt, param, .. } = &range_event2 else {
+            panic!("expected signal range, got {range_event2:?}");
+        };
+        assert_eq!(&SAMPLE[prompt.clone()], "ping(see");
+        assert!(param.is_none());
+        let range_event3 = iter.next().expect("fourth range event");
+        let Range::Text(range3) = &range_event3 else {
+            panic!("expected text range, got {range_event3:?}");
+        };
+        assert_eq!(&SAMPLE[range3.clone()], " below) ");
+        let range_event4 = iter.next().expect("fifth range event");
+        let Range::Signal { prompt, param, .. } = &range_event4 else {
+            panic!("expected signal range, got {range_event4:?}");
+        };
+        assert_eq!(&SAMPLE[prompt.clone()], "x");
+        assert_eq!(&SAMPLE[param.clone().unwrap()], "y");
+        assert_eq!(iter.next(), None);
+
+        let mut iter = Iter::with_config(SAMPLE, ParseConfig::new().brackets(&[('<', '>')]));
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Signal { prompt, param, .. } = &range_event0 else {
+            panic!("expected signal range, got {range_event0:?}");
+        };
+        assert_eq!(&SAMPLE[prompt.clone()], "wave");
+        assert_eq!(&SAMPLE[param.clone().unwrap()], "3 @ping(see below) @x{y}");
+    }
+
+    #[test]
+    fn comments() {
+        const SAMPLE: &str = "Hello @--{ note {1} } world @-- rest {of} @line";
+        let mut iter = Iter::new(SAMPLE);
+        assert!(matches!(iter.next(), Some(Range::Text(_))));
+        let range_event1 = iter.next().expect("second range event");
+        let Range::Comment(range1) = &range_event1 else {
+            panic!("expected comment range, got {range_event1:?}");
+        };
+        assert_eq!(&SAMPLE[range1.clone()], "@--{ note {1} }");
+        let range_event2 = iter.next().expect("third range event");
+        let Range::Text(range2) = &range_event2 else {
+            panic!("expected text range, got {range_event2:?}");
+        };
+        assert_eq!(&SAMPLE[range2.clone()], " world ");
+        let range_event3 = iter.next().expect("fourth range event");
+        let Range::Comment(range3) = &range_event3 else {
+            panic!("expected comment range, got {range_event3:?}");
+        };
+        assert_eq!(&SAMPLE[range3.clone()], "@-- rest {of} @line");
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn adjacent_signals() {
+        fn prompts_and_params(text: &str) -> Vec<(&str, Option<&str>)> {
+            Iter::new(text)
+                .map(|range| match range {
+                    Range::Signal { prompt, param, .. } => {
+                        (&text[prompt], param.map(|param| &text[param]))
+                    }
+                    range => panic!("expected signal range, got {range:?}"),
+                })
+                .collect()
+        }
+        assert_eq!(prompts_and_params("@a@b"), [("a", None), ("b", None)]);
+        assert_eq!(
+            prompts_and_params("@a{x}@b"),
+            [("a", Some("x")), ("b", None)]
+        );
+        assert_eq!(prompts_and_params("@a@{x}"), [("a", None), ("", Some("x"))]);
+        assert_eq!(
+            prompts_and_params("@style{b}@{Hi}@wave@bookmark{x}"),
+            [
+                ("style", Some("b")),
+                ("", Some("Hi")),
+                ("wave", None),
+                ("bookmark", Some("x"))
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_brackets() {
+        const SAMPLE: &str = r"@note{see fig. \}3\}} @x{\\}@y(\{\)) tail\";
+        let mut iter = Iter::new(SAMPLE);
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Signal { param, span, .. } = &range_event0 else {
+            panic!("expected signal range, got {range_event0:?}");
+        };
+        assert_eq!(&SAMPLE[param.clone().unwrap()], r"see fig. \}3\}");
+        assert_eq!(&SAMPLE[span.clone()], r"@note{see fig. \}3\}}");
+        assert!(matches!(iter.next(), Some(Range::Text(_))));
+        let range_event2 = iter.next().expect("third range event");
+        let Range::Signal { param, .. } = &range_event2 else {
+            panic!("expected signal range, got {range_event2:?}");
+        };
+        assert_eq!(&SAMPLE[param.clone().unwrap()], r"\\");
+        let range_event3 = iter.next().expect("fourth range event");
+        let Range::Signal {
+            param, unclosed, ..
+        } = &range_event3
+        else {
+            panic!("expected signal range, got {range_event3:?}");
+        };
+        assert_eq!(&SAMPLE[param.clone().unwrap()], r"\{\)");
+        assert!(unclosed.is_none());
+        let range_event4 = iter.next().expect("fifth range event");
+        let Range::Text(range4) = &range_event4 else {
+            panic!("expected text range, got {range_event4:?}");
+        };
+        assert_eq!(&SAMPLE[range4.clone()], r" tail\");
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn non_ascii_prompts() {
+        const SAMPLE: &str = "@жест привет @振る{手}@🎉@{🎈} @жест«x» ok";
+        let config = ParseConfig::new().brackets(&[('{', '}'), ('«', '»')]);
+        let signals: Vec<_> = Iter::with_config(SAMPLE, config)
+            .filter_map(|range| match range {
+                Range::Signal {
+                    span,
+                    prompt,
+                    param,
+                    ..
+                } => Some((
+                    &SAMPLE[span],
+                    &SAMPLE[prompt],
+                    param.map(|param| &SAMPLE[param]),
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            signals,
+            [
+                ("@жест", "жест", None),
+                ("@振る{手}", "振る", Some("手")),
+                ("@🎉", "🎉", None),
+                ("@{🎈}", "", Some("🎈")),
+                ("@жест«x»", "жест", Some("x")),
+            ]
+        );
+    }
+
+    #[test]
+    fn verbatim_params() {
+        const SAMPLE: &str =
+            r"@code{{ let x = map[&k]; } @wave \}} tail @x(()) @--{{ } }} @y{{open }";
+        let mut iter = Iter::new(SAMPLE);
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Signal {
+            prompt,
+            param,
+            span,
+            unclosed,
+            ..
+        } = &range_event0
+        else {
+            panic!("expected signal range, got {range_event0:?}");
+        };
+        assert_eq!(&SAMPLE[prompt.clone()], "code");
+        assert_eq!(
+            &SAMPLE[param.clone().unwrap()],
+            r" let x = map[&k]; } @wave \"
+        );
+        assert_eq!(
+            &SAMPLE[span.clone()],
+            r"@code{{ let x = map[&k]; } @wave \}}"
+        );
+        assert!(unclosed.is_none());
+        assert!(matches!(iter.next(), Some(Range::Text(_))));
+        let range_event2 = iter.next().expect("third range event");
+        let Range::Signal { param, .. } = &range_event2 else {
+            panic!("expected signal range, got {range_event2:?}");
+        };
+        assert_eq!(&SAMPLE[param.clone().unwrap()], "");
+        assert!(matches!(iter.next(), Some(Range::Text(_))));
+        let range_event4 = iter.next().expect("fifth range event");
+        let Range::Comment(range4) = &range_event4 else {
+            panic!("expected comment range, got {range_event4:?}");
+        };
+        assert_eq!(&SAMPLE[range4.clone()], "@--{{ } }}");
+        assert!(matches!(iter.next(), Some(Range::Text(_))));
+        let range_event6 = iter.next().expect("seventh range event");
+        let Range::Signal {
+            param, unclosed, ..
+        } = &range_event6
+        else {
+            panic!("expected signal range, got {range_event6:?}");
+        };
+        assert_eq!(&SAMPLE[param.clone().unwrap()], "open }");
+        assert_eq!(&SAMPLE[unclosed.clone().unwrap()], "{{");
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn extra_params() {
+        const SAMPLE: &str = "@style{b}{Bold}(x) @{a}{b} @x{a} {b} @y{a}{b";
+        let signals: Vec<_> = Iter::new(SAMPLE)
+            .filter_map(|range| match range {
+                Range::Signal {
+                    param,
+                    extra_params,
+                    unclosed,
+                    ..
+                } => Some((
+                    param.map(|param| &SAMPLE[param]),
+                    extra_params
+                        .into_iter()
+                        .map(|param| &SAMPLE[param])
+                        .collect::<Vec<_>>(),
+                    unclosed.is_some(),
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            signals,
+            [
+                (Some("b"), vec!["Bold", "x"], false),
+                (Some("a"), vec![], false),
+                (Some("a"), vec![], false),
+                (Some("a"), vec!["b"], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits() {
+        const SAMPLE: &str = "@abcdef{x} @a{12345}6} @b{{1\\}} }}@c{ё}{234567}@ @d @e";
+        let limits = ParseLimits {
+            max_prompt_len: 3,
+            max_param_len: 4,
+            max_signals_per_line: 5,
+        };
+        let ranges: Vec<_> = Iter::with_config(SAMPLE, ParseConfig::new().limits(limits))
+            .map(|range| match range {
+                Range::Signal {
+                    span,
+                    param,
+                    extra_params,
+                    ..
+                } => {
+                    let params: Vec<_> = param
+                        .into_iter()
+                        .chain(extra_params)
+                        .map(|param| &SAMPLE[param])
+                        .collect();
+                    format!("{} {params:?}", &SAMPLE[span])
+                }
+                Range::Text(range) => format!("{:?}", &SAMPLE[range]),
+                Range::LimitExceeded { limit, at } => format!("{limit:?} at {at}"),
+                range => panic!("unexpected {range:?}"),
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            [
+                "@abc []",
+                "PromptLen at 4",
+                r#""def{x} ""#,
+                r#"@a{1234 ["1234"]"#,
+                "ParamLen at 18",
+                r#""5}6} ""#,
+                r#"@b{{1\}} ["1\\"]"#,
+                r#"" }}""#,
+                r#"@c{ё}{2345 ["ё", "2345"]"#,
+                "ParamLen at 45",
+                r#""67}""#,
+                "@ []",
+                r#"" ""#,
+                r#""@d @e""#,
+                "SignalsPerLine at 50",
+            ]
+        );
+    }
 }
@@ -1,59 +1,94 @@
-use super::raw::{self, Range};
-use ::core::ops;
+use super::{
+    raw::{self, Range},
+    ParseConfig, Spacing,
+};
+use ::core::{iter::Peekable, ops};
 
 fn remove_right(text: &str, range: ops::Range<usize>) -> ops::Range<usize> {
-    match text[range.clone()].rfind(|ch: char| !ch.is_whitespace()) {
-        Some(index) => {
-            range.start
-                ..text[range.start + index..range.end]
-                    .char_indices()
-                    .nth(1)
-                    .map_or(range.end, |(whitespace_start, _)| {
-                        range.start + index + whitespace_start
-                    })
-        }
-        None => range.start..range.start,
-    }
+    let end = range.start + text[range.clone()].trim_end().len();
+    debug_assert!(text.is_char_boundary(end));
+    range.start..end
 }
 
 fn remove_left(text: &str, range: ops::Range<usize>) -> ops::Range<usize> {
-    match text[range.clone()].find(|ch: char| !ch.is_whitespace()) {
-        Some(index) => range.start + index..range.end,
-        None => range.start..range.start,
-    }
+    let start = range.end - text[range.clone()].trim_start().len();
+    debug_assert!(text.is_char_boundary(start));
+    start..range.end
 }
 
 #[derive(Clone, Debug)]
 pub(super) struct Iter<'a> {
-    raw: raw::Iter<'a>,
+    raw: Peekable<raw::Iter<'a>>,
+    text: &'a str,
+    config: ParseConfig,
     remove_left_next: bool,
     seen_signal: bool,
+    leading: Spacing,
 }
 
 impl<'a> Iter<'a> {
+    #[cfg(test)]
     pub fn new(text: &'a str) -> Self {
+        Self::with_config(text, ParseConfig::new())
+    }
+
+    /// Unless trimming is configured off, in which case ranges pass through untouched,
+    /// keeping whitespace and escapes as they are in `text`
+    pub fn with_config(text: &'a str, config: ParseConfig) -> Self {
         Self {
-            raw: raw::Iter::new(text),
+            raw: raw::Iter::with_config(text, config).peekable(),
+            text,
+            config,
             remove_left_next: true,
             seen_signal: false,
+            leading: Spacing::None,
         }
     }
 
     pub fn as_full_str(&self) -> &'a str {
-        self.raw.as_full_str()
+        self.text
+    }
+
+    /// Whitespace trimmed off the left of the last text or escape range
+    pub fn leading(&self) -> Spacing {
+        self.leading
     }
 }
 
 impl<'a> Iterator for Iter<'a> {
     type Item = Range;
 
+    /// Empty text ranges are dropped, so there might be none
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.raw.size_hint().1)
+    }
+
     fn next(&mut self) -> Option<Self::Item> {
         let next = self.raw.next()?;
-        if let Range::Text(range) = &next {
-            let mut range = remove_right(self.as_full_str(), range.clone());
+        self.leading = Spacing::None;
+        if !self.config.trim {
+            Some(next)
+        } else if let Range::Escape(range) = next {
+            self.remove_left_next = false;
+            Some(Range::Escape(
+                range.start + raw::SIGNAL_CHAR.len_utf8()..range.end,
+            ))
+        } else if let Range::LimitExceeded { .. } = next {
+            Some(next)
+        } else if let Range::Text(range) = &next {
+            // Text glued to an escaped `@` stays untouched on that side
+            let mut range = if let Some(Range::Escape(_)) = self.raw.peek() {
+                range.clone()
+            } else {
+                remove_right(self.as_full_str(), range.clone())
+            };
             if self.remove_left_next {
-                if self.seen_signal {
+                if self.seen_signal && !self.config.keep_indent {
+                    let start = range.start;
                     range = remove_left(self.as_full_str(), range);
+                    if range.start != start {
+                        self.leading = Spacing::Space;
+                    }
                 }
                 self.remove_left_next = false;
             }
@@ -71,7 +106,7 @@ impl<'a> Iterator for Iter<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Iter, Range};
+    use super::{Iter, ParseConfig, Range};
 
     #[test]
     fn no_trim_required() {
@@ -121,4 +156,93 @@ mod tests {
         assert_eq!(&SAMPLE[range1.clone()], "Hello, world!");
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn escape_trim() {
+        const SAMPLE: &str = "@oops mail me @@ home ";
+        let mut iter = Iter::new(SAMPLE);
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Signal { .. } = &range_event0 else {
+            panic!("expected signal range, got {range_event0:?}");
+        };
+        let range_event1 = iter.next().expect("second range event");
+        let Range::Text(range1) = &range_event1 else {
+            panic!("expected text range, got {range_event1:?}");
+        };
+        assert_eq!(&SAMPLE[range1.clone()], "mail me ");
+        let range_event2 = iter.next().expect("third range event");
+        let Range::Escape(range2) = &range_event2 else {
+            panic!("expected escape range, got {range_event2:?}");
+        };
+        assert_eq!(&SAMPLE[range2.clone()], "@");
+        let range_event3 = iter.next().expect("fourth range event");
+        let Range::Text(range3) = &range_event3 else {
+            panic!("expected text range, got {range_event3:?}");
+        };
+        assert_eq!(&SAMPLE[range3.clone()], " home");
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn untrimmed() {
+        const SAMPLE: &str = "  @oops  mail me @@ ";
+        let mut iter = Iter::with_config(SAMPLE, ParseConfig::new().trim(false));
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Text(range0) = &range_event0 else {
+            panic!("expected text range, got {range_event0:?}");
+        };
+        assert_eq!(&SAMPLE[range0.clone()], "  ");
+        let range_event1 = iter.next().expect("second range event");
+        let Range::Signal { .. } = &range_event1 else {
+            panic!("expected signal range, got {range_event1:?}");
+        };
+        let range_event2 = iter.next().expect("third range event");
+        let Range::Text(range2) = &range_event2 else {
+            panic!("expected text range, got {range_event2:?}");
+        };
+        assert_eq!(&SAMPLE[range2.clone()], "  mail me ");
+        let range_event3 = iter.next().expect("fourth range event");
+        let Range::Escape(range3) = &range_event3 else {
+            panic!("expected escape range, got {range_event3:?}");
+        };
+        assert_eq!(&SAMPLE[range3.clone()], "@@");
+        let range_event4 = iter.next().expect("fifth range event");
+        let Range::Text(range4) = &range_event4 else {
+            panic!("expected text range, got {range_event4:?}");
+        };
+        assert_eq!(&SAMPLE[range4.clone()], " ");
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn multibyte_trim() {
+        const SAMPLE: &str = "\u{3000}Wait…\u{a0}@oops\u{a0}— what?\u{3000}\u{a0}";
+        let mut iter = Iter::new(SAMPLE);
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Text(range0) = &range_event0 else {
+            panic!("expected text range, got {range_event0:?}");
+        };
+        assert_eq!(&SAMPLE[range0.clone()], "\u{3000}Wait…");
+        let range_event1 = iter.next().expect("second range event");
+        let Range::Signal { .. } = &range_event1 else {
+            panic!("expected signal range, got {range_event1:?}");
+        };
+        let range_event2 = iter.next().expect("third range event");
+        let Range::Text(range2) = &range_event2 else {
+            panic!("expected text range, got {range_event2:?}");
+        };
+        assert_eq!(&SAMPLE[range2.clone()], "\u{a0}— what?");
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn multibyte_whitespace_only() {
+        const SAMPLE: &str = "@oops\u{3000}\u{a0}\u{2003}";
+        let mut iter = Iter::new(SAMPLE);
+        let range_event0 = iter.next().expect("first range event");
+        let Range::Signal { .. } = &range_event0 else {
+            panic!("expected signal range, got {range_event0:?}");
+        };
+        assert_eq!(iter.next(), None);
+    }
 }
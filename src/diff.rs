@@ -0,0 +1,206 @@
+use crate::{bookmark_names, story_choices, story_text, Guide, Story};
+use alloc::vec::Vec;
+use petgraph::graph::NodeIndex;
+
+/// One of the differences between two stories, see [`diff`]
+#[derive(Clone, PartialEq, Debug)]
+pub enum StoryChange<'a> {
+    AddedBookmark {
+        name: &'a str,
+    },
+    RemovedBookmark {
+        name: &'a str,
+    },
+    /// Choice of a bookmark that is in both stories, which leads elsewhere,
+    /// where `index` tells which of the choices it is in the order they are written
+    RetargetedChoice {
+        from: &'a str,
+        index: usize,
+        old_target: &'a str,
+        new_target: &'a str,
+    },
+    /// Choice that only the new story has, at `index` of the choices of `from`
+    AddedChoice {
+        from: &'a str,
+        index: usize,
+        target: &'a str,
+    },
+    /// Choice that only the old story has, at `index` of the choices of `from`
+    RemovedChoice {
+        from: &'a str,
+        index: usize,
+        target: &'a str,
+    },
+    /// Bookmark with other text, where `similarity` goes from `0.0` for nothing
+    /// in common to `1.0` for the same text, not counting whitespace around it
+    TextChanged {
+        name: &'a str,
+        similarity: f32,
+    },
+}
+
+/// Structural differences between two stories, with bookmarks matched by their names
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct StoryDiff<'a> {
+    /// Changes of bookmarks in the order they are written in the old story,
+    /// followed by the bookmarks added in the new one
+    pub changes: Vec<StoryChange<'a>>,
+}
+
+impl<'a> StoryDiff<'a> {
+    /// Whether stories have the same structure and text
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Pairs of chars in a row, sorted
+fn bigrams(text: &str) -> Vec<(char, char)> {
+    let mut bigrams: Vec<_> = text.chars().zip(text.chars().skip(1)).collect();
+    bigrams.sort_unstable();
+    bigrams
+}
+
+/// Dice coefficient of char bigrams of both texts
+#[allow(clippy::cast_precision_loss)]
+fn similarity(old: &str, new: &str) -> f32 {
+    let (old, new) = (bigrams(old), bigrams(new));
+    if old.is_empty() && new.is_empty() {
+        return 0.0;
+    }
+    let (mut old_index, mut new_index, mut common) = (0, 0, 0);
+    while let (Some(old_bigram), Some(new_bigram)) = (old.get(old_index), new.get(new_index)) {
+        match old_bigram.cmp(new_bigram) {
+            core::cmp::Ordering::Less => old_index += 1,
+            core::cmp::Ordering::Greater => new_index += 1,
+            core::cmp::Ordering::Equal => {
+                common += 1;
+                old_index += 1;
+                new_index += 1;
+            }
+        }
+    }
+    (2 * common) as f32 / (old.len() + new.len()) as f32
+}
+
+/// Names of bookmarks that choices of `node` lead to, in the order they are written
+fn targets<'a>(story: &Story, node: NodeIndex, names: &[&'a str]) -> Vec<&'a str> {
+    story_choices(story, node)
+        .map(|(edge, _)| {
+            let (_, target) = story.edge_endpoints(edge).expect("edge of the story");
+            names[target.index()]
+        })
+        .collect()
+}
+
+/// Structural differences between `old` and `new` stories, each read from the text next to it
+#[must_use]
+pub fn diff<'a>(
+    (old_guide, old_story, old_full): (&Guide<'a>, &Story, &str),
+    (new_guide, new_story, new_full): (&Guide<'a>, &Story, &str),
+) -> StoryDiff<'a> {
+    let old_names = bookmark_names(old_guide, old_story, old_full);
+    let new_names = bookmark_names(new_guide, new_story, new_full);
+    let mut changes = Vec::new();
+    for old_node in old_story.node_indices() {
+        let name = old_names[old_node.index()];
+        let Some(new_node) = new_guide.get(name).copied() else {
+            changes.push(StoryChange::RemovedBookmark { name });
+            continue;
+        };
+        let old_text = story_text(old_story, old_node, old_full).unwrap_or_default();
+        let new_text = story_text(new_story, new_node, new_full).unwrap_or_default();
+        let (old_text, new_text) = (old_text.trim(), new_text.trim());
+        if old_text != new_text {
+            changes.push(StoryChange::TextChanged {
+                name,
+                similarity: similarity(old_text, new_text),
+            });
+        }
+        let old_targets = targets(old_story, old_node, &old_names);
+        let new_targets = targets(new_story, new_node, &new_names);
+        for index in 0..old_targets.len().max(new_targets.len()) {
+            changes.extend(match (old_targets.get(index), new_targets.get(index)) {
+                (Some(old_target), Some(new_target)) if old_target != new_target => {
+                    Some(StoryChange::RetargetedChoice {
+                        from: name,
+                        index,
+                        old_target,
+                        new_target,
+                    })
+                }
+                (Some(target), None) => Some(StoryChange::RemovedChoice {
+                    from: name,
+                    index,
+                    target,
+                }),
+                (None, Some(target)) => Some(StoryChange::AddedChoice {
+                    from: name,
+                    index,
+                    target,
+                }),
+                _ => None,
+            });
+        }
+    }
+    for new_node in new_story.node_indices() {
+        let name = new_names[new_node.index()];
+        if !old_guide.contains_key(name) {
+            changes.push(StoryChange::AddedBookmark { name });
+        }
+    }
+    StoryDiff { changes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, StoryChange};
+
+    #[test]
+    fn same() {
+        const SAMPLE: &str = "@bookmark{greet}Hi!\n@choice{end}Bye\n@bookmark{end}End.";
+        let (guide, story) = crate::read([SAMPLE]);
+        assert!(diff((&guide, &story, SAMPLE), (&guide, &story, SAMPLE)).is_empty());
+    }
+
+    #[test]
+    fn changes() {
+        const OLD: &str = "@bookmark{greet}Hello there!\n@choice{end}Bye\n@choice{end}Later\n\
+            @bookmark{middle}Mid\n@choice{end}On\n@bookmark{end}End.";
+        const NEW: &str = "@bookmark{greet}Hello there, you!\n@choice{secret}Bye\n\
+            @bookmark{end}End.\n@choice{greet}Again\n@bookmark{secret}Secret!";
+        let (old_guide, old_story) = crate::read([OLD]);
+        let (new_guide, new_story) = crate::read([NEW]);
+        let story_diff = diff((&old_guide, &old_story, OLD), (&new_guide, &new_story, NEW));
+        let [StoryChange::TextChanged { name, similarity }, rest @ ..] = &story_diff.changes[..]
+        else {
+            panic!("expected text change, got {:?}", story_diff.changes);
+        };
+        assert_eq!(*name, "greet");
+        assert!(0.5 < *similarity && *similarity < 1.0, "{similarity}");
+        assert_eq!(
+            rest,
+            [
+                StoryChange::RetargetedChoice {
+                    from: "greet",
+                    index: 0,
+                    old_target: "end",
+                    new_target: "secret",
+                },
+                StoryChange::RemovedChoice {
+                    from: "greet",
+                    index: 1,
+                    target: "end",
+                },
+                StoryChange::RemovedBookmark { name: "middle" },
+                StoryChange::AddedChoice {
+                    from: "end",
+                    index: 0,
+                    target: "greet",
+                },
+                StoryChange::AddedBookmark { name: "secret" },
+            ]
+        );
+    }
+}
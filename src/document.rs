@@ -0,0 +1,226 @@
+use crate::{Event, EventIter, OwnedEvent, ReadOptions, StrRange, Style, StyleConfig};
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::ops;
+
+fn is_line_end(ch: char) -> bool {
+    ch == '\n' || ch == '\r'
+}
+
+#[derive(Clone, Debug)]
+struct Line {
+    /// Byte-index of the line start in the document
+    start: usize,
+    /// Byte-length of the line without its terminator
+    len: usize,
+    /// Events of the line, with ranges relative to its start
+    events: Vec<OwnedEvent>,
+}
+
+impl Line {
+    fn new(text: &str, start: usize, len: usize) -> Self {
+        Self {
+            start,
+            len,
+            events: EventIter::new(&text[start..start + len])
+                .map(Event::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Splits text from `start`, which must be a line start, into lines up to `end`,
+/// which must be the start of the line following them, or the end of text if there is none
+fn parse_lines(text: &str, mut start: usize, end: Option<usize>) -> Vec<Line> {
+    let mut lines = Vec::new();
+    while let Some(index) = text[start..end.unwrap_or(text.len())].find(is_line_end) {
+        lines.push(Line::new(text, start, index));
+        let line_end = start + index;
+        start = if text[line_end..].starts_with("\r\n") {
+            line_end + 2
+        } else {
+            line_end + 1
+        };
+    }
+    if end.is_none() {
+        lines.push(Line::new(text, start, text.len() - start));
+    }
+    lines
+}
+
+/// Text with cached events of each line, which only reparses lines touched by an edit.
+/// Events are the same as those of [`EventIter::new`] for the whole text.
+#[derive(Clone, Debug)]
+pub struct Document {
+    text: String,
+    lines: Vec<Line>,
+}
+
+impl Document {
+    #[must_use]
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            lines: parse_lines(text, 0, None),
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    fn line_index(&self, offset: usize) -> usize {
+        self.lines.partition_point(|line| line.start <= offset) - 1
+    }
+
+    /// Replaces `range` of text with `replacement`, reparsing the lines around it,
+    /// while the lines after it only get their offsets shifted
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or doesn't lie on char boundaries
+    pub fn apply_edit(&mut self, range: ops::Range<usize>, replacement: &str) {
+        // neighbouring lines are reparsed too, as terminators may merge or split
+        let first = self.line_index(range.start).saturating_sub(1);
+        let last = (self.line_index(range.end) + 1).min(self.lines.len() - 1);
+        let start = self.lines[first].start;
+        let removed_len = range.len();
+        self.text.replace_range(range, replacement);
+        for line in &mut self.lines[last + 1..] {
+            line.start = line.start - removed_len + replacement.len();
+        }
+        let end = self.lines.get(last + 1).map(|line| line.start);
+        let lines = parse_lines(&self.text, start, end);
+        self.lines.splice(first..=last, lines);
+    }
+
+    fn is_skipped(&self, line_index: usize) -> bool {
+        let line = &self.lines[line_index];
+        line_index != 0
+            && line_index != self.lines.len() - 1
+            && self.text[line.start..line.start + line.len]
+                .chars()
+                .all(char::is_whitespace)
+    }
+
+    /// Cached events, with ranges relative to the whole text
+    pub fn events(&self) -> impl Iterator<Item = Event<'_>> {
+        let mut prev_end = None;
+        // lines are parsed on their own, so scopes left open by those before are applied here
+        let mut scope = Style::REGULAR;
+        let styles = StyleConfig::new();
+        (0..self.lines.len())
+            .filter(|line_index| !self.is_skipped(*line_index))
+            .flat_map(move |line_index| {
+                let line = &self.lines[line_index];
+                let line_break = prev_end.replace(line.start + line.len).map(|prev_end| {
+                    let line_break = StrRange {
+                        slice: &self.text[prev_end..line.start],
+                        range: prev_end..line.start,
+                    };
+                    match line_break.slice {
+                        "\n" | "\r\n" | "\r" => Event::Break(line_break),
+                        _ => Event::ParagraphBreak(line_break),
+                    }
+                });
+                line_break.into_iter().chain(
+                    line.events
+                        .iter()
+                        .map(|event| Event::from(event).map_indices(|index| index + line.start)),
+                )
+            })
+            .map(move |event| crate::style::scoped(&mut scope, event, ReadOptions::new(), &styles))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Document;
+    use crate::EventIter;
+    use quickcheck::quickcheck;
+
+    const PIECES: [&str; 12] = [
+        "@",
+        "{",
+        "}",
+        " ",
+        "\n",
+        "\r",
+        "\r\n",
+        "wave",
+        "bi",
+        "ё",
+        "@bookmark{x}",
+        "\t",
+    ];
+
+    fn assert_reparsed(document: &Document) {
+        let cached: Vec<_> = document.events().collect();
+        let whole: Vec<_> = EventIter::new(document.as_str()).collect();
+        assert_eq!(cached, whole, "{:?}", document.as_str());
+    }
+
+    #[test]
+    fn same_as_whole() {
+        for sample in [
+            "",
+            "\n",
+            "Hello!",
+            "@bookmark{greet} Hello, @name!\r\n\n \n@style{b}@{Bold} @@ home @-- note\n@{open\r",
+            "@style{b+}Bold\n@style{i}{both}\n\n@style{-}Regular",
+        ] {
+            assert_reparsed(&Document::new(sample));
+        }
+    }
+
+    #[test]
+    fn edits() {
+        let mut document = Document::new("@bookmark{greet} Hello!\n\nBye @wave\r\nEnd.");
+        for (range, replacement) in [
+            (10..15, "welcome"),
+            (0..0, "First line\n"),
+            (34..35, ""),
+            (34..34, "\n  \n"),
+            (39..39, "\r"),
+            (45..46, ""),
+            (0..3, "@{"),
+            (5..40, ""),
+            (0..0, "\n"),
+        ] {
+            document.apply_edit(range, replacement);
+            assert_reparsed(&document);
+        }
+        let len = document.as_str().len();
+        document.apply_edit(0..len, "");
+        assert_reparsed(&document);
+        document.apply_edit(0..0, "@x{y}\r");
+        assert_reparsed(&document);
+        document.apply_edit(6..6, "\n@z");
+        assert_reparsed(&document);
+    }
+
+    quickcheck! {
+        fn arbitrary_edits(initial: Vec<u8>, edits: Vec<(u8, u8, u8)>) -> bool {
+            let piece = |index: u8| PIECES[usize::from(index) % PIECES.len()];
+            let initial: String = initial.into_iter().map(piece).collect();
+            let mut document = Document::new(&initial);
+            for (start, len, replacement) in edits {
+                let text = document.as_str();
+                let mut start = usize::from(start).min(text.len());
+                while !text.is_char_boundary(start) {
+                    start -= 1;
+                }
+                let mut end = (start + usize::from(len) % 8).min(text.len());
+                while !text.is_char_boundary(end) {
+                    end += 1;
+                }
+                document.apply_edit(start..end, piece(replacement));
+                let cached: Vec<_> = document.events().collect();
+                if cached != EventIter::new(document.as_str()).collect::<Vec<_>>() {
+                    return false;
+                }
+            }
+            true
+        }
+    }
+}
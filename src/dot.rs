@@ -0,0 +1,186 @@
+use crate::{choice_label, choice_text, graph::names_by_index, story_choices, Guide, Story};
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+/// Direction of ranks in a graph laid out by Graphviz, see [`DotOptions::rank_dir`]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum RankDir {
+    #[default]
+    TopBottom,
+    LeftRight,
+    BottomTop,
+    RightLeft,
+}
+
+impl fmt::Display for RankDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::TopBottom => "TB",
+            Self::LeftRight => "LR",
+            Self::BottomTop => "BT",
+            Self::RightLeft => "RL",
+        })
+    }
+}
+
+/// Options of writing a story in DOT, see [`to_dot_with_options`]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct DotOptions {
+    max_choice_chars: Option<usize>,
+    rank_dir: RankDir,
+}
+
+impl DotOptions {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_choice_chars: Some(24),
+            rank_dir: RankDir::TopBottom,
+        }
+    }
+
+    /// How many chars of choice text to keep in edge labels, which is 24 by default,
+    /// with `…` in place of the rest, or `None` to keep the whole text
+    #[must_use]
+    pub const fn max_choice_chars(self, max_choice_chars: Option<usize>) -> Self {
+        Self {
+            max_choice_chars,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub const fn rank_dir(self, rank_dir: RankDir) -> Self {
+        Self { rank_dir, ..self }
+    }
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes `text` as a quoted DOT string, with whitespace runs collapsed into single spaces
+/// and chars past `max_chars` replaced by `…`
+fn write_label(out: &mut String, text: &str, max_chars: Option<usize>) {
+    out.push('"');
+    let mut chars = 0;
+    for (word_index, word) in text.split_whitespace().enumerate() {
+        if word_index != 0 {
+            out.push(' ');
+            chars += 1;
+        }
+        for ch in word.chars() {
+            if max_chars.is_some_and(|max_chars| chars >= max_chars) {
+                out.push_str("…\"");
+                return;
+            }
+            if ch == '"' || ch == '\\' {
+                out.push('\\');
+            }
+            out.push(ch);
+            chars += 1;
+        }
+    }
+    out.push('"');
+}
+
+/// Same as [`to_dot`], but with truncation and layout as [`DotOptions`] tell
+#[must_use]
+pub fn to_dot_with_options(
+    guide: &Guide,
+    story: &Story,
+    full: &str,
+    options: DotOptions,
+) -> String {
+    let mut out = String::new();
+    // writing to a string never fails
+    let _ = write_dot(&mut out, guide, story, full, options);
+    out
+}
+
+fn write_dot(
+    out: &mut String,
+    guide: &Guide,
+    story: &Story,
+    full: &str,
+    options: DotOptions,
+) -> fmt::Result {
+    writeln!(out, "digraph {{")?;
+    writeln!(out, "    rankdir={};", options.rank_dir)?;
+    let names = names_by_index(guide, story, full);
+    for node in story.node_indices() {
+        write!(out, "    n{} [label=", node.index())?;
+        write_label(out, names[node.index()], None);
+        writeln!(out, "];")?;
+    }
+    for node in story.node_indices() {
+        for (edge, _) in story_choices(story, node) {
+            let Some((_, target)) = story.edge_endpoints(edge) else {
+                continue;
+            };
+            write!(out, "    n{} -> n{} [label=", node.index(), target.index())?;
+            let text = choice_label(story, edge, full)
+                .or_else(|| choice_text(story, edge, full))
+                .unwrap_or_default();
+            write_label(out, text, options.max_choice_chars);
+            writeln!(out, "];")?;
+        }
+    }
+    writeln!(out, "}}")
+}
+
+/// Writes a story read from `full` in DOT, e.g. to see its shape with Graphviz.
+/// Nodes are labeled with bookmark names, and edges with choice labels or the start of choice text.
+#[must_use]
+pub fn to_dot(guide: &Guide, story: &Story, full: &str) -> String {
+    to_dot_with_options(guide, story, full, DotOptions::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_dot, to_dot_with_options, DotOptions, RankDir};
+
+    const README: &str = "@bookmark{greet}\n– Hello, you!\n@choice{greet}– Come again?\n\
+        @choice{bye}– Hi!\n\n@bookmark{bye}\n– Well, farewell..\n";
+
+    #[test]
+    fn readme() {
+        let (guide, story) = crate::read([README]);
+        assert_eq!(
+            to_dot(&guide, &story, README),
+            "digraph {
+    rankdir=TB;
+    n0 [label=\"greet\"];
+    n1 [label=\"bye\"];
+    n0 -> n0 [label=\"– Come again?\"];
+    n0 -> n1 [label=\"– Hi!\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn options() {
+        const SAMPLE: &str =
+            "@bookmark{say \"hi\"}Hi!\n@choice{end}A \"rather\"  long\tchoice\n@bookmark{end}End.";
+        let (guide, story) = crate::read([SAMPLE]);
+        let options = DotOptions::new()
+            .max_choice_chars(Some(12))
+            .rank_dir(RankDir::LeftRight);
+        assert_eq!(
+            to_dot_with_options(&guide, &story, SAMPLE, options),
+            "digraph {
+    rankdir=LR;
+    n0 [label=\"say \\\"hi\\\"\"];
+    n1 [label=\"end\"];
+    n0 -> n1 [label=\"A \\\"rather\\\" l…\"];
+}
+"
+        );
+        let options = options.max_choice_chars(None);
+        assert!(to_dot_with_options(&guide, &story, SAMPLE, options)
+            .contains("[label=\"A \\\"rather\\\" long choice\"]"));
+    }
+}
@@ -0,0 +1,333 @@
+use crate::compose::push_text;
+use crate::core::{Event, Iter, Signal};
+use crate::graph::{split_param, ParamParts};
+use crate::{ParseConfig, ReadOptions};
+use alloc::{string::String, vec::Vec};
+use core::{fmt, ops::Range};
+
+/// Reason for [`rename_bookmark`], [`insert_choice`] or [`insert_bookmark`]
+/// to leave the source as it is
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum EditError<'a> {
+    /// No bookmark has the name, nor an alias of it
+    Missing { name: &'a str },
+    /// Another bookmark has the new name already, or an alias of it
+    Taken { name: &'a str },
+    /// New name is empty, has whitespace around it, or has a char that has a meaning in params,
+    /// which are brackets, backslashes, `@`, `|`, `?`, `*` and `"`
+    InvalidName { name: &'a str },
+}
+
+/// Same as [`EditError`], which it was named before other edits were added
+pub type RenameError<'a> = EditError<'a>;
+
+impl<'a> fmt::Display for EditError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { name } => write!(f, "no bookmark is named `{name}`"),
+            Self::Taken { name } => write!(f, "bookmark `{name}` exists already"),
+            Self::InvalidName { name } => write!(f, "`{name}` can't be a bookmark name"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> std::error::Error for EditError<'a> {}
+
+/// Fails unless `name` can be written in a param as it is
+fn check_name(name: &str) -> Result<(), EditError<'_>> {
+    let is_valid = !name.is_empty()
+        && name.trim() == name
+        && !name.contains([
+            '{', '}', '(', ')', '[', ']', '<', '>', '\\', '@', '|', '?', '*', '"',
+        ]);
+    if is_valid {
+        Ok(())
+    } else {
+        Err(EditError::InvalidName { name })
+    }
+}
+
+/// Spans of `bookmark` signals along with their names and aliases, in the order they are written
+fn bookmark_spans(source: &str) -> Vec<(Range<usize>, Vec<&str>)> {
+    let options = ReadOptions::new();
+    let mut spans = Vec::new();
+    for event in Iter::new(source) {
+        if let Event::Signal(Signal::Call {
+            prompt,
+            param,
+            span,
+            ..
+        }) = event
+        {
+            if options.is_prompt(prompt.slice, "bookmark") {
+                let ParamParts { name, aliases, .. } = split_param(&param, true);
+                let names = [name]
+                    .into_iter()
+                    .chain(aliases)
+                    .map(|part| part.trimmed().slice)
+                    .collect();
+                spans.push((span, names));
+            }
+        }
+    }
+    spans
+}
+
+/// Where to write past the bookmark that has `name` first, which is right after
+/// the line of its last text, and whether a line break should go before whatever is written
+fn bookmark_end<'a>(source: &str, name: &'a str) -> Result<(usize, bool), EditError<'a>> {
+    let spans = bookmark_spans(source);
+    let index = spans
+        .iter()
+        .position(|(_, names)| names.contains(&name))
+        .ok_or(EditError::Missing { name })?;
+    let start = spans[index].0.end;
+    let end = spans
+        .get(index + 1)
+        .map_or(source.len(), |(span, _)| span.start);
+    let content_end = start + source[start..end].trim_end().len();
+    let rest = &source[content_end..end];
+    Ok(if rest.starts_with("\r\n") {
+        (content_end + 2, false)
+    } else if rest.starts_with(['\n', '\r']) {
+        (content_end + 1, false)
+    } else {
+        (content_end, true)
+    })
+}
+
+/// Same `source` with `@choice{target}` and `text` after the last text of the `at_bookmark` bookmark,
+/// on a line of its own, with every `@` of the text doubled, so that none of it is taken for a signal
+///
+/// # Errors
+///
+/// Fails if either bookmark is missing, or the target can't be written as it is
+pub fn insert_choice<'a>(
+    source: &str,
+    at_bookmark: &'a str,
+    target: &'a str,
+    text: &str,
+) -> Result<String, EditError<'a>> {
+    check_name(target)?;
+    let (guide, _) = crate::read([source]);
+    if !guide.contains_key(target) {
+        return Err(EditError::Missing { name: target });
+    }
+    let (at, needs_break) = bookmark_end(source, at_bookmark)?;
+    let mut choice = String::new();
+    if needs_break {
+        choice.push('\n');
+    }
+    choice.push_str("@choice{");
+    choice.push_str(target);
+    choice.push('}');
+    // otherwise the text would be taken for another param of the choice
+    if text.starts_with(|ch| ParseConfig::new().is_left_bracket(ch)) {
+        choice.push(' ');
+    }
+    push_text(&mut choice, text);
+    choice.push('\n');
+    let mut out = String::from(&source[..at]);
+    out.push_str(&choice);
+    out.push_str(&source[at..]);
+    Ok(out)
+}
+
+/// Same `source` with `@bookmark{name}` and its `text` on the next line, after the bookmark
+/// named `after` along with its choices, or at the end of the source if it's `None`,
+/// with every `@` of the text doubled, so that none of it is taken for a signal
+///
+/// # Errors
+///
+/// Fails if the name is taken or can't be written as it is, or if there's no `after` bookmark
+pub fn insert_bookmark<'a>(
+    source: &str,
+    name: &'a str,
+    text: &str,
+    after: Option<&'a str>,
+) -> Result<String, EditError<'a>> {
+    check_name(name)?;
+    let (guide, _) = crate::read([source]);
+    if guide.contains_key(name) {
+        return Err(EditError::Taken { name });
+    }
+    let (at, needs_break) = match after {
+        Some(after) => bookmark_end(source, after)?,
+        None => (
+            source.len(),
+            !source.is_empty() && !source.ends_with(['\n', '\r']),
+        ),
+    };
+    let mut bookmark = String::new();
+    if needs_break {
+        bookmark.push('\n');
+    }
+    bookmark.push_str("@bookmark{");
+    bookmark.push_str(name);
+    bookmark.push_str("}\n");
+    push_text(&mut bookmark, text);
+    bookmark.push('\n');
+    let mut out = String::from(&source[..at]);
+    out.push_str(&bookmark);
+    out.push_str(&source[at..]);
+    Ok(out)
+}
+
+/// Same `source` with the `old` name of a bookmark, or one of its aliases,
+/// replaced by the `new` one in the params of `bookmark` and `choice` signals,
+/// leaving the rest as it is, including the same name written anywhere else, e.g. in the text
+///
+/// # Errors
+///
+/// Fails if there's no such bookmark, the new name is taken or can't be written as it is
+pub fn rename_bookmark<'a>(
+    source: &str,
+    old: &'a str,
+    new: &'a str,
+) -> Result<String, EditError<'a>> {
+    check_name(new)?;
+    let (guide, _) = crate::read([source]);
+    if !guide.contains_key(old) {
+        return Err(EditError::Missing { name: old });
+    }
+    if guide.contains_key(new) {
+        return Err(EditError::Taken { name: new });
+    }
+    let options = ReadOptions::new();
+    let mut ranges = Vec::new();
+    for event in Iter::new(source) {
+        let Event::Signal(Signal::Call { prompt, param, .. }) = event else {
+            continue;
+        };
+        let is_bookmark = options.is_prompt(prompt.slice, "bookmark");
+        if !is_bookmark && !options.is_prompt(prompt.slice, "choice") {
+            continue;
+        }
+        let ParamParts { name, aliases, .. } = split_param(&param, is_bookmark);
+        ranges.extend(
+            [name]
+                .into_iter()
+                .chain(aliases)
+                .map(|part| part.trimmed())
+                .filter(|part| part.slice == old)
+                .map(|part| part.range),
+        );
+    }
+    let mut out = String::with_capacity(source.len());
+    let mut end = 0;
+    for range in ranges {
+        out.push_str(&source[end..range.start]);
+        out.push_str(new);
+        end = range.end;
+    }
+    out.push_str(&source[end..]);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{insert_bookmark, insert_choice, rename_bookmark, EditError};
+
+    const SAMPLE: &str = "@bookmark{intro}The cave is dark.\n@choice{ cave * 2 | To the cave}Go\n\
+        @bookmark{cave | hole}A cave.\n@choice{hole}Down\n@choice{intro}Out\n";
+
+    #[test]
+    fn renamed() {
+        assert_eq!(
+            rename_bookmark(SAMPLE, "cave", "cavern"),
+            Ok(
+                "@bookmark{intro}The cave is dark.\n@choice{ cavern * 2 | To the cave}Go\n\
+                @bookmark{cavern | hole}A cave.\n@choice{hole}Down\n@choice{intro}Out\n"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            rename_bookmark(SAMPLE, "hole", "pit"),
+            Ok(SAMPLE.replace("hole", "pit"))
+        );
+    }
+
+    #[test]
+    fn refused() {
+        assert_eq!(
+            rename_bookmark(SAMPLE, "cave", "hole"),
+            Err(EditError::Taken { name: "hole" })
+        );
+        assert_eq!(
+            rename_bookmark(SAMPLE, "forest", "woods"),
+            Err(EditError::Missing { name: "forest" })
+        );
+        assert_eq!(
+            rename_bookmark(SAMPLE, "cave", "a | b"),
+            Err(EditError::InvalidName { name: "a | b" })
+        );
+    }
+
+    /// Names of bookmarks that choices of `from` lead to in `source`, in the order they are written
+    fn targets<'a>(source: &'a str, from: &str) -> Vec<&'a str> {
+        let (guide, story) = crate::read([source]);
+        let names = crate::bookmark_names(&guide, &story, source);
+        crate::story_choices(&story, guide[from])
+            .map(|(edge, _)| names[story.edge_endpoints(edge).unwrap().1.index()])
+            .collect()
+    }
+
+    #[test]
+    fn inserted_choice() {
+        let source = insert_choice(SAMPLE, "intro", "hole", "{Jump} @once").expect("inserted");
+        assert_eq!(
+            source,
+            SAMPLE.replacen("Go\n", "Go\n@choice{hole} {Jump} @@once\n", 1)
+        );
+        assert_eq!(targets(&source, "intro"), ["cave", "cave"]);
+        let (guide, story) = crate::read([source.as_str()]);
+        let (edge, _) = crate::story_choices(&story, guide["intro"]).nth(1).unwrap();
+        assert_eq!(
+            crate::choice_text(&story, edge, &source),
+            Some(" {Jump} @@once\n")
+        );
+        let source = insert_choice("@bookmark{a}A\n\n\n@bookmark{b}B", "b", "a", "Up").unwrap();
+        assert_eq!(source, "@bookmark{a}A\n\n\n@bookmark{b}B\n@choice{a}Up\n");
+        assert_eq!(targets(&source, "b"), ["a"]);
+        let source = insert_choice("@bookmark{a}A\n\n\n@bookmark{b}B", "a", "b", "On").unwrap();
+        assert_eq!(source, "@bookmark{a}A\n@choice{b}On\n\n\n@bookmark{b}B");
+        assert_eq!(
+            insert_choice(SAMPLE, "intro", "nowhere", "Go"),
+            Err(EditError::Missing { name: "nowhere" })
+        );
+        assert_eq!(
+            insert_choice(SAMPLE, "intro", "a}b", "Go"),
+            Err(EditError::InvalidName { name: "a}b" })
+        );
+    }
+
+    #[test]
+    fn inserted_bookmark() {
+        let source = insert_bookmark(SAMPLE, "lake", "Still @water.", Some("intro")).unwrap();
+        assert!(source.starts_with(
+            "@bookmark{intro}The cave is dark.\n@choice{ cave * 2 | To the cave}Go\n\
+            @bookmark{lake}\nStill @@water.\n@bookmark{cave | hole}"
+        ));
+        let (guide, story) = crate::read([source.as_str()]);
+        assert_eq!(
+            crate::bookmark_names(&guide, &story, &source),
+            ["intro", "lake", "cave"]
+        );
+        assert_eq!(targets(&source, "intro"), ["cave"]);
+        let source = insert_bookmark("@bookmark{a}A", "b", "B", None).unwrap();
+        assert_eq!(source, "@bookmark{a}A\n@bookmark{b}\nB\n");
+        assert_eq!(
+            insert_bookmark(SAMPLE, "hole", "", None),
+            Err(EditError::Taken { name: "hole" })
+        );
+        assert_eq!(
+            insert_bookmark(SAMPLE, "a@b", "", None),
+            Err(EditError::InvalidName { name: "a@b" })
+        );
+        assert_eq!(
+            insert_bookmark(SAMPLE, "lake", "", Some("sea")),
+            Err(EditError::Missing { name: "sea" })
+        );
+    }
+}
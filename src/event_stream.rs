@@ -0,0 +1,167 @@
+use crate::{Event, EventIter, OwnedEvent, Style};
+use alloc::{boxed::Box, collections::VecDeque, string::String};
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_core::{FusedStream, Stream};
+
+fn is_line_end(ch: char) -> bool {
+    ch == '\n' || ch == '\r'
+}
+
+/// Owned events of text received in chunks, see [`stream_events`]
+pub struct EventStream<S> {
+    chunks: Pin<Box<S>>,
+    /// Text received from the start of the last line with anything but whitespace,
+    /// as blank lines after it go into a single paragraph break with its terminator
+    partial: String,
+    /// Start of the last line in `partial`, which may be received only partly
+    line_start: usize,
+    /// Whether the last line has nothing but whitespace so far
+    is_line_blank: bool,
+    offset: usize,
+    /// Persistent style of scopes left open by lines received so far
+    scope: Style,
+    events: VecDeque<OwnedEvent>,
+    finished: bool,
+}
+
+impl<S: Stream<Item = String>> EventStream<S> {
+    #[must_use]
+    pub fn new(chunks: S) -> Self {
+        Self {
+            chunks: Box::pin(chunks),
+            partial: String::new(),
+            line_start: 0,
+            is_line_blank: true,
+            offset: 0,
+            scope: Style::REGULAR,
+            events: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    fn push_lines(&mut self, lines: &str) {
+        let mut events = EventIter::with_offset(lines, self.offset).in_scope(self.scope);
+        self.events.extend(events.by_ref().map(Event::into_owned));
+        self.scope = events.scope();
+        self.offset += lines.len();
+    }
+
+    /// Scans only the chunk, as lines received before are either blank
+    /// or the last one with anything but whitespace, which `partial` starts with
+    fn push_chunk(&mut self, chunk: &str) {
+        let scanned = self.partial.len();
+        self.partial.push_str(chunk);
+        let mut end = 0;
+        for (index, ch) in chunk.char_indices() {
+            if self.is_line_blank && !ch.is_whitespace() {
+                self.is_line_blank = false;
+                end = self.line_start;
+            }
+            if is_line_end(ch) {
+                self.line_start = scanned + index + ch.len_utf8();
+                self.is_line_blank = true;
+            }
+        }
+        if end != 0 {
+            let partial = self.partial.split_off(end);
+            let lines = core::mem::replace(&mut self.partial, partial);
+            self.push_lines(&lines);
+            self.line_start -= end;
+        }
+    }
+}
+
+/// Same as [`read_stream`](crate::read_stream), but for text received in chunks,
+/// such as messages of a socket. Chunks may end anywhere, as a line is parsed
+/// only after a line with anything but whitespace or the end of the stream is received,
+/// so that blank lines in between become a paragraph break.
+#[must_use]
+pub fn stream_events<S: Stream<Item = String>>(chunks: S) -> EventStream<S> {
+    EventStream::new(chunks)
+}
+
+impl<S: Stream<Item = String>> Stream for EventStream<S> {
+    type Item = OwnedEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(event) = this.events.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            if this.finished {
+                return Poll::Ready(None);
+            }
+            match this.chunks.as_mut().poll_next(cx) {
+                Poll::Ready(Some(chunk)) => this.push_chunk(&chunk),
+                Poll::Ready(None) => {
+                    this.finished = true;
+                    let lines = core::mem::take(&mut this.partial);
+                    if !lines.is_empty() {
+                        this.push_lines(&lines);
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.finished {
+            (self.events.len(), Some(self.events.len()))
+        } else {
+            (self.events.len(), None)
+        }
+    }
+}
+
+impl<S: Stream<Item = String>> FusedStream for EventStream<S> {
+    fn is_terminated(&self) -> bool {
+        self.finished && self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stream_events;
+    use crate::{Event, EventIter, OwnedEvent};
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn same_as_whole() {
+        const SAMPLE: &str =
+            "@bookmark{greet} Hello, @name!\r\n@style{b}@{Bold} @@ home @-- note\n\
+            ёжик @wave{ok}\n@style{i+}Italic\n@{open\nEnd.";
+        let chunks: Vec<String> = SAMPLE
+            .as_bytes()
+            .chunks(7)
+            .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+            .collect();
+        // the sample is made so that 7-byte chunks fall on char boundaries
+        assert_eq!(chunks.concat(), SAMPLE);
+        let streamed: Vec<_> = stream_events(tokio_stream::iter(chunks)).collect().await;
+        let whole: Vec<OwnedEvent> = EventIter::new(SAMPLE).map(Event::into_owned).collect();
+        assert_eq!(streamed, whole);
+    }
+
+    #[tokio::test]
+    async fn paragraphs() {
+        const SAMPLE: &str =
+            "\n \nOne\n\nTwo @wave\r\n  \n\t\n@style{b+}Three\r\rFour\r \r\nFive\n\n";
+        let whole: Vec<OwnedEvent> = EventIter::new(SAMPLE).map(Event::into_owned).collect();
+        assert!(whole
+            .iter()
+            .any(|event| matches!(event, OwnedEvent::ParagraphBreak(_))));
+        for size in 1..=8 {
+            let chunks = SAMPLE
+                .as_bytes()
+                .chunks(size)
+                .map(|chunk| String::from_utf8(chunk.to_vec()).expect("ascii sample"));
+            let streamed: Vec<_> = stream_events(tokio_stream::iter(chunks)).collect().await;
+            assert_eq!(streamed, whole, "chunks of {size} bytes");
+        }
+    }
+}
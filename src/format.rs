@@ -0,0 +1,229 @@
+use crate::core::{Event, Iter};
+use crate::{ReadOptions, Signal};
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+/// Options of formatting a source, see [`format()`]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct FormatOptions {
+    blank_lines: usize,
+}
+
+impl FormatOptions {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { blank_lines: 1 }
+    }
+
+    /// How many blank lines go before each bookmark that begins a line, which is 1 by default
+    #[must_use]
+    pub const fn blank_lines(self, blank_lines: usize) -> Self {
+        Self { blank_lines }
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Ranges of lines of `source` without their terminators, along with their raw events
+fn lines(source: &str) -> Vec<(Range<usize>, Vec<Event<'_>>)> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut events = Vec::new();
+    for event in Iter::raw(source) {
+        match event {
+            Event::Break(line_break) => {
+                lines.push((start..line_break.range.start, core::mem::take(&mut events)));
+                start = line_break.range.end;
+            }
+            event => events.push(event),
+        }
+    }
+    lines.push((start..source.len(), events));
+    lines
+}
+
+/// Adds the line of `source` in `range` to `out`, without trailing whitespace,
+/// nor indentation if it has nothing but signals, and with a single space
+/// between the signals that begin it and its first text
+fn push_line(out: &mut String, source: &str, range: Range<usize>, events: &[Event]) {
+    let line = &source[range.clone()];
+    // unclosed params run up to the end of the line, including its whitespace
+    let end = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Signal(signal) => Some(signal.span().end),
+            _ => None,
+        })
+        .fold(range.start + line.trim_end().len(), usize::max);
+    let is_signals_only = events.iter().all(|event| match event {
+        Event::Text { content, .. } => content.slice.trim().is_empty(),
+        _ => true,
+    });
+    let mut cursor = range.start;
+    if is_signals_only {
+        cursor = end - source[range.start..end].trim_start().len();
+    } else if matches!(events.first(), Some(Event::Signal(signal)) if signal.span().start == range.start)
+    {
+        // only the first text of such a line is trimmed, however much whitespace it starts with,
+        // while elsewhere whitespace is a part of the text
+        let first = events.iter().find_map(|event| match event {
+            Event::Text { content, .. } => Some(content),
+            _ => None,
+        });
+        if let Some(content) = first {
+            let gap = content.slice.len() - content.slice.trim_start().len();
+            if gap > 0 && content.range.start + gap < end && &content.slice[..gap] != " " {
+                out.push_str(&source[cursor..content.range.start]);
+                out.push(' ');
+                cursor = content.range.start + gap;
+            }
+        }
+    }
+    out.push_str(&source[cursor..end]);
+}
+
+/// Canonical form of a `source`, where each bookmark that begins a line starts a block of its own,
+/// with as many blank lines before it as `options` say, lines have no trailing whitespace,
+/// lines of nothing but signals have no indentation, and there is a single space
+/// between the signals that begin a line and its text, if there was any whitespace in between.
+/// Blank lines at either end are dropped, lines end with `\n`, and prose is left as it is otherwise.
+///
+/// Formatting is idempotent and keeps events the same, up to their ranges,
+/// the kind of line terminators, and breaks at either end and before bookmarks.
+#[must_use]
+pub fn format(source: &str, options: FormatOptions) -> String {
+    let read_options = ReadOptions::new();
+    let mut out = String::with_capacity(source.len());
+    let mut blank_lines = 0;
+    for (range, events) in lines(source) {
+        let line = &source[range.clone()];
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        let first = events.iter().find(|event| match event {
+            Event::Text { content, .. } => !content.slice.trim().is_empty(),
+            _ => true,
+        });
+        let is_bookmark = matches!(
+            first,
+            Some(Event::Signal(Signal::Call { prompt, .. })) if read_options.is_prompt(prompt.slice, "bookmark")
+        );
+        if !out.is_empty() {
+            let count = if is_bookmark {
+                options.blank_lines
+            } else {
+                blank_lines
+            };
+            for _ in 0..count {
+                out.push('\n');
+            }
+        }
+        blank_lines = 0;
+        push_line(&mut out, source, range, &events);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format, FormatOptions};
+    use crate::{Event, EventIter, Signal};
+    use alloc::vec::Vec;
+    use quickcheck::quickcheck;
+
+    const PIECES: [&str; 16] = [
+        "@",
+        "@@",
+        "@--",
+        "@bookmark{a}",
+        "{",
+        "}",
+        "(",
+        " ",
+        "\t",
+        "\n",
+        "\r\n",
+        "\r",
+        "bookmark",
+        "wave",
+        "hi",
+        "ё",
+    ];
+
+    fn is_break(event: &Event) -> bool {
+        matches!(event, Event::Break(_) | Event::ParagraphBreak(_))
+    }
+
+    /// Whether events of both texts are the same, up to their ranges, slices of breaks,
+    /// and breaks at either end and before bookmarks
+    fn is_same_story(left: &str, right: &str) -> bool {
+        fn events(text: &str) -> Vec<Event<'_>> {
+            let events: Vec<_> = EventIter::new(text).collect();
+            let mut kept = Vec::new();
+            for (index, event) in events.iter().enumerate() {
+                if is_break(event) {
+                    let next = events[index + 1..].iter().find(|next| !is_break(next));
+                    let is_bookmark_next = matches!(
+                        next,
+                        Some(Event::Signal(Signal::Call { prompt, .. })) if prompt.slice == "bookmark"
+                    );
+                    if kept.is_empty() || next.is_none() || is_bookmark_next {
+                        continue;
+                    }
+                }
+                kept.push(event.clone());
+            }
+            kept
+        }
+        let (left, right) = (events(left), events(right));
+        left.len() == right.len()
+            && left.iter().zip(&right).all(|(left, right)| {
+                if is_break(left) {
+                    core::mem::discriminant(left) == core::mem::discriminant(right)
+                } else {
+                    left.eq_ignoring_ranges(right)
+                }
+            })
+    }
+
+    #[test]
+    fn canonical() {
+        const SAMPLE: &str = "\n@bookmark{greet}\t Hello,   @wave\tyou!  \r\n\
+            \n\n  Indented   prose @@home\n@choice{bye}  Bye  \n   @bookmark{bye}\n\
+            Well,\n\n\n   @style{b}{farewell}   \n\n";
+        let formatted = format(SAMPLE, FormatOptions::new());
+        assert_eq!(
+            formatted,
+            "@bookmark{greet} Hello,   @wave\tyou!\n\n\n  Indented   prose @@home\n\
+            @choice{bye} Bye\n\n@bookmark{bye}\nWell,\n\n\n@style{b}{farewell}\n"
+        );
+        assert_eq!(format(&formatted, FormatOptions::new()), formatted);
+        assert!(is_same_story(SAMPLE, &formatted));
+        assert_eq!(
+            format(
+                "@bookmark{a}A\n\n\n@bookmark{b}B",
+                FormatOptions::new().blank_lines(0)
+            ),
+            "@bookmark{a}A\n@bookmark{b}B\n"
+        );
+        assert_eq!(format("@{open  \n  ", FormatOptions::new()), "@{open  \n");
+        assert_eq!(format(" \n\t", FormatOptions::new()), "");
+    }
+
+    quickcheck! {
+        fn arbitrary_format(indices: Vec<u8>) -> bool {
+            let text: String = indices
+                .into_iter()
+                .map(|index| PIECES[usize::from(index) % PIECES.len()])
+                .collect();
+            let formatted = format(&text, FormatOptions::new());
+            format(&formatted, FormatOptions::new()) == formatted && is_same_story(&text, &formatted)
+        }
+    }
+}
@@ -1,135 +1,2125 @@
-use crate::core::{Event, Signal, StrRange};
-use petgraph::graph::{DiGraph, NodeIndex};
-use std::{
-    collections::{hash_map, HashMap},
+use crate::core::{Diagnostic, Event, LineIndex, ParseConfig, Signal, StrRange};
+use crate::{
+    compose::{push_meta, push_param, push_tags},
+    EventIter,
+};
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use core::{
+    fmt,
+    hash::Hash,
     mem,
-    ops::Range,
+    ops::{self, Range},
+};
+use hashbrown::HashMap;
+use petgraph::{
+    algo::tarjan_scc,
+    data::Build,
+    graph::{DiGraph, EdgeIndex, NodeIndex},
+    stable_graph::StableDiGraph,
+    visit::{Dfs, EdgeRef, GraphBase},
 };
+#[cfg(feature = "std")]
+use std::io::{self, BufRead};
+
+/// Options of reading a story, see [`read_with_options`]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "options are independent switches, set one by one with builder methods"
+)]
+pub struct ReadOptions {
+    trim_names: bool,
+    case_insensitive_prompts: bool,
+    create_missing_bookmarks: bool,
+    normalize_names: bool,
+    implicit_start: Option<&'static str>,
+}
+
+impl ReadOptions {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            trim_names: true,
+            case_insensitive_prompts: false,
+            create_missing_bookmarks: false,
+            normalize_names: false,
+            implicit_start: None,
+        }
+    }
+
+    /// Whether to trim whitespace around `bookmark` and `choice` names, which is the default,
+    /// so that `@choice{intro}` leads to `@bookmark{ intro }`
+    #[must_use]
+    pub const fn trim_names(self, trim_names: bool) -> Self {
+        Self { trim_names, ..self }
+    }
+
+    /// Whether to compare prompts ignoring ASCII case, so that `@Bookmark{intro}`
+    /// and `@STYLE{b}` work too, while params (e.g. names) are still compared as they are
+    #[must_use]
+    pub const fn case_insensitive_prompts(self, case_insensitive_prompts: bool) -> Self {
+        Self {
+            case_insensitive_prompts,
+            ..self
+        }
+    }
+
+    /// Whether to add an empty node for each name that choices lead to, but no bookmark has,
+    /// e.g. to prototype a story, instead of leaving those choices out
+    #[must_use]
+    pub const fn create_missing_bookmarks(self, create_missing_bookmarks: bool) -> Self {
+        Self {
+            create_missing_bookmarks,
+            ..self
+        }
+    }
+
+    /// Whether to match choices with bookmarks by names turned into slugs with [`normalize_name`],
+    /// so that `@choice{the-cave}` leads to `@bookmark{The Cave}`, while the guide keeps names as written.
+    /// Functions that take bookmarks by name, e.g. [`path_between`] or [`unreachable_from`],
+    /// find them by slugs too, if there are no such names as written.
+    #[must_use]
+    pub const fn normalize_names(self, normalize_names: bool) -> Self {
+        Self {
+            normalize_names,
+            ..self
+        }
+    }
+
+    /// Name of a node to add for the text before the first bookmark, e.g. `Some("start")`,
+    /// so that it isn't left out along with its choices, which is `None` by default.
+    /// There is no such node if the text goes right to the first bookmark, or is only whitespace.
+    #[must_use]
+    pub const fn implicit_start(self, implicit_start: Option<&'static str>) -> Self {
+        Self {
+            implicit_start,
+            ..self
+        }
+    }
+
+    /// Whether `prompt` is the same as `name`, given the case sensitivity
+    pub(crate) fn is_prompt(&self, prompt: &str, name: &str) -> bool {
+        if self.case_insensitive_prompts {
+            prompt.eq_ignore_ascii_case(name)
+        } else {
+            prompt == name
+        }
+    }
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Something off about the text of a story, which doesn't stop it from being read,
+/// where `N` is a bookmark name, see [`read_checked`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ReadWarning<N> {
+    /// `@bookmark` with a name that is taken already. The first one wins,
+    /// while the duplicate is left out of the story along with its text and choices.
+    /// Ranges are spans of both signals.
+    DuplicateBookmark {
+        name: N,
+        first_range: Range<usize>,
+        duplicate_range: Range<usize>,
+    },
+    /// `@choice` leading to a name that no bookmark has, which is left out of the story,
+    /// unless [`ReadOptions::create_missing_bookmarks`] is set.
+    /// Range is the span of the signal.
+    DanglingChoice {
+        from_bookmark: N,
+        target_name: N,
+        range: Range<usize>,
+    },
+    /// `@choice` before the first bookmark, which is left out of the story,
+    /// as there is no node for it to lead from, unless [`ReadOptions::implicit_start`] is set.
+    /// Range is the span of the signal.
+    ChoiceOutsideBookmark { target_name: N, range: Range<usize> },
+    /// `@bookmark` with a name that is empty, or only whitespace when trimming names,
+    /// which is still read. Range is the span of the signal.
+    EmptyBookmarkName { range: Range<usize> },
+    /// Alias of a `@bookmark`, as in `@bookmark{intro | start}`, that another bookmark
+    /// has as its name or alias already, which is left out. Ranges are spans of both signals.
+    TakenAlias {
+        alias: N,
+        first_range: Range<usize>,
+        duplicate_range: Range<usize>,
+    },
+    /// `@bookmark` with a name that is not taken, but turns into the same slug
+    /// as `first_name` when [`ReadOptions::normalize_names`] is set, which is left out
+    /// the same as a duplicate. Ranges are spans of both signals.
+    CollidingName {
+        name: N,
+        first_name: N,
+        first_range: Range<usize>,
+        duplicate_range: Range<usize>,
+    },
+    /// `@choice` with a `weight` after `*` that is not a whole number,
+    /// which is still read with the default weight of 1. Range is that of the weight.
+    InvalidChoiceWeight {
+        target_name: N,
+        weight: N,
+        range: Range<usize>,
+    },
+    /// `@choice` with nothing but whitespace after an unquoted `?`, as in `@choice{hall | Wait?}`,
+    /// which is read as unconditional. Range is the span of the signal.
+    EmptyChoiceCondition { target_name: N, range: Range<usize> },
+    /// `@choice` with nothing but whitespace after `|`, as in `@choice{hall |}`,
+    /// which is read as having no label. Range is the span of the signal.
+    EmptyChoiceLabel { target_name: N, range: Range<usize> },
+    /// `@bookmark` or `@choice` followed right away by more params in brackets,
+    /// as in `@choice{hall}(quietly) Leave.`, which are left out of its text.
+    /// Range is the span of the signal, including those params.
+    IgnoredParams { target_name: N, range: Range<usize> },
+}
+
+/// Reason for [`read_strict`] to fail, with `position` being zero-based line and column
+/// of the range start, see [`LineIndex::position`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum GraphError<'a> {
+    /// See [`ReadWarning::DuplicateBookmark`]
+    DuplicateBookmark {
+        name: &'a str,
+        first_range: Range<usize>,
+        duplicate_range: Range<usize>,
+        position: (usize, usize),
+    },
+    /// See [`ReadWarning::DanglingChoice`]
+    DanglingChoice {
+        from_bookmark: &'a str,
+        target_name: &'a str,
+        range: Range<usize>,
+        position: (usize, usize),
+    },
+    /// See [`ReadWarning::ChoiceOutsideBookmark`]
+    ChoiceOutsideBookmark {
+        target_name: &'a str,
+        range: Range<usize>,
+        position: (usize, usize),
+    },
+    /// See [`ReadWarning::EmptyBookmarkName`]
+    EmptyBookmarkName {
+        range: Range<usize>,
+        position: (usize, usize),
+    },
+    /// See [`ReadWarning::TakenAlias`]
+    TakenAlias {
+        alias: &'a str,
+        first_range: Range<usize>,
+        duplicate_range: Range<usize>,
+        position: (usize, usize),
+    },
+    /// See [`ReadWarning::CollidingName`]
+    CollidingName {
+        name: &'a str,
+        first_name: &'a str,
+        first_range: Range<usize>,
+        duplicate_range: Range<usize>,
+        position: (usize, usize),
+    },
+    /// See [`ReadWarning::InvalidChoiceWeight`]
+    InvalidChoiceWeight {
+        target_name: &'a str,
+        weight: &'a str,
+        range: Range<usize>,
+        position: (usize, usize),
+    },
+    /// See [`ReadWarning::EmptyChoiceCondition`]
+    EmptyChoiceCondition {
+        target_name: &'a str,
+        range: Range<usize>,
+        position: (usize, usize),
+    },
+    /// See [`ReadWarning::EmptyChoiceLabel`]
+    EmptyChoiceLabel {
+        target_name: &'a str,
+        range: Range<usize>,
+        position: (usize, usize),
+    },
+    /// See [`ReadWarning::IgnoredParams`]
+    IgnoredParams {
+        target_name: &'a str,
+        range: Range<usize>,
+        position: (usize, usize),
+    },
+    /// See [`Diagnostic::UnclosedParam`]
+    UnclosedParam {
+        bracket: StrRange<'a>,
+        position: (usize, usize),
+    },
+}
+
+impl<'a> GraphError<'a> {
+    pub(crate) fn from_warning(warning: ReadWarning<&'a str>, index: &LineIndex) -> Self {
+        match warning {
+            ReadWarning::DuplicateBookmark {
+                name,
+                first_range,
+                duplicate_range,
+            } => Self::DuplicateBookmark {
+                name,
+                first_range,
+                position: index.position(duplicate_range.start),
+                duplicate_range,
+            },
+            ReadWarning::DanglingChoice {
+                from_bookmark,
+                target_name,
+                range,
+            } => Self::DanglingChoice {
+                from_bookmark,
+                target_name,
+                position: index.position(range.start),
+                range,
+            },
+            ReadWarning::ChoiceOutsideBookmark { target_name, range } => {
+                Self::ChoiceOutsideBookmark {
+                    target_name,
+                    position: index.position(range.start),
+                    range,
+                }
+            }
+            ReadWarning::EmptyBookmarkName { range } => Self::EmptyBookmarkName {
+                position: index.position(range.start),
+                range,
+            },
+            ReadWarning::TakenAlias {
+                alias,
+                first_range,
+                duplicate_range,
+            } => Self::TakenAlias {
+                alias,
+                first_range,
+                position: index.position(duplicate_range.start),
+                duplicate_range,
+            },
+            ReadWarning::CollidingName {
+                name,
+                first_name,
+                first_range,
+                duplicate_range,
+            } => Self::CollidingName {
+                name,
+                first_name,
+                first_range,
+                position: index.position(duplicate_range.start),
+                duplicate_range,
+            },
+            ReadWarning::InvalidChoiceWeight {
+                target_name,
+                weight,
+                range,
+            } => Self::InvalidChoiceWeight {
+                target_name,
+                weight,
+                position: index.position(range.start),
+                range,
+            },
+            ReadWarning::EmptyChoiceCondition { target_name, range } => {
+                Self::EmptyChoiceCondition {
+                    target_name,
+                    position: index.position(range.start),
+                    range,
+                }
+            }
+            ReadWarning::EmptyChoiceLabel { target_name, range } => Self::EmptyChoiceLabel {
+                target_name,
+                position: index.position(range.start),
+                range,
+            },
+            ReadWarning::IgnoredParams { target_name, range } => Self::IgnoredParams {
+                target_name,
+                position: index.position(range.start),
+                range,
+            },
+        }
+    }
+
+    /// Zero-based line and column of where the error is
+    #[must_use]
+    pub fn position(&self) -> (usize, usize) {
+        match self {
+            Self::DuplicateBookmark { position, .. }
+            | Self::DanglingChoice { position, .. }
+            | Self::ChoiceOutsideBookmark { position, .. }
+            | Self::EmptyBookmarkName { position, .. }
+            | Self::TakenAlias { position, .. }
+            | Self::CollidingName { position, .. }
+            | Self::InvalidChoiceWeight { position, .. }
+            | Self::EmptyChoiceCondition { position, .. }
+            | Self::EmptyChoiceLabel { position, .. }
+            | Self::IgnoredParams { position, .. }
+            | Self::UnclosedParam { position, .. } => *position,
+        }
+    }
+
+    /// Range that the error is about, which is the span of the duplicate signal for duplicates
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            Self::DuplicateBookmark {
+                duplicate_range, ..
+            }
+            | Self::TakenAlias {
+                duplicate_range, ..
+            }
+            | Self::CollidingName {
+                duplicate_range, ..
+            } => duplicate_range.clone(),
+            Self::DanglingChoice { range, .. }
+            | Self::ChoiceOutsideBookmark { range, .. }
+            | Self::EmptyBookmarkName { range, .. }
+            | Self::InvalidChoiceWeight { range, .. }
+            | Self::EmptyChoiceCondition { range, .. }
+            | Self::EmptyChoiceLabel { range, .. }
+            | Self::IgnoredParams { range, .. } => range.clone(),
+            Self::UnclosedParam { bracket, .. } => bracket.range.clone(),
+        }
+    }
+
+    /// What the error is, without where it is
+    pub(crate) fn message(&self) -> String {
+        match self {
+            Self::DuplicateBookmark { name, .. } => {
+                format!("bookmark `{name}` is defined more than once")
+            }
+            Self::DanglingChoice {
+                from_bookmark,
+                target_name,
+                ..
+            } => {
+                format!("choice under `{from_bookmark}` leads to missing bookmark `{target_name}`")
+            }
+            Self::ChoiceOutsideBookmark { target_name, .. } => {
+                format!("choice of `{target_name}` is before any bookmark")
+            }
+            Self::EmptyBookmarkName { .. } => "bookmark has an empty name".to_owned(),
+            Self::TakenAlias { alias, .. } => {
+                format!("alias `{alias}` is taken by another bookmark")
+            }
+            Self::CollidingName {
+                name, first_name, ..
+            } => format!("bookmark `{name}` has the same slug as `{first_name}`"),
+            Self::InvalidChoiceWeight {
+                target_name,
+                weight,
+                ..
+            } => format!(
+                "choice of `{target_name}` has weight `{weight}`, which is not a whole number"
+            ),
+            Self::EmptyChoiceCondition { target_name, .. } => {
+                format!("choice of `{target_name}` has an empty condition after `?`")
+            }
+            Self::EmptyChoiceLabel { target_name, .. } => {
+                format!("choice of `{target_name}` has an empty label after `|`")
+            }
+            Self::IgnoredParams { target_name, .. } => {
+                format!("params after `{target_name}` are left out of its text")
+            }
+            Self::UnclosedParam { bracket, .. } => format!("unclosed `{}`", bracket.slice),
+        }
+    }
+}
 
-struct Choice<'a> {
+impl<'a> fmt::Display for GraphError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (line, column) = self.position();
+        write!(f, "{}:{}: {}", line + 1, column + 1, self.message())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> std::error::Error for GraphError<'a> {}
+
+struct Choice<K, W> {
     from_anchor: NodeIndex,
-    to_anchor: &'a str,
-    range: Range<usize>,
+    to_anchor: K,
+    /// Name of the bookmark made for the choice if it's missing
+    to_anchor_name: W,
+    span: Range<usize>,
+    weight: ChoiceWeight<W>,
 }
 
-// NOTE: can add `2` to  signal params ends and sub `1` from signal prompt starts,
-//       because braces and signal chars in `texal` are always ascii
-fn node_pass<'a>(
-    range_graph: &mut DiGraph<Range<usize>, Range<usize>>,
-    bookmark_map: &mut HashMap<&'a str, NodeIndex>,
-    choice_map: &mut Vec<Choice<'a>>,
-    iter: impl IntoIterator<Item = Event<'a>>,
-) {
-    let mut current_end = 0;
-    let mut last_bookmark_index = NodeIndex::default();
-    let mut unclosed_param = None;
-    let mut is_prev_bookmark = false;
-    for event in iter {
+/// Param of a `bookmark` or `choice` signal along with the span of the signal,
+/// where only a bookmark may have aliases, and only a choice may have a label,
+/// a condition, a weight and the `once` flag
+struct Param<K, W> {
+    name: K,
+    name_range: W,
+    aliases: Vec<K>,
+    span: Range<usize>,
+    /// Where the text goes from, which is past `meta` signals of a bookmark
+    text_start: usize,
+    label: Option<W>,
+    condition: Option<W>,
+    weight: u32,
+    once: bool,
+}
+
+impl<K, W> Param<K, W> {
+    /// Same param with the name, label and condition turned into other weights
+    fn map<V>(self, f: impl Fn(W) -> V) -> Param<K, V> {
+        Param {
+            name: self.name,
+            name_range: f(self.name_range),
+            aliases: self.aliases,
+            span: self.span,
+            text_start: self.text_start,
+            label: self.label.map(&f),
+            condition: self.condition.map(&f),
+            weight: self.weight,
+            once: self.once,
+        }
+    }
+}
+
+/// Parts of the param of a `bookmark` or `choice` signal, with the name and aliases not trimmed
+pub(crate) struct ParamParts<'a> {
+    pub(crate) name: StrRange<'a>,
+    pub(crate) aliases: Vec<StrRange<'a>>,
+    label: Option<StrRange<'a>>,
+    condition: Option<StrRange<'a>>,
+    weight: Option<StrRange<'a>>,
+    once: bool,
+}
+
+/// Splits the param of a `bookmark` into its name and aliases after `|`,
+/// or that of a `choice` into its target and the rest, see [`ChoiceWeight`]
+pub(crate) fn split_param<'a>(param: &StrRange<'a>, is_bookmark: bool) -> ParamParts<'a> {
+    let mut aliases = Vec::new();
+    let (param, label, condition) = if is_bookmark {
+        let mut param = param.clone();
+        while let Some((part, rest)) = param.split_unquoted(LABEL_SEPARATOR) {
+            aliases.push(part);
+            param = rest;
+        }
+        aliases.push(param);
+        (aliases.remove(0), None, None)
+    } else {
+        let (param, condition) = match param.split_unquoted(CONDITION_SEPARATOR) {
+            Some((param, condition)) => (param, Some(condition.trimmed())),
+            None => (param.clone(), None),
+        };
+        match param.split_unquoted(LABEL_SEPARATOR) {
+            Some((name, label)) => (name, Some(label.trimmed().unquoted()), condition),
+            None => (param, None, condition),
+        }
+    };
+    let (param, weight) = match param.split_unquoted(WEIGHT_SEPARATOR) {
+        Some((name, weight)) if !is_bookmark => (name, Some(weight.trimmed())),
+        _ => (param, None),
+    };
+    let (name, once) = match param.slice.trim_end().strip_suffix(ONCE_FLAG) {
+        Some(name) if !is_bookmark && name.ends_with(char::is_whitespace) => {
+            let name = param.slice(0..name.len()).expect("start of the param");
+            (name, true)
+        }
+        _ => (param, false),
+    };
+    ParamParts {
+        name,
+        aliases,
+        label,
+        condition,
+        weight,
+        once,
+    }
+}
+
+/// Builds a story out of events pushed one by one, where `K` is a bookmark name,
+/// `W` is a weight made out of a text range, e.g. of a node or of a choice label,
+/// and `G` is the graph to build. Choices are kept aside until all of the bookmarks are known.
+struct NodePass<K, W, G> {
+    graph: G,
+    bookmark_map: HashMap<K, NodeIndex>,
+    /// Slugs of names and aliases, which are only kept when normalizing names
+    slug_map: HashMap<String, NodeIndex>,
+    choice_map: Vec<Choice<K, W>>,
+    current_end: usize,
+    /// Node that choices lead from, which is none before the first bookmark or after a duplicate
+    last_bookmark_index: Option<NodeIndex>,
+    /// Param of the last `bookmark` or `choice`
+    unclosed_param: Option<Param<K, W>>,
+    is_prev_bookmark: bool,
+    /// Keys and values of `meta` signals of the last bookmark, which may only go before its text
+    meta: Vec<(K, W, Option<W>)>,
+    /// Args of `tag` signals of the last bookmark, same as `meta`
+    tags: Vec<(K, W)>,
+    is_before_text: bool,
+    /// Names and spans of `bookmark` signals, indexed by their nodes
+    bookmarks: Vec<(K, Range<usize>)>,
+    warnings: Vec<ReadWarning<K>>,
+    options: ReadOptions,
+}
+
+impl<K, W, G> NodePass<K, W, G>
+where
+    K: Hash + Eq + Clone + AsRef<str>,
+    G: Build<NodeWeight = BookmarkWeight<W>, EdgeWeight = ChoiceWeight<W>>
+        + GraphBase<NodeId = NodeIndex>
+        + Default,
+{
+    fn new(options: ReadOptions) -> Self {
+        Self {
+            graph: G::default(),
+            bookmark_map: HashMap::new(),
+            slug_map: HashMap::new(),
+            choice_map: Vec::new(),
+            current_end: 0,
+            last_bookmark_index: None,
+            unclosed_param: None,
+            is_prev_bookmark: false,
+            meta: Vec::new(),
+            tags: Vec::new(),
+            is_before_text: false,
+            bookmarks: Vec::new(),
+            warnings: Vec::new(),
+            options,
+        }
+    }
+
+    /// Name out of the param of a signal, along with the label after `|` of a choice,
+    /// its condition after `?`, its weight after `*` and whether it has the `!once` flag,
+    /// warning if a bookmark has no name, a choice has a weight that is not a number
+    /// or the signal has extra params, which are left out of the text
+    fn param<'a>(
+        &mut self,
+        param: &StrRange<'a>,
+        extra_params: &[StrRange<'a>],
+        span: Range<usize>,
+        is_bookmark: bool,
+        key: impl Fn(&'a str) -> K,
+    ) -> Param<K, StrRange<'a>> {
+        let ParamParts {
+            name: param,
+            aliases,
+            label,
+            condition,
+            weight,
+            once,
+        } = split_param(param, is_bookmark);
+        let trim_names = self.options.trim_names;
+        let trim = |param: &StrRange<'a>| {
+            if trim_names {
+                param.trimmed()
+            } else {
+                param.clone()
+            }
+        };
+        let name_range = trim(&param);
+        let name = name_range.slice;
+        if is_bookmark && name.is_empty() {
+            self.warnings.push(ReadWarning::EmptyBookmarkName {
+                range: span.clone(),
+            });
+        }
+        if !extra_params.is_empty() {
+            self.warnings.push(ReadWarning::IgnoredParams {
+                target_name: key(name),
+                range: span.clone(),
+            });
+        }
+        let label = label.filter(|label| {
+            let is_empty = label.slice.is_empty();
+            if is_empty {
+                self.warnings.push(ReadWarning::EmptyChoiceLabel {
+                    target_name: key(name),
+                    range: span.clone(),
+                });
+            }
+            !is_empty
+        });
+        let condition = condition.filter(|condition| {
+            let is_empty = condition.slice.is_empty();
+            if is_empty {
+                self.warnings.push(ReadWarning::EmptyChoiceCondition {
+                    target_name: key(name),
+                    range: span.clone(),
+                });
+            }
+            !is_empty
+        });
+        let weight = match weight.map(|weight| (weight.slice.parse(), weight)) {
+            None => DEFAULT_CHOICE_WEIGHT,
+            Some((Ok(weight), _)) => weight,
+            Some((Err(_), weight)) => {
+                self.warnings.push(ReadWarning::InvalidChoiceWeight {
+                    target_name: key(name),
+                    weight: key(weight.slice),
+                    range: weight.range,
+                });
+                DEFAULT_CHOICE_WEIGHT
+            }
+        };
+        Param {
+            name: key(name),
+            name_range,
+            aliases: aliases
+                .iter()
+                .map(trim)
+                .filter(|alias| !alias.slice.is_empty())
+                .map(|alias| key(alias.slice))
+                .collect(),
+            text_start: span.end,
+            span,
+            label,
+            condition,
+            weight,
+            once,
+        }
+    }
+
+    /// Node of the bookmark that `name` leads to, if any, comparing slugs when normalizing names
+    fn find(&self, name: &K) -> Option<NodeIndex> {
+        if self.options.normalize_names {
+            self.slug_map.get(&normalize_name(name.as_ref())).copied()
+        } else {
+            self.bookmark_map.get(name).copied()
+        }
+    }
+
+    fn insert(&mut self, name: K, index: NodeIndex) {
+        if self.options.normalize_names {
+            self.slug_map.insert(normalize_name(name.as_ref()), index);
+        }
+        self.bookmark_map.insert(name, index);
+    }
+
+    /// Start of the text that is going to be weighted next, if any
+    #[cfg(feature = "std")]
+    fn pending_start(&self) -> Option<usize> {
+        self.unclosed_param.as_ref().map(|param| param.text_start)
+    }
+
+    fn close(
+        &mut self,
+        Param {
+            name,
+            name_range,
+            aliases,
+            span,
+            label,
+            condition,
+            weight: choice_weight,
+            once,
+            ..
+        }: Param<K, W>,
+        range: Range<usize>,
+        weight: impl Fn(Range<usize>) -> W,
+    ) {
+        let meta = mem::take(&mut self.meta);
+        let tags = mem::take(&mut self.tags);
+        if self.is_prev_bookmark {
+            if let Some(first_index) = self.find(&name) {
+                let (first_name, first_span) = &self.bookmarks[first_index.index()];
+                self.warnings
+                    .push(if self.bookmark_map.contains_key(&name) {
+                        ReadWarning::DuplicateBookmark {
+                            name,
+                            first_range: first_span.clone(),
+                            duplicate_range: span,
+                        }
+                    } else {
+                        ReadWarning::CollidingName {
+                            name,
+                            first_name: first_name.clone(),
+                            first_range: first_span.clone(),
+                            duplicate_range: span,
+                        }
+                    });
+                self.last_bookmark_index = None;
+            } else {
+                let index = self.graph.add_node(BookmarkWeight {
+                    name: name_range,
+                    meta: meta
+                        .into_iter()
+                        .map(|(_, key, value)| (key, value))
+                        .collect(),
+                    tags: tags.into_iter().map(|(_, tag)| tag).collect(),
+                    text: weight(range),
+                });
+                self.bookmarks.push((name.clone(), span.clone()));
+                self.last_bookmark_index = Some(index);
+                self.insert(name, index);
+                for alias in aliases {
+                    match self.find(&alias) {
+                        Some(first_index) if first_index == index => (),
+                        Some(first_index) => {
+                            let (_, first_span) = &self.bookmarks[first_index.index()];
+                            self.warnings.push(ReadWarning::TakenAlias {
+                                alias,
+                                first_range: first_span.clone(),
+                                duplicate_range: span.clone(),
+                            });
+                        }
+                        None => self.insert(alias, index),
+                    }
+                }
+            }
+        } else if let Some(from_anchor) = self.last_bookmark_index {
+            self.choice_map.push(Choice {
+                from_anchor,
+                to_anchor: name,
+                to_anchor_name: name_range,
+                span,
+                weight: ChoiceWeight {
+                    label,
+                    condition,
+                    weight: choice_weight,
+                    once,
+                    text: weight(range),
+                },
+            });
+        }
+    }
+
+    /// Merges keys and values of a `meta` signal into those of the last bookmark
+    fn add_meta<'a>(
+        &mut self,
+        param: &StrRange<'a>,
+        key: impl Fn(&'a str) -> K,
+        weight: impl Fn(Range<usize>) -> W,
+    ) {
+        for (meta_key, value) in param.pairs() {
+            let value = value.map(|value| weight(value.range));
+            let name = key(meta_key.slice);
+            match self
+                .meta
+                .iter_mut()
+                .find(|(prev_name, ..)| *prev_name == name)
+            {
+                Some((.., prev_value)) => *prev_value = value,
+                None => self.meta.push((name, weight(meta_key.range), value)),
+            }
+        }
+    }
+
+    /// Adds args of a `tag` signal to the tags of the last bookmark, unless they are empty or there
+    fn add_tags<'a>(
+        &mut self,
+        param: &StrRange<'a>,
+        key: impl Fn(&'a str) -> K,
+        weight: impl Fn(Range<usize>) -> W,
+    ) {
+        for tag in param.args().filter(|tag| !tag.slice.is_empty()) {
+            let name = key(tag.slice);
+            if self.tags.iter().all(|(prev_name, _)| *prev_name != name) {
+                self.tags.push((name, weight(tag.range)));
+            }
+        }
+    }
+
+    fn push<'a>(
+        &mut self,
+        event: Event<'a>,
+        key: impl Fn(&'a str) -> K,
+        weight: impl Fn(Range<usize>) -> W,
+    ) {
+        let options = self.options;
+        let is_bookmark = |prompt: &StrRange| options.is_prompt(prompt.slice, "bookmark");
+        let is_choice = |prompt: &StrRange| options.is_prompt(prompt.slice, "choice");
+        let is_meta = |prompt: &StrRange| options.is_prompt(prompt.slice, "meta");
+        let is_tag = |prompt: &StrRange| options.is_prompt(prompt.slice, "tag");
+        if let Some(start) = options.implicit_start {
+            let is_start = match &event {
+                Event::Signal(Signal::Call { prompt, .. }) => !is_bookmark(prompt),
+                Event::Signal(_) => true,
+                Event::Text { content, .. } => !content.slice.trim().is_empty(),
+                _ => false,
+            };
+            if is_start && self.bookmarks.is_empty() && self.unclosed_param.is_none() {
+                self.unclosed_param = Some(Param {
+                    name: key(start),
+                    name_range: weight(0..0),
+                    aliases: Vec::new(),
+                    span: 0..0,
+                    text_start: 0,
+                    label: None,
+                    condition: None,
+                    weight: DEFAULT_CHOICE_WEIGHT,
+                    once: false,
+                });
+                self.is_prev_bookmark = true;
+                self.is_before_text = true;
+            }
+        }
+        let is_before_text = mem::replace(&mut self.is_before_text, false);
         match event {
             Event::Signal(Signal::Call {
-                prompt:
-                    StrRange {
-                        slice: next_prompt_slice @ ("bookmark" | "choice"),
-                        ..
-                    },
+                prompt,
                 param,
-            }) if unclosed_param.is_none() => {
-                if next_prompt_slice == "bookmark" {
-                    unclosed_param = Some(param);
-                    is_prev_bookmark = true;
+                extra_params,
+                span,
+            }) if self.unclosed_param.is_none() && (is_bookmark(&prompt) || is_choice(&prompt)) => {
+                self.is_prev_bookmark = is_bookmark(&prompt);
+                let param = self.param(&param, &extra_params, span, self.is_prev_bookmark, key);
+                if self.is_prev_bookmark {
+                    self.unclosed_param = Some(param.map(|range| weight(range.range)));
+                    self.is_before_text = true;
+                } else {
+                    self.warnings.push(ReadWarning::ChoiceOutsideBookmark {
+                        target_name: param.name,
+                        range: param.span,
+                    });
                 }
             }
             Event::Signal(Signal::Call {
-                prompt:
-                    StrRange {
-                        slice: next_prompt_slice @ ("bookmark" | "choice"),
-                        range: next_prompt_range,
-                    },
+                prompt: next_prompt,
                 param: next_param,
-            }) => {
-                let prev_param = unclosed_param.replace(next_param.clone()).unwrap();
-                if mem::replace(&mut is_prev_bookmark, next_prompt_slice == "bookmark") {
-                    match bookmark_map.entry(prev_param.slice) {
-                        hash_map::Entry::Occupied(_) => (),
-                        hash_map::Entry::Vacant(anchor_entry) => {
-                            last_bookmark_index = range_graph
-                                .add_node(prev_param.range.end + 1..next_prompt_range.start - 1);
-                            anchor_entry.insert(last_bookmark_index);
-                        }
-                    }
+                extra_params: next_extra_params,
+                span: next_span,
+            }) if is_bookmark(&next_prompt) || is_choice(&next_prompt) => {
+                let next_start = next_span.start;
+                let next = self
+                    .param(
+                        &next_param,
+                        &next_extra_params,
+                        next_span,
+                        is_bookmark(&next_prompt),
+                        key,
+                    )
+                    .map(|range| weight(range.range));
+                let prev = self.unclosed_param.replace(next).unwrap();
+                let prev_end = prev.text_start;
+                self.close(prev, prev_end..next_start, weight);
+                self.is_prev_bookmark = is_bookmark(&next_prompt);
+                self.is_before_text = self.is_prev_bookmark;
+            }
+            Event::Signal(Signal::Call {
+                prompt,
+                param,
+                span,
+                ..
+            }) if is_before_text && (is_meta(&prompt) || is_tag(&prompt)) => {
+                if is_tag(&prompt) {
+                    self.add_tags(&param, key, weight);
                 } else {
-                    choice_map.push(Choice {
-                        from_anchor: last_bookmark_index,
-                        to_anchor: prev_param.slice,
-                        range: prev_param.range.end + 1..next_prompt_range.start - 1,
+                    self.add_meta(&param, key, weight);
+                }
+                if let Some(param) = &mut self.unclosed_param {
+                    param.text_start = span.end;
+                }
+                self.current_end = span.end;
+                self.is_before_text = true;
+            }
+            Event::Signal(signal) => self.current_end = signal.span().end,
+            Event::Text {
+                content: StrRange { slice, range },
+                ..
+            } => {
+                self.current_end = range.end;
+                self.is_before_text = is_before_text && slice.trim().is_empty();
+            }
+            _ => self.is_before_text = is_before_text,
+        }
+    }
+
+    /// Closes the last bookmark or choice at the end of one of several separate texts,
+    /// so that the next text starts anew, with ranges relative to it
+    fn end_text(&mut self, weight: impl Fn(Range<usize>) -> W) {
+        if let Some(prev) = self.unclosed_param.take() {
+            let prev_end = prev.text_start;
+            self.close(prev, prev_end..self.current_end, weight);
+        }
+        self.last_bookmark_index = None;
+        self.is_before_text = false;
+        self.current_end = 0;
+    }
+
+    /// Takes the story out, leaving the warnings behind
+    fn finish(&mut self, weight: impl Fn(Range<usize>) -> W) -> (HashMap<K, NodeIndex>, G)
+    where
+        W: Default,
+    {
+        self.end_text(weight);
+        for choice in mem::take(&mut self.choice_map) {
+            let to_anchor_index = match self.find(&choice.to_anchor) {
+                Some(index) => index,
+                None if self.options.create_missing_bookmarks => {
+                    let index = self.graph.add_node(BookmarkWeight {
+                        name: choice.to_anchor_name,
+                        ..BookmarkWeight::default()
                     });
+                    self.insert(choice.to_anchor, index);
+                    index
                 }
+                None => {
+                    let (from_bookmark, _) = &self.bookmarks[choice.from_anchor.index()];
+                    self.warnings.push(ReadWarning::DanglingChoice {
+                        from_bookmark: from_bookmark.clone(),
+                        target_name: choice.to_anchor,
+                        range: choice.span,
+                    });
+                    continue;
+                }
+            };
+            self.graph
+                .add_edge(choice.from_anchor, to_anchor_index, choice.weight);
+        }
+        (
+            mem::take(&mut self.bookmark_map),
+            mem::take(&mut self.graph),
+        )
+    }
+}
+
+/// Slug of a bookmark name, which is lowercase, with runs of whitespace, `_` and `-`
+/// turned into a single `-` and none of them around it, e.g. `the-cave` for ` The  Cave_`,
+/// see [`ReadOptions::normalize_names`]
+#[must_use]
+pub fn normalize_name(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for word in name
+        .split(|ch: char| ch.is_whitespace() || ch == '_' || ch == '-')
+        .filter(|word| !word.is_empty())
+    {
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.extend(word.chars().flat_map(char::to_lowercase));
+    }
+    slug
+}
+
+/// Guide can help searching for the particular bookmark story should continue from.
+/// Nodes are added in the order bookmarks are written, so that sorting by index
+/// gives the document order, see [`read_ordered`].
+pub type Guide<'a> = HashMap<&'a str, NodeIndex>;
+
+/// A story is a graph where spans of text are connected to each other through choices.
+/// Ranges of original string stored in nodes relate to main text under a particular `bookmark`,
+/// and the ranges stored in edges relate to the text of a certain `choice`.
+pub type Story = DiGraph<BookmarkWeight, ChoiceWeight>;
+
+/// Node of a story, with `text` written after a `bookmark` signal, and the keys and values
+/// of `meta` signals between the two, e.g. `@meta{author=Kai, mood=calm}`,
+/// which are split the same way as [`StrRange::pairs`]. Several `meta` signals are merged,
+/// with later values of the same key replacing earlier ones.
+/// Same goes for `tags` of `tag` signals, e.g. `@tag{act1, forest}`, which are written once each.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookmarkWeight<W = Range<usize>> {
+    /// Name written first in the `bookmark` signal, rather than an alias,
+    /// as `intro` of `@bookmark{intro | start}`, or the target of a choice it's made for,
+    /// which is empty for the bookmark of [`ReadOptions::implicit_start`], see [`bookmark_name`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name: W,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub meta: Vec<(W, Option<W>)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tags: Vec<W>,
+    pub text: W,
+}
+
+/// Separates the target of a choice from its label, as in `@choice{target | Take the left path}`
+const LABEL_SEPARATOR: char = '|';
+
+/// Separates the target of a choice, along with its label, from its condition,
+/// as in `@choice{vault | Open the vault ? has_key}`
+const CONDITION_SEPARATOR: char = '?';
+
+/// Separates the target of a choice from its weight, as in `@choice{ambush * 3 | Fight}`
+const WEIGHT_SEPARATOR: char = '*';
+
+/// Flag written after the target of a choice that may only be taken once, as in `@choice{gossip !once}`
+const ONCE_FLAG: &str = "!once";
+
+/// Weight of choices that have none written
+pub(crate) const DEFAULT_CHOICE_WEIGHT: u32 = 1;
+
+#[cfg(feature = "serde")]
+const fn default_choice_weight() -> u32 {
+    DEFAULT_CHOICE_WEIGHT
+}
+
+/// Edge of a story, with `text` written after a `choice` signal, and the `label`
+/// and the `condition` written in its param after `|` and `?`, if there are ones.
+/// Labels are there to show in menus instead of the prose, and may be quoted to have `?` in them.
+/// Conditions are never evaluated, it's up to the runtime to tell which choices to show.
+/// `weight` is written after `*` of the target, as in `@choice{ambush * 3}`,
+/// and tells how often to pick the choice when picking randomly, which is 1 by default.
+/// `once` is set by the `!once` flag right after the target, as in `@choice{gossip !once}`,
+/// for choices that are gone once taken, see [`Playthrough::choices`](crate::Playthrough::choices).
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChoiceWeight<W = Range<usize>> {
+    pub label: Option<W>,
+    pub condition: Option<W>,
+    #[cfg_attr(feature = "serde", serde(default = "default_choice_weight"))]
+    pub weight: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub once: bool,
+    pub text: W,
+}
+
+impl<W: Default> Default for ChoiceWeight<W> {
+    fn default() -> Self {
+        Self {
+            label: None,
+            condition: None,
+            weight: DEFAULT_CHOICE_WEIGHT,
+            once: false,
+            text: W::default(),
+        }
+    }
+}
+
+/// Choices leading from `node` along with their ranges, in the order they are written,
+/// unlike [`DiGraph::edges`], which goes from the last edge added to the first
+pub fn story_choices(
+    story: &Story,
+    node: NodeIndex,
+) -> impl Iterator<Item = (EdgeIndex, &ChoiceWeight)> {
+    let mut choices: Vec<_> = story
+        .edges(node)
+        .map(|edge| (edge.id(), edge.weight()))
+        .collect();
+    choices.sort_by_key(|(_, weight)| weight.text.start);
+    choices.into_iter()
+}
+
+/// Text under `node` of a story read from `full`, or `None` if there's no such node,
+/// or its range doesn't fit `full`, e.g. when the story was read from another text
+#[must_use]
+pub fn story_text<'a>(story: &Story, node: NodeIndex, full: &'a str) -> Option<&'a str> {
+    full.get(story.node_weight(node)?.text.clone())
+}
+
+/// Keys and values of `meta` signals of the bookmark at `node`, in the order they are written,
+/// see [`BookmarkWeight`]. Those with ranges that don't fit `full` are left out.
+#[must_use]
+pub fn node_meta<'a>(
+    story: &Story,
+    node: NodeIndex,
+    full: &'a str,
+) -> Vec<(&'a str, Option<&'a str>)> {
+    story.node_weight(node).map_or_else(Vec::new, |bookmark| {
+        bookmark
+            .meta
+            .iter()
+            .filter_map(|(key, value)| {
+                let value = match value {
+                    Some(value) => Some(full.get(value.clone())?),
+                    None => None,
+                };
+                Some((full.get(key.clone())?, value))
+            })
+            .collect()
+    })
+}
+
+/// Tags of the bookmark at `node`, in the order they are written, see [`BookmarkWeight`].
+/// Those with ranges that don't fit `full` are left out.
+#[must_use]
+pub fn node_tags<'a>(story: &Story, node: NodeIndex, full: &'a str) -> Vec<&'a str> {
+    story.node_weight(node).map_or_else(Vec::new, |bookmark| {
+        bookmark
+            .tags
+            .iter()
+            .filter_map(|tag| full.get(tag.clone()))
+            .collect()
+    })
+}
+
+/// Names of bookmarks of a story read from `full` that have `tag`, in the order they are written
+#[must_use]
+pub fn bookmarks_with_tag<'a>(
+    guide: &Guide<'a>,
+    story: &Story,
+    full: &str,
+    tag: &str,
+) -> Vec<&'a str> {
+    let names = bookmark_names(guide, story, full);
+    story
+        .node_indices()
+        .filter(|node| node_tags(story, *node, full).contains(&tag))
+        .map(|node| names[node.index()])
+        .collect()
+}
+
+/// Same as [`story_text`], but for the text of the choice at `edge`
+#[must_use]
+pub fn choice_text<'a>(story: &Story, edge: EdgeIndex, full: &'a str) -> Option<&'a str> {
+    full.get(story.edge_weight(edge)?.text.clone())
+}
+
+/// Name of the bookmark at `node` written first rather than an alias,
+/// e.g. `intro` of `@bookmark{intro | start}`, or `None` if there's no such bookmark
+#[must_use]
+pub fn bookmark_name<'a>(story: &Story, node: NodeIndex, full: &'a str) -> Option<&'a str> {
+    full.get(story.node_weight(node)?.name.clone())
+}
+
+/// Label of the choice at `edge`, e.g. `Take the left path` of `@choice{target | Take the left path}`,
+/// or `None` if the choice has no label, or there's no such choice
+#[must_use]
+pub fn choice_label<'a>(story: &Story, edge: EdgeIndex, full: &'a str) -> Option<&'a str> {
+    full.get(story.edge_weight(edge)?.label.clone()?)
+}
+
+/// Condition of the choice at `edge`, e.g. `has_key` of `@choice{vault ? has_key}`,
+/// or `None` if the choice is unconditional, or there's no such choice
+#[must_use]
+pub fn choice_condition<'a>(story: &Story, edge: EdgeIndex, full: &'a str) -> Option<&'a str> {
+    full.get(story.edge_weight(edge)?.condition.clone()?)
+}
+
+/// Events of the text under `node` of a story read from `full`,
+/// with ranges relative to `full` rather than to the text of the bookmark.
+/// There are none if [`story_text`] has no text for `node`.
+#[must_use]
+pub fn node_events<'a>(story: &Story, node: NodeIndex, full: &'a str) -> EventIter<'a> {
+    node_events_with_config(story, node, full, ParseConfig::new())
+}
+
+/// Same as [`node_events`], but parsed as `config` tells, e.g. to keep indentation
+#[must_use]
+pub fn node_events_with_config<'a>(
+    story: &Story,
+    node: NodeIndex,
+    full: &'a str,
+    config: ParseConfig,
+) -> EventIter<'a> {
+    story_text(story, node, full).map_or_else(EventIter::default, |text| {
+        EventIter::with_config_and_offset(text, config, story[node].text.start)
+    })
+}
+
+/// Same as [`node_events`], but for the text of the choice at `edge`
+#[must_use]
+pub fn edge_events<'a>(story: &Story, edge: EdgeIndex, full: &'a str) -> EventIter<'a> {
+    edge_events_with_config(story, edge, full, ParseConfig::new())
+}
+
+/// Same as [`edge_events`], but parsed as `config` tells
+#[must_use]
+pub fn edge_events_with_config<'a>(
+    story: &Story,
+    edge: EdgeIndex,
+    full: &'a str,
+    config: ParseConfig,
+) -> EventIter<'a> {
+    choice_text(story, edge, full).map_or_else(EventIter::default, |text| {
+        EventIter::with_config_and_offset(text, config, story[edge].text.start)
+    })
+}
+
+/// Whether some path of choices leads to each node from the `start` bookmark, if there's one
+fn reached_from(guide: &Guide, story: &Story, start: &str) -> Vec<bool> {
+    let mut reached = alloc::vec![false; story.node_count()];
+    if let Some(start) = find_bookmark(guide, start) {
+        let mut dfs = Dfs::new(story, start);
+        while let Some(node) = dfs.next(story) {
+            reached[node.index()] = true;
+        }
+    }
+    reached
+}
+
+/// Choice of the rest of a story that leads into its [`subgraph`], which the subgraph leaves out,
+/// so that it would dangle if the part was split off, with `edge` of the original story
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct CrossingChoice<'a> {
+    pub from_bookmark: &'a str,
+    pub target_name: &'a str,
+    pub edge: EdgeIndex,
+}
+
+/// Part of a story that the `start` bookmark leads to, including itself,
+/// e.g. to split a story into chapters. Ranges still point into the original text,
+/// while nodes get new indices, keeping their order. Empty if there's no `start` bookmark.
+/// As every choice of the part leads within it, only choices from the rest of the story
+/// cross into it, which are left out and returned, in the order of their edges.
+#[must_use]
+pub fn subgraph<'a>(
+    guide: &Guide<'a>,
+    story: &Story,
+    start: &str,
+    full: &str,
+) -> (Guide<'a>, Story, Vec<CrossingChoice<'a>>) {
+    let reached = reached_from(guide, story, start);
+    let names = bookmark_names(guide, story, full);
+    let crossing = story
+        .edge_references()
+        .filter(|edge| !reached[edge.source().index()] && reached[edge.target().index()])
+        .map(|edge| CrossingChoice {
+            from_bookmark: names[edge.source().index()],
+            target_name: names[edge.target().index()],
+            edge: edge.id(),
+        })
+        .collect();
+    let mut new_indices = Vec::with_capacity(reached.len());
+    let mut node_count = 0;
+    for is_reached in &reached {
+        new_indices.push(NodeIndex::new(node_count));
+        node_count += usize::from(*is_reached);
+    }
+    let subguide = guide
+        .iter()
+        .filter(|(_, index)| reached[index.index()])
+        .map(|(name, index)| (*name, new_indices[index.index()]))
+        .collect();
+    let substory = story.filter_map(
+        |index, bookmark| reached[index.index()].then(|| bookmark.clone()),
+        |_, choice| Some(choice.clone()),
+    );
+    (subguide, substory, crossing)
+}
+
+/// Source text of the [`subgraph`] of a story read from `full`, which reads as that part alone.
+/// Each bookmark is written with its text and then its choices, as they are in `full`.
+/// Choices that cross into the part are told by [`subgraph`].
+#[must_use]
+pub fn subgraph_source(guide: &Guide, story: &Story, start: &str, full: &str) -> String {
+    let (subguide, substory, _) = subgraph(guide, story, start, full);
+    let names = names_by_index(&subguide, &substory, full);
+    let mut out = String::new();
+    for node in substory.node_indices() {
+        out.push_str("@bookmark");
+        push_param(
+            &mut out,
+            names[node.index()],
+            DEFAULT_CHOICE_WEIGHT,
+            false,
+            None,
+            None,
+        );
+        push_meta(&mut out, node_meta(&substory, node, full));
+        push_tags(&mut out, node_tags(&substory, node, full));
+        out.push_str(story_text(&substory, node, full).unwrap_or_default());
+        for (edge, _) in story_choices(&substory, node) {
+            let Some((_, target)) = substory.edge_endpoints(edge) else {
+                continue;
+            };
+            out.push_str("@choice");
+            let label = choice_label(&substory, edge, full);
+            let condition = choice_condition(&substory, edge, full);
+            let ChoiceWeight { weight, once, .. } = substory[edge];
+            push_param(
+                &mut out,
+                names[target.index()],
+                weight,
+                once,
+                label,
+                condition,
+            );
+            out.push_str(choice_text(&substory, edge, full).unwrap_or_default());
+        }
+    }
+    out
+}
+
+/// Names of bookmarks that no path of choices leads to from the `start` one,
+/// in the order they are written, or all of them if there's no `start` bookmark
+#[must_use]
+pub fn unreachable_from<'a>(
+    guide: &Guide<'a>,
+    story: &Story,
+    start: &str,
+    full: &str,
+) -> Vec<&'a str> {
+    let reached = reached_from(guide, story, start);
+    bookmark_names(guide, story, full)
+        .into_iter()
+        .zip(reached)
+        .filter(|(_, is_reached)| !is_reached)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Bookmarks without choices, in the order they are written
+#[must_use]
+pub fn dead_ends(story: &Story) -> Vec<NodeIndex> {
+    story
+        .node_indices()
+        .filter(|node| story.edges(*node).next().is_none())
+        .collect()
+}
+
+/// Same as [`dead_ends`], but leaves out bookmarks of a story read from `full`
+/// that are endings, as told by a signal with the `terminator` prompt in their text,
+/// e.g. `@end` for `"end"`
+#[must_use]
+pub fn dead_ends_with_terminator(story: &Story, full: &str, terminator: &str) -> Vec<NodeIndex> {
+    let is_ending = |node| {
+        node_events(story, node, full).signals().any(|signal| {
+            matches!(
+                signal,
+                Signal::Prompt { prompt, .. } | Signal::Call { prompt, .. }
+                    if prompt.slice == terminator
+            )
+        })
+    };
+    dead_ends(story)
+        .into_iter()
+        .filter(|node| !is_ending(*node))
+        .collect()
+}
+
+/// Loops of choices, one for each group of bookmarks that lead to each other
+/// and for each bookmark with a choice of itself. Each loop is the shortest one
+/// through its first bookmark, which is the earliest written one of the group,
+/// with the last bookmark having a choice of the first one.
+#[must_use]
+pub fn find_cycles(story: &Story) -> Vec<Vec<NodeIndex>> {
+    let mut cycles: Vec<_> = tarjan_scc(story)
+        .into_iter()
+        .filter_map(|mut component| {
+            component.sort_unstable();
+            let start = component[0];
+            if component.len() == 1 {
+                return story
+                    .contains_edge(start, start)
+                    .then(|| alloc::vec![start]);
             }
-            Event::Signal(
-                Signal::Call {
-                    param: StrRange { range, .. },
-                    ..
+            // breadth-first search within the component for the way back to `start`,
+            // not counting a choice of itself, so that the loop shows the whole group
+            let mut prev = HashMap::new();
+            let mut queue = alloc::collections::VecDeque::from([start]);
+            while let Some(node) = queue.pop_front() {
+                for next in story.neighbors(node) {
+                    if next == start && node != start {
+                        let mut cycle = alloc::vec![node];
+                        let mut last = node;
+                        while let Some(prev_node) = prev.get(&last) {
+                            cycle.push(*prev_node);
+                            last = *prev_node;
+                        }
+                        cycle.reverse();
+                        return Some(cycle);
+                    }
+                    if next != start
+                        && component.binary_search(&next).is_ok()
+                        && !prev.contains_key(&next)
+                    {
+                        prev.insert(next, node);
+                        queue.push_back(next);
+                    }
                 }
-                | Signal::Param(StrRange { range, .. }),
-            ) => current_end = range.end + 1,
-            Event::Signal(Signal::Prompt(StrRange { range, .. }))
-            | Event::Text(StrRange { range, .. }) => {
-                current_end = range.end;
             }
-            _ => (),
+            None
+        })
+        .collect();
+    cycles.sort_unstable_by_key(|cycle| cycle[0]);
+    cycles
+}
+
+/// Name given to [`path_between`] or [`all_paths`] that no bookmark has
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MissingBookmark<'a>(pub &'a str);
+
+impl<'a> fmt::Display for MissingBookmark<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no bookmark is named `{}`", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> std::error::Error for MissingBookmark<'a> {}
+
+/// Node of the bookmark with `name` as written, or else with the same slug, if only one has it,
+/// since the guide of a story read with [`ReadOptions::normalize_names`] keeps names as written
+pub(crate) fn find_bookmark(guide: &Guide, name: &str) -> Option<NodeIndex> {
+    if let Some(node) = guide.get(name) {
+        return Some(*node);
+    }
+    let slug = normalize_name(name);
+    let mut found = guide
+        .iter()
+        .filter(|(key, _)| normalize_name(key) == slug)
+        .map(|(_, node)| *node);
+    let node = found.next()?;
+    found.all(|other| other == node).then_some(node)
+}
+
+fn bookmark_index<'a>(guide: &Guide, name: &'a str) -> Result<NodeIndex, MissingBookmark<'a>> {
+    find_bookmark(guide, name).ok_or(MissingBookmark(name))
+}
+
+/// One of the shortest sequences of choices leading from the `from` bookmark to the `to` one,
+/// each being the bookmark it's taken at and the edge of the choice, or `None` if there's none.
+/// The sequence is empty if both are the same bookmark.
+///
+/// # Errors
+///
+/// Fails if either name isn't in `guide`
+pub fn path_between<'a>(
+    guide: &Guide,
+    story: &Story,
+    from: &'a str,
+    to: &'a str,
+) -> Result<Option<Vec<(NodeIndex, EdgeIndex)>>, MissingBookmark<'a>> {
+    let from = bookmark_index(guide, from)?;
+    let to = bookmark_index(guide, to)?;
+    let mut prev = HashMap::new();
+    let mut queue = alloc::collections::VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            let mut path = Vec::new();
+            let mut node = to;
+            while node != from {
+                let (prev_node, edge) = prev[&node];
+                path.push((prev_node, edge));
+                node = prev_node;
+            }
+            path.reverse();
+            return Ok(Some(path));
+        }
+        for (edge, _) in story_choices(story, node) {
+            let Some((_, next)) = story.edge_endpoints(edge) else {
+                continue;
+            };
+            if next != from && !prev.contains_key(&next) {
+                prev.insert(next, (node, edge));
+                queue.push_back(next);
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn push_paths(
+    story: &Story,
+    to: NodeIndex,
+    max_choices: usize,
+    path: &mut Vec<(NodeIndex, EdgeIndex)>,
+    node: NodeIndex,
+    paths: &mut Vec<Vec<(NodeIndex, EdgeIndex)>>,
+) {
+    if node == to {
+        paths.push(path.clone());
+        return;
+    }
+    if path.len() == max_choices {
+        return;
+    }
+    for (edge, _) in story_choices(story, node) {
+        let Some((_, next)) = story.edge_endpoints(edge) else {
+            continue;
+        };
+        let is_visited = next == node || path.iter().any(|(visited, _)| *visited == next);
+        if !is_visited {
+            path.push((node, edge));
+            push_paths(story, to, max_choices, path, next, paths);
+            path.pop();
         }
     }
-    if let Some(prev_param) = unclosed_param {
-        if is_prev_bookmark {
-            match bookmark_map.entry(prev_param.slice) {
-                hash_map::Entry::Occupied(_) => (),
-                hash_map::Entry::Vacant(entry) => {
-                    entry.insert(range_graph.add_node(prev_param.range.end + 1..current_end));
+}
+
+/// Same as [`path_between`], but lists every sequence of at most `max_choices` choices,
+/// in which no bookmark is visited twice, so that loops don't make it endless
+///
+/// # Errors
+///
+/// Fails if either name isn't in `guide`
+pub fn all_paths<'a>(
+    guide: &Guide,
+    story: &Story,
+    from: &'a str,
+    to: &'a str,
+    max_choices: usize,
+) -> Result<Vec<Vec<(NodeIndex, EdgeIndex)>>, MissingBookmark<'a>> {
+    let from = bookmark_index(guide, from)?;
+    let to = bookmark_index(guide, to)?;
+    let mut paths = Vec::new();
+    push_paths(story, to, max_choices, &mut Vec::new(), from, &mut paths);
+    Ok(paths)
+}
+
+/// Index of one of several texts given to [`merge`], e.g. of a file
+pub type SourceId = usize;
+
+/// Same as [`Guide`], but for a [`MultiStory`]
+pub type MultiGuide<'a> = Guide<'a>;
+
+/// Same as [`Story`], but read from several texts, so that each range
+/// goes along with the source it is relative to
+pub type MultiStory =
+    DiGraph<BookmarkWeight<(SourceId, Range<usize>)>, ChoiceWeight<(SourceId, Range<usize>)>>;
+
+/// What's off about a story read by [`merge`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum MergeWarning<'a> {
+    /// Same as [`ReadWarning::DuplicateBookmark`], but with each span in its own source
+    DuplicateBookmark {
+        name: &'a str,
+        first: (SourceId, Range<usize>),
+        duplicate: (SourceId, Range<usize>),
+    },
+    /// Any other warning, with ranges relative to `source`
+    Source {
+        source: SourceId,
+        warning: ReadWarning<&'a str>,
+    },
+}
+
+impl<'a> MergeWarning<'a> {
+    /// Tags `warning` found in `source` with sources, given the story read so far
+    fn new(
+        warning: ReadWarning<&'a str>,
+        source: SourceId,
+        guide: &MultiGuide,
+        story: &MultiStory,
+    ) -> Self {
+        match warning {
+            ReadWarning::DuplicateBookmark {
+                name,
+                first_range,
+                duplicate_range,
+            } => Self::DuplicateBookmark {
+                name,
+                first: (story[guide[name]].text.0, first_range),
+                duplicate: (source, duplicate_range),
+            },
+            ReadWarning::DanglingChoice { from_bookmark, .. } => Self::Source {
+                source: story[guide[from_bookmark]].text.0,
+                warning,
+            },
+            warning => Self::Source { source, warning },
+        }
+    }
+}
+
+/// Same as [`read_checked`], but for several texts, e.g. files, each being its own document
+/// rather than a chunk of one, while choices may lead to bookmarks of any of them.
+/// Ranges are relative to the text they are in, which is told by its [`SourceId`].
+#[must_use]
+pub fn merge<'a>(
+    sources: Vec<(SourceId, &'a str)>,
+) -> (MultiGuide<'a>, MultiStory, Vec<MergeWarning<'a>>) {
+    let mut pass = NodePass::<_, _, MultiStory>::new(ReadOptions::new());
+    let mut warnings = Vec::new();
+    for (source, text) in sources {
+        for event in crate::core::Iter::new(text) {
+            pass.push(event, |name| name, |range| (source, range));
+        }
+        pass.end_text(|range| (source, range));
+        warnings.extend(
+            mem::take(&mut pass.warnings)
+                .into_iter()
+                .map(|warning| MergeWarning::new(warning, source, &pass.bookmark_map, &pass.graph)),
+        );
+    }
+    // dangling choices are only known in the end, each relative to the bookmark it's under
+    let (guide, story) = pass.finish(|range| (SourceId::default(), range));
+    warnings.extend(
+        pass.warnings
+            .into_iter()
+            .map(|warning| MergeWarning::new(warning, SourceId::default(), &guide, &story)),
+    );
+    (guide, story, warnings)
+}
+
+/// Same as [`Story`], but indices stay the same when nodes or edges are removed,
+/// e.g. to update a story without invalidating indices kept elsewhere
+pub type StableStory = StableDiGraph<BookmarkWeight, ChoiceWeight>;
+
+/// Same as [`Guide`], but owning bookmark names
+pub type OwnedGuide = HashMap<String, NodeIndex>;
+
+/// Names of `guide` indexed by their nodes, picking the name each node has in `full`
+/// out of several names of the same node, see [`bookmark_name`], or the first of them
+/// in the order of chars if that one isn't in `guide`, e.g. after a bookmark is renamed
+fn pick_names<'a>(
+    guide: impl Iterator<Item = (&'a str, NodeIndex)>,
+    story: &Story,
+    full: &str,
+) -> Vec<&'a str> {
+    let mut names: Vec<Option<&str>> = alloc::vec![None; story.node_count()];
+    for (name, index) in guide {
+        let own_name = bookmark_name(story, index, full);
+        let picked = &mut names[index.index()];
+        if picked.is_none_or(|picked| {
+            Some(picked) != own_name && (Some(name) == own_name || name < picked)
+        }) {
+            *picked = Some(name);
+        }
+    }
+    names.into_iter().map(Option::unwrap_or_default).collect()
+}
+
+/// Bookmark names indexed by their nodes, see [`bookmark_names`]
+pub(crate) fn names_by_index<'g, K: AsRef<str>>(
+    guide: &'g HashMap<K, NodeIndex>,
+    story: &Story,
+    full: &str,
+) -> Vec<&'g str> {
+    pick_names(
+        guide.iter().map(|(name, index)| (name.as_ref(), *index)),
+        story,
+        full,
+    )
+}
+
+/// Bookmark names of a story read from `full` indexed by their nodes, which are
+/// the names written first rather than aliases, as `intro` of `@bookmark{intro | start}`
+#[must_use]
+pub fn bookmark_names<'a>(guide: &Guide<'a>, story: &Story, full: &str) -> Vec<&'a str> {
+    pick_names(
+        guide.iter().map(|(name, index)| (*name, *index)),
+        story,
+        full,
+    )
+}
+
+/// Node of an [`OwnedStory`], which displays as its text, see [`BookmarkWeight`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedBookmark {
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub meta: Vec<(String, Option<String>)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tags: Vec<String>,
+    pub text: String,
+}
+
+impl fmt::Display for OwnedBookmark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Edge of an [`OwnedStory`], which displays as its text, see [`ChoiceWeight`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedChoice {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub label: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub condition: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default = "default_choice_weight"))]
+    pub weight: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub once: bool,
+    pub text: String,
+}
+
+impl Default for OwnedChoice {
+    fn default() -> Self {
+        Self {
+            label: None,
+            condition: None,
+            weight: DEFAULT_CHOICE_WEIGHT,
+            once: false,
+            text: String::new(),
+        }
+    }
+}
+
+impl fmt::Display for OwnedChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Same as [`Story`], but nodes and edges own their text instead of pointing into the original,
+/// so that it outlives the text. Dereferences to the graph itself.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedStory(DiGraph<OwnedBookmark, OwnedChoice>);
+
+impl OwnedStory {
+    /// Copies text out of the ranges of `story` read from `text`, with the same indices
+    ///
+    /// # Panics
+    ///
+    /// Panics if ranges are out of bounds of `text` or don't lie on char boundaries
+    #[must_use]
+    pub fn from_ranges(guide: &Guide, story: &Story, text: &str) -> Self {
+        let names = names_by_index(guide, story, text);
+        Self(story.map(
+            |index, bookmark| {
+                OwnedBookmark {
+                    name: names[index.index()].to_owned(),
+                    meta: bookmark
+                        .meta
+                        .iter()
+                        .map(|(key, value)| {
+                            let value = value.clone().map(|value| text[value].to_owned());
+                            (text[key.clone()].to_owned(), value)
+                        })
+                        .collect(),
+                    tags: bookmark
+                        .tags
+                        .iter()
+                        .map(|tag| text[tag.clone()].to_owned())
+                        .collect(),
+                    text: text[bookmark.text.clone()].to_owned(),
                 }
-            }
-        } else {
-            choice_map.push(Choice {
-                from_anchor: last_bookmark_index,
-                to_anchor: prev_param.slice,
-                range: prev_param.range.end + 1..current_end,
+            },
+            |_, weight| OwnedChoice {
+                label: weight.label.clone().map(|range| text[range].to_owned()),
+                condition: weight.condition.clone().map(|range| text[range].to_owned()),
+                weight: weight.weight,
+                once: weight.once,
+                text: text[weight.text.clone()].to_owned(),
+            },
+        ))
+    }
+
+    #[must_use]
+    pub fn into_graph(self) -> DiGraph<OwnedBookmark, OwnedChoice> {
+        self.0
+    }
+}
+
+impl From<DiGraph<OwnedBookmark, OwnedChoice>> for OwnedStory {
+    fn from(graph: DiGraph<OwnedBookmark, OwnedChoice>) -> Self {
+        Self(graph)
+    }
+}
+
+impl ops::Deref for OwnedStory {
+    type Target = DiGraph<OwnedBookmark, OwnedChoice>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Bookmark of a [`StoryExport`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExportedBookmark {
+    pub name: String,
+    /// Range of the name, see [`BookmarkWeight::name`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name_range: Range<usize>,
+    pub range: Range<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub meta: Vec<(Range<usize>, Option<Range<usize>>)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tags: Vec<Range<usize>>,
+}
+
+/// Choice of a [`StoryExport`], leading from one bookmark name to another
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExportedChoice {
+    pub from: String,
+    pub to: String,
+    pub range: Range<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub label: Option<Range<usize>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub condition: Option<Range<usize>>,
+    #[cfg_attr(feature = "serde", serde(default = "default_choice_weight"))]
+    pub weight: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub once: bool,
+}
+
+impl Default for ExportedChoice {
+    fn default() -> Self {
+        Self {
+            from: String::new(),
+            to: String::new(),
+            range: Range::default(),
+            label: None,
+            condition: None,
+            weight: DEFAULT_CHOICE_WEIGHT,
+            once: false,
+        }
+    }
+}
+
+/// Structure of a story without the text, e.g. to hand it over to tools in other languages.
+/// Bookmarks are listed in the order of their indices, and choices in the order they are written.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StoryExport {
+    pub bookmarks: Vec<ExportedBookmark>,
+    pub choices: Vec<ExportedChoice>,
+}
+
+impl StoryExport {
+    /// Rebuilds the story, with bookmarks getting indices in the order they are listed.
+    /// Choices between names that no bookmark has are left out.
+    #[must_use]
+    pub fn into_graph(self) -> (OwnedGuide, Story) {
+        let mut guide = OwnedGuide::with_capacity(self.bookmarks.len());
+        let mut story = Story::with_capacity(self.bookmarks.len(), self.choices.len());
+        for bookmark in self.bookmarks {
+            let index = story.add_node(BookmarkWeight {
+                name: bookmark.name_range,
+                meta: bookmark.meta,
+                tags: bookmark.tags,
+                text: bookmark.range,
             });
+            guide.insert(bookmark.name, index);
+        }
+        for choice in self.choices {
+            if let (Some(from), Some(to)) = (guide.get(&choice.from), guide.get(&choice.to)) {
+                let weight = ChoiceWeight {
+                    label: choice.label,
+                    condition: choice.condition,
+                    weight: choice.weight,
+                    once: choice.once,
+                    text: choice.range,
+                };
+                story.add_edge(*from, *to, weight);
+            }
+        }
+        (guide, story)
+    }
+}
+
+impl From<(&Guide<'_>, &Story, &str)> for StoryExport {
+    /// Exports a story read from the text next to it
+    fn from((guide, story, full): (&Guide<'_>, &Story, &str)) -> Self {
+        let names = names_by_index(guide, story, full);
+        Self {
+            bookmarks: story
+                .node_indices()
+                .map(|index| ExportedBookmark {
+                    name: names[index.index()].to_owned(),
+                    name_range: story[index].name.clone(),
+                    range: story[index].text.clone(),
+                    meta: story[index].meta.clone(),
+                    tags: story[index].tags.clone(),
+                })
+                .collect(),
+            choices: story
+                .node_indices()
+                .flat_map(|index| story_choices(story, index))
+                .map(|(edge, weight)| {
+                    let (from, to) = story.edge_endpoints(edge).expect("edge of the story");
+                    ExportedChoice {
+                        from: names[from.index()].to_owned(),
+                        to: names[to.index()].to_owned(),
+                        range: weight.text.clone(),
+                        label: weight.label.clone(),
+                        condition: weight.condition.clone(),
+                        weight: weight.weight,
+                        once: weight.once,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Consume `bookmark` and `choice` signals from text to create a graph.
+/// Text may come in chunks, which are parsed one by one, but make up a single story,
+/// with ranges being byte-indices of all of the chunks joined together.
+#[must_use]
+pub fn read<'a, I: IntoIterator<Item = &'a str>>(text_chunks: I) -> (Guide<'a>, Story) {
+    read_with_options(text_chunks, ReadOptions::new())
+}
+
+/// Same as [`read`], but with `options` other than the default ones
+#[must_use]
+pub fn read_with_options<'a, I: IntoIterator<Item = &'a str>>(
+    text_chunks: I,
+    options: ReadOptions,
+) -> (Guide<'a>, Story) {
+    let (guide, story, _) = read_checked(text_chunks, options);
+    (guide, story)
+}
+
+/// Same as [`read`], but also lists bookmark names in the order they are written
+#[must_use]
+pub fn read_ordered<'a, I: IntoIterator<Item = &'a str>>(
+    text_chunks: I,
+) -> (Guide<'a>, Story, Vec<&'a str>) {
+    let mut pass = NodePass::new(ReadOptions::new());
+    let mut chunk_offset = 0;
+    for chunk in text_chunks {
+        for event in crate::core::Iter::with_offset(chunk, chunk_offset) {
+            pass.push(event, |name| name, |range| range);
         }
+        chunk_offset += chunk.len();
     }
+    let (guide, story) = pass.finish(|range| range);
+    // nodes are added as bookmarks are read, each with the name rather than an alias
+    let bookmarks = pass.bookmarks.into_iter().map(|(name, _)| name).collect();
+    (guide, story, bookmarks)
 }
 
-fn edge_pass<'a>(
-    range_graph: &mut DiGraph<Range<usize>, Range<usize>>,
-    anchor_map: &HashMap<&'a str, NodeIndex>,
-    choice_map: &[Choice<'a>],
-) {
-    for choice in choice_map {
-        if let Some(to_anchor_index) = anchor_map.get(choice.to_anchor) {
-            range_graph.add_edge(choice.from_anchor, *to_anchor_index, choice.range.clone());
+/// Same as [`read`], but builds a [`StableStory`]
+#[must_use]
+pub fn read_stable<'a, I: IntoIterator<Item = &'a str>>(
+    text_chunks: I,
+) -> (Guide<'a>, StableStory) {
+    read_stable_with_options(text_chunks, ReadOptions::new())
+}
+
+/// Same as [`read_with_options`], but also tells what's off about the story
+#[must_use]
+pub fn read_checked<'a, I: IntoIterator<Item = &'a str>>(
+    text_chunks: I,
+    options: ReadOptions,
+) -> (Guide<'a>, Story, Vec<ReadWarning<&'a str>>) {
+    read_inspecting(text_chunks, options, |_| ())
+}
+
+/// Same as [`read_stable`], but with `options` other than the default ones
+#[must_use]
+pub fn read_stable_with_options<'a, I: IntoIterator<Item = &'a str>>(
+    text_chunks: I,
+    options: ReadOptions,
+) -> (Guide<'a>, StableStory) {
+    let (guide, story, _) = read_inspecting(text_chunks, options, |_| ());
+    (guide, story)
+}
+
+/// Same as [`read_checked`], but every event is passed to `inspect` before being read
+pub(crate) fn read_inspecting<'a, I, G>(
+    text_chunks: I,
+    options: ReadOptions,
+    mut inspect: impl FnMut(&Event<'a>),
+) -> (Guide<'a>, G, Vec<ReadWarning<&'a str>>)
+where
+    I: IntoIterator<Item = &'a str>,
+    G: Build<NodeWeight = BookmarkWeight, EdgeWeight = ChoiceWeight>
+        + GraphBase<NodeId = NodeIndex>
+        + Default,
+{
+    let mut pass = NodePass::new(options);
+    let mut chunk_offset = 0;
+    for chunk in text_chunks {
+        for event in crate::core::Iter::with_offset(chunk, chunk_offset) {
+            inspect(&event);
+            pass.push(event, |name| name, |range| range);
         }
+        chunk_offset += chunk.len();
     }
+    let (guide, story) = pass.finish(|range| range);
+    (guide, story, pass.warnings)
 }
 
-/// Guide can help searching for the particular bookmark story should continue from
-pub type Guide<'a> = HashMap<&'a str, NodeIndex>;
+/// Same as [`read`], but fails if anything is off about the story,
+/// with errors ordered by where they are in text
+///
+/// # Errors
+///
+/// Returns every error found, which includes unclosed params along with [`ReadWarning`]s
+pub fn read_strict<'a, I: IntoIterator<Item = &'a str>>(
+    text_chunks: I,
+) -> Result<(Guide<'a>, Story), Vec<GraphError<'a>>> {
+    let text_chunks: Vec<_> = text_chunks.into_iter().collect();
+    let mut unclosed = Vec::new();
+    let (guide, story, warnings) =
+        read_inspecting(text_chunks.iter().copied(), ReadOptions::new(), |event| {
+            if let Event::Diagnostic(Diagnostic::UnclosedParam { bracket }) = event {
+                unclosed.push(bracket.clone());
+            }
+        });
+    if warnings.is_empty() && unclosed.is_empty() {
+        return Ok((guide, story));
+    }
+    let text = text_chunks.concat();
+    let index = LineIndex::new(&text);
+    let mut errors: Vec<_> = warnings
+        .into_iter()
+        .map(|warning| GraphError::from_warning(warning, &index))
+        .chain(
+            unclosed
+                .into_iter()
+                .map(|bracket| GraphError::UnclosedParam {
+                    position: index.position(bracket.range.start),
+                    bracket,
+                }),
+        )
+        .collect();
+    errors.sort_by_key(GraphError::position);
+    Err(errors)
+}
 
-/// A story is a graph where spans of text are connected to each other through choices.
-/// Ranges of original string stored in nodes relate to main text under a particular `bookmark`,
-/// and the ranges stored in edges relate to the text of a certain `choice`.
-pub type Story = DiGraph<Range<usize>, Range<usize>>;
+/// Same as [`read`] for bytes that may be invalid UTF-8, see [`event_iter_bytes`](crate::event_iter_bytes).
+/// Story ranges are byte-indices of `bytes`, while bookmark names are decoded.
+#[must_use]
+pub fn read_bytes(bytes: &[u8]) -> (OwnedGuide, Story) {
+    let lossy = crate::bytes::LossyText::new(bytes);
+    let original =
+        |range: Range<usize>| lossy.original_index(range.start)..lossy.original_index(range.end);
+    let mut pass = NodePass::new(ReadOptions::new());
+    for event in crate::core::Iter::new(&lossy.text) {
+        pass.push(event, str::to_owned, original);
+    }
+    pass.finish(original)
+}
 
-fn from_iter<'a, I: IntoIterator<Item = Event<'a>>>(iter: I) -> (Guide<'a>, Story) {
-    let mut range_graph = DiGraph::new();
-    let mut anchor_map = HashMap::new();
-    let mut choice_map = Vec::new();
-    node_pass(&mut range_graph, &mut anchor_map, &mut choice_map, iter);
-    edge_pass(&mut range_graph, &anchor_map, &choice_map);
-    (anchor_map, range_graph)
+/// Same as [`read`], but reads text line by line, keeping only the text
+/// of the node or edge that is not finished yet
+///
+/// # Errors
+///
+/// Returns the first error of `reader`, including invalid UTF-8
+#[cfg(feature = "std")]
+pub fn read_story_stream<R: BufRead>(reader: R) -> io::Result<(OwnedGuide, OwnedStory)> {
+    read_story_stream_with_options(reader, ReadOptions::new())
 }
 
-/// Consume `bookmark` and `choice` signals from text to create a graph
-#[must_use]
-pub fn read<'a, I: IntoIterator<Item = &'a str>>(text_chunks: I) -> (Guide<'a>, Story) {
-    from_iter(text_chunks.into_iter().flat_map(crate::core::Iter::new))
+/// Same as [`read_story_stream`], but with `options` other than the default ones
+///
+/// # Errors
+///
+/// Returns the first error of `reader`, including invalid UTF-8
+#[cfg(feature = "std")]
+pub fn read_story_stream_with_options<R: BufRead>(
+    mut reader: R,
+    options: ReadOptions,
+) -> io::Result<(OwnedGuide, OwnedStory)> {
+    let mut pass = NodePass::new(options);
+    // stream text starting from `pending_start`, with the latest line at the end
+    let mut pending = String::new();
+    let mut pending_start = 0;
+    loop {
+        let line_start = pending.len();
+        let line_len = reader.read_line(&mut pending)?;
+        if line_len == 0 {
+            break;
+        }
+        let line_offset = pending_start + line_start;
+        for event in crate::core::Iter::with_offset(&pending[line_start..], line_offset) {
+            pass.push(event, str::to_owned, |range| {
+                pending[range.start - pending_start..range.end - pending_start].to_owned()
+            });
+        }
+        let keep_start = pass
+            .pending_start()
+            .unwrap_or(line_offset + line_len)
+            .max(pending_start);
+        pending.drain(..keep_start - pending_start);
+        pending_start = keep_start;
+    }
+    let (guide, story): (_, DiGraph<_, _>) = pass.finish(|range| {
+        pending[range.start - pending_start..range.end.max(range.start) - pending_start].to_owned()
+    });
+    let story = story.map_owned(
+        |_,
+         BookmarkWeight {
+             name,
+             meta,
+             tags,
+             text,
+         }| OwnedBookmark {
+            name,
+            meta,
+            tags,
+            text,
+        },
+        |_,
+         ChoiceWeight {
+             label,
+             condition,
+             weight,
+             once,
+             text,
+         }| OwnedChoice {
+            label,
+            condition,
+            weight,
+            once,
+            text,
+        },
+    );
+    Ok((guide, OwnedStory(story)))
 }
 
 #[cfg(test)]
@@ -137,12 +2127,12 @@ mod tests {
     #[test]
     fn single_bookmark() {
         const SAMPLE: &str = "@bookmark{greet}Hello, World!";
-        let (guide, story) = super::from_iter(crate::core::Iter::new(SAMPLE));
+        let (guide, story) = super::read([SAMPLE]);
         assert_eq!(guide.len(), 1);
         assert_eq!(story.node_count(), 1);
         assert_eq!(story.edge_count(), 0);
         let bookmark_index = guide.get("greet").expect("greet");
-        let text_range = story[*bookmark_index].clone();
+        let text_range = story[*bookmark_index].text.clone();
         assert_eq!(&SAMPLE[text_range], "Hello, World!");
     }
 
@@ -150,38 +2140,995 @@ mod tests {
     fn double_bookmark() {
         const SAMPLE: &str =
             "@bookmark{greet}Hello, World!\n@bookmark{greet-back}Hello back at you!";
-        let (guide, story) = super::from_iter(crate::core::Iter::new(SAMPLE));
+        let (guide, story) = super::read([SAMPLE]);
         assert_eq!(guide.len(), 2);
         assert_eq!(story.node_count(), 2);
         assert_eq!(story.edge_count(), 0);
         let bookmark_index = guide.get("greet").expect("greet");
-        let text_range = story[*bookmark_index].clone();
+        let text_range = story[*bookmark_index].text.clone();
         assert_eq!(&SAMPLE[text_range], "Hello, World!\n");
         let bookmark_index = guide.get("greet-back").expect("greet-back");
-        let text_range = story[*bookmark_index].clone();
+        let text_range = story[*bookmark_index].text.clone();
         assert_eq!(&SAMPLE[text_range], "Hello back at you!");
     }
 
     #[test]
     fn choices() {
         const SAMPLE: &str = "@bookmark{greet}Hello, World!\n@choice{end}Hi!\n@choice{end}Hello back at you!\n@bookmark{end}End.";
-        let (guide, story) = super::from_iter(crate::core::Iter::new(SAMPLE));
+        let (guide, story) = super::read([SAMPLE]);
         assert_eq!(guide.len(), 2);
         assert_eq!(story.node_count(), 2);
         assert_eq!(story.edge_count(), 2);
         let greet_index = guide.get("greet").expect("greet");
-        let text_range = story[*greet_index].clone();
+        let text_range = story[*greet_index].text.clone();
         assert_eq!(&SAMPLE[text_range], "Hello, World!\n");
         let end_index = guide.get("end").expect("end");
-        let text_range = story[*end_index].clone();
+        let text_range = story[*end_index].text.clone();
         assert_eq!(&SAMPLE[text_range], "End.");
         let mut edges = story.edges_connecting(*greet_index, *end_index);
         let hello_back_edge = edges.next().unwrap();
         assert_eq!(
-            &SAMPLE[hello_back_edge.weight().clone()],
+            &SAMPLE[hello_back_edge.weight().text.clone()],
             "Hello back at you!\n"
         );
         let hi_edge = edges.next().unwrap();
-        assert_eq!(&SAMPLE[hi_edge.weight().clone()], "Hi!\n");
+        assert_eq!(&SAMPLE[hi_edge.weight().text.clone()], "Hi!\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        const SAMPLE: &str = "@bookmark{greet}Hello, World!\n@choice{end}Hi!\n@bookmark{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        let json = serde_json::to_string(&(&guide, &story)).expect("serialized story");
+        let (guide_copy, story_copy): (crate::Guide, crate::Story) =
+            serde_json::from_str(&json).expect("deserialized story");
+        assert_eq!(guide_copy, guide);
+        assert_eq!(story_copy.node_count(), story.node_count());
+        assert_eq!(story_copy.edge_count(), story.edge_count());
+        let end_index = guide_copy.get("end").expect("end");
+        assert_eq!(story_copy[*end_index], story[*end_index]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn story_stream() {
+        const SAMPLE: &str = "Skipped.\n@bookmark{greet}Hello, World!\n@choice{end}Hi!\n@choice{end}Hello back at you!\n@bookmark{end}End.\n";
+        let (guide, story) = super::read([SAMPLE]);
+        let (owned_guide, owned_story) =
+            super::read_story_stream(SAMPLE.as_bytes()).expect("no errors");
+        assert_eq!(owned_guide.len(), guide.len());
+        assert_eq!(owned_story.edge_count(), story.edge_count());
+        for (name, index) in &guide {
+            let owned_index = owned_guide.get(*name).expect("same bookmarks");
+            assert_eq!(owned_story[*owned_index].name, *name);
+            assert_eq!(
+                owned_story[*owned_index].text,
+                SAMPLE[story[*index].text.clone()]
+            );
+        }
+        let owned_edges: Vec<_> = owned_story
+            .edge_weights()
+            .map(|choice| choice.text.as_str())
+            .collect();
+        let edges: Vec<_> = story
+            .edge_weights()
+            .map(|choice| &SAMPLE[choice.text.clone()])
+            .collect();
+        assert_eq!(owned_edges, edges);
+    }
+
+    #[test]
+    fn non_ascii_bookmarks() {
+        const SAMPLE: &str =
+            "@bookmark{начало}Привет!\n@choice{終わり}🎉 Да\n@bookmark{終わり}Конец.";
+        let (guide, story) = super::read([SAMPLE]);
+        assert_eq!(story.edge_count(), 1);
+        let start_index = guide.get("начало").expect("начало");
+        assert_eq!(&SAMPLE[story[*start_index].text.clone()], "Привет!\n");
+        let end_index = guide.get("終わり").expect("終わり");
+        assert_eq!(&SAMPLE[story[*end_index].text.clone()], "Конец.");
+        let edge = story.edge_weights().next().expect("choice");
+        assert_eq!(&SAMPLE[edge.text.clone()], "🎉 Да\n");
+    }
+
+    #[test]
+    fn trimmed_names() {
+        const SAMPLE: &str =
+            "@bookmark{ intro }Hi!\n@choice{intro}Again\n@choice{the end }Bye\n@bookmark{the end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        assert_eq!(guide.len(), 2);
+        assert!(guide.contains_key("intro"));
+        assert!(guide.contains_key("the end"));
+        assert_eq!(story.edge_count(), 2);
+        let (guide, story) =
+            super::read_with_options([SAMPLE], super::ReadOptions::new().trim_names(false));
+        assert!(guide.contains_key(" intro "));
+        assert!(guide.contains_key("the end"));
+        assert_eq!(story.edge_count(), 0);
+    }
+
+    #[test]
+    fn lossy_bytes() {
+        const SAMPLE: &[u8] =
+            b"@bookmark{gr\xffeet}Hi \xe2\x82!\n@choice{end}Bye\n@bookmark{end}End.";
+        let (guide, story) = super::read_bytes(SAMPLE);
+        assert_eq!(story.edge_count(), 1);
+        let greet_index = guide.get("gr\u{fffd}eet").expect("greet");
+        assert_eq!(&SAMPLE[story[*greet_index].text.clone()], b"Hi \xe2\x82!\n");
+        let end_index = guide.get("end").expect("end");
+        assert_eq!(&SAMPLE[story[*end_index].text.clone()], b"End.");
+        let edge = story.edge_weights().next().expect("choice");
+        assert_eq!(&SAMPLE[edge.text.clone()], b"Bye\n");
+    }
+
+    #[test]
+    fn case_insensitive_prompts() {
+        const SAMPLE: &str =
+            "@Bookmark{intro}Hi!\n@CHOICE{Intro}Again\n@choice{end}Bye\n@BOOKMARK{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        assert!(guide.is_empty());
+        assert_eq!(story.node_count(), 0);
+        let (guide, story) = super::read_with_options(
+            [SAMPLE],
+            super::ReadOptions::new().case_insensitive_prompts(true),
+        );
+        assert_eq!(guide.len(), 2);
+        assert!(guide.contains_key("intro"));
+        assert!(guide.contains_key("end"));
+        // params aren't folded, so `Intro` doesn't lead to `intro`
+        assert_eq!(story.edge_count(), 1);
+        let edge = story.edge_weights().next().expect("choice");
+        assert_eq!(&SAMPLE[edge.text.clone()], "Bye\n");
+    }
+
+    #[test]
+    fn chunks() {
+        const CHUNKS: [&str; 2] = [
+            "@bookmark{greet}Hi!\n@choice{end}Bye\n",
+            "@bookmark{end}End.\n@choice{greet}Again",
+        ];
+        let text = CHUNKS.concat();
+        let (guide, story) = super::read(CHUNKS);
+        let (greet_index, end_index) = (guide["greet"], guide["end"]);
+        assert_eq!(&text[story[greet_index].text.clone()], "Hi!\n");
+        assert_eq!(&text[story[end_index].text.clone()], "End.\n");
+        let edges: Vec<_> = story
+            .edge_indices()
+            .map(|edge| {
+                let (from, to) = story.edge_endpoints(edge).expect("endpoints");
+                (from, to, &text[story[edge].text.clone()])
+            })
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                (greet_index, end_index, "Bye\n"),
+                (end_index, greet_index, "Again")
+            ]
+        );
+        let (_, whole) = super::read([text.as_str()]);
+        assert!(story.node_weights().eq(whole.node_weights()));
+        assert!(story.edge_weights().eq(whole.edge_weights()));
+    }
+
+    #[test]
+    fn duplicate_bookmarks() {
+        const SAMPLE: &str = "@bookmark{a}First\n@choice{b}To b\n@bookmark{b}B\n\
+            @bookmark{a}Second\n@choice{b}Lost\n@bookmark{c}C\n@choice{a}To a";
+        let (guide, story, warnings) = super::read_checked([SAMPLE], super::ReadOptions::new());
+        assert_eq!(guide.len(), 3);
+        assert_eq!(&SAMPLE[story[guide["a"]].text.clone()], "First\n");
+        let duplicate_start = SAMPLE.find("@bookmark{a}Second").expect("duplicate");
+        assert_eq!(
+            warnings,
+            [super::ReadWarning::DuplicateBookmark {
+                name: "a",
+                first_range: 0..12,
+                duplicate_range: duplicate_start..duplicate_start + 12,
+            }]
+        );
+        let edges: Vec<_> = story
+            .edge_weights()
+            .map(|choice| &SAMPLE[choice.text.clone()])
+            .collect();
+        assert_eq!(edges, ["To b\n", "To a"]);
+        let (read_guide, read_story) = super::read([SAMPLE]);
+        assert_eq!(read_guide, guide);
+        assert!(read_story.edge_weights().eq(story.edge_weights()));
+    }
+
+    #[test]
+    fn dangling_choices() {
+        const SAMPLE: &str =
+            "@bookmark{greet}Hi!\n@choice{bey}Typo\n@bookmark{bye}Bye.\n@choice{greet}Again\n@choice{end}";
+        let (guide, story, warnings) = super::read_checked([SAMPLE], super::ReadOptions::new());
+        assert_eq!(guide.len(), 2);
+        assert_eq!(story.edge_count(), 1);
+        let typo_start = SAMPLE.find("@choice{bey}").expect("typo");
+        assert_eq!(
+            warnings,
+            [
+                super::ReadWarning::DanglingChoice {
+                    from_bookmark: "greet",
+                    target_name: "bey",
+                    range: typo_start..typo_start + 12,
+                },
+                super::ReadWarning::DanglingChoice {
+                    from_bookmark: "bye",
+                    target_name: "end",
+                    range: SAMPLE.len() - 12..SAMPLE.len(),
+                },
+            ]
+        );
+        let (guide, story, warnings) = super::read_checked(
+            [SAMPLE],
+            super::ReadOptions::new().create_missing_bookmarks(true),
+        );
+        assert!(warnings.is_empty());
+        assert_eq!(guide.len(), 4);
+        assert!(story[guide["bey"]].text.is_empty());
+        assert!(story[guide["end"]].text.is_empty());
+        assert_eq!(story.edge_count(), 3);
+        let edge = story.find_edge(guide["bye"], guide["end"]).expect("end");
+        assert!(story[edge].text.is_empty());
+    }
+
+    #[test]
+    fn choice_outside_bookmark() {
+        const SAMPLE: &str = "@choice{intro}Go!\n@bookmark{intro}Hi!\n@choice{intro}Again";
+        let (guide, story, warnings) = super::read_checked([SAMPLE], super::ReadOptions::new());
+        assert_eq!(guide.len(), 1);
+        assert_eq!(&SAMPLE[story[guide["intro"]].text.clone()], "Hi!\n");
+        assert_eq!(
+            warnings,
+            [super::ReadWarning::ChoiceOutsideBookmark {
+                target_name: "intro",
+                range: 0..14,
+            }]
+        );
+        let edges: Vec<_> = story
+            .edge_weights()
+            .map(|choice| &SAMPLE[choice.text.clone()])
+            .collect();
+        assert_eq!(edges, ["Again"]);
+    }
+
+    #[test]
+    fn strict() {
+        const SAMPLE_OK: &str = "@bookmark{a}Hi!\n@choice{b}Go\n@bookmark{b}Bye";
+        const SAMPLE: &str = "@choice{a}Go!\n@bookmark{a}Hi!\n@bookmark{ }Empty\n\
+            @choice{b}Dead\n@bookmark{a}Again\n@choice{x";
+        let errors = super::read_strict([SAMPLE]).expect_err("errors");
+        let messages: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "1:1: choice of `a` is before any bookmark",
+                "3:1: bookmark has an empty name",
+                "4:1: choice under `` leads to missing bookmark `b`",
+                "5:1: bookmark `a` is defined more than once",
+                "6:8: unclosed `{`",
+            ]
+        );
+        assert!(matches!(
+            &errors[1],
+            super::GraphError::EmptyBookmarkName { range, .. } if &SAMPLE[range.clone()] == "@bookmark{ }"
+        ));
+        #[cfg(feature = "std")]
+        {
+            let error: &dyn std::error::Error = &errors[0];
+            assert_eq!(error.to_string(), messages[0]);
+        }
+        let (guide, story) = super::read_strict([SAMPLE_OK]).expect("no errors");
+        assert_eq!((guide, story.edge_count()), (super::read([SAMPLE_OK]).0, 1));
+    }
+
+    #[test]
+    fn ordered_choices() {
+        const SAMPLE: &str = "@bookmark{greet}Hello!\n@choice{end}Hi!\n@choice{greet}Again\n\
+            @choice{end}Hello back at you!\n@choice{end}Bye\n@bookmark{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        let choices: Vec<_> = super::story_choices(&story, guide["greet"])
+            .map(|(edge, choice)| {
+                let (_, target) = story.edge_endpoints(edge).expect("endpoints");
+                (target, &SAMPLE[choice.text.clone()])
+            })
+            .collect();
+        assert_eq!(
+            choices,
+            [
+                (guide["end"], "Hi!\n"),
+                (guide["greet"], "Again\n"),
+                (guide["end"], "Hello back at you!\n"),
+                (guide["end"], "Bye\n"),
+            ]
+        );
+        assert_eq!(super::story_choices(&story, guide["end"]).count(), 0);
+    }
+
+    #[test]
+    fn stable() {
+        const SAMPLE: &str = "@bookmark{greet}Hi!\n@choice{end}Bye\n@bookmark{middle}Mid\n\
+            @choice{end}Skip\n@bookmark{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        let (stable_guide, mut stable_story) = super::read_stable([SAMPLE]);
+        assert_eq!(stable_guide, guide);
+        assert!(stable_story.node_weights().eq(story.node_weights()));
+        assert!(stable_story.edge_weights().eq(story.edge_weights()));
+        stable_story.remove_node(guide["middle"]);
+        assert_eq!(&SAMPLE[stable_story[guide["end"]].text.clone()], "End.");
+        assert_eq!(stable_story.edge_count(), 1);
+        let edge = stable_story
+            .find_edge(guide["greet"], guide["end"])
+            .expect("choice");
+        assert_eq!(&SAMPLE[stable_story[edge].text.clone()], "Bye\n");
+    }
+
+    #[test]
+    fn ordered_bookmarks() {
+        const SAMPLE: &str = "@bookmark{zeta}Z\n@choice{alpha}A\n@bookmark{mu}M\n\
+            @bookmark{alpha}A\n@bookmark{mu}Again\n@bookmark{beta}B";
+        let (guide, story, bookmarks) = super::read_ordered([SAMPLE]);
+        assert_eq!(bookmarks, ["zeta", "mu", "alpha", "beta"]);
+        assert_eq!((guide, story.node_count()), (super::read([SAMPLE]).0, 4));
+    }
+
+    #[test]
+    fn owned_story() {
+        const SAMPLE: &str =
+            "@bookmark{greet}Hello, ёжик!\n@choice{end}Hi!\n@choice{greet}Again\n@bookmark{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        let owned = super::OwnedStory::from_ranges(&guide, &story, SAMPLE);
+        for (name, index) in &guide {
+            assert_eq!(owned[*index].name, *name);
+            assert_eq!(
+                owned[*index].to_string(),
+                SAMPLE[story[*index].text.clone()]
+            );
+        }
+        let edges: Vec<_> = owned.edge_weights().map(ToString::to_string).collect();
+        let expected: Vec<_> = story
+            .edge_weights()
+            .map(|choice| &SAMPLE[choice.text.clone()])
+            .collect();
+        assert_eq!(edges, expected);
+        assert_eq!(owned.into_graph().node_count(), story.node_count());
+    }
+
+    #[test]
+    fn node_and_edge_events() {
+        const SAMPLE: &str =
+            "@bookmark{greet}Hi, @name here\n@choice{end}Bye @wave\n@bookmark{end}End.";
+        const INDENTED: &str = "@bookmark{code}\n@style{p}  let x = 1;";
+        let (guide, story) = super::read([SAMPLE]);
+        for event in super::node_events(&story, guide["greet"], SAMPLE) {
+            if let Some(slice) = event.slice() {
+                assert_eq!(&SAMPLE[event.range()], slice);
+            }
+        }
+        let spans: Vec<_> = super::node_events(&story, guide["greet"], SAMPLE)
+            .signals()
+            .map(|signal| &SAMPLE[signal.span()])
+            .collect();
+        assert_eq!(spans, ["@name"]);
+        let (edge, _) = super::story_choices(&story, guide["greet"])
+            .next()
+            .expect("choice");
+        let spans: Vec<_> = super::edge_events(&story, edge, SAMPLE)
+            .signals()
+            .map(|signal| &SAMPLE[signal.span()])
+            .collect();
+        assert_eq!(spans, ["@wave"]);
+        // indentation after leading signals is only kept as the config tells
+        let config = crate::ParseConfig::new().keep_indent(true);
+        let (guide, story) = super::read([INDENTED]);
+        let text: Vec<_> = super::node_events_with_config(&story, guide["code"], INDENTED, config)
+            .text()
+            .map(|(_, content)| &INDENTED[content.range])
+            .collect();
+        assert_eq!(text, ["  let x = 1;"]);
+        let text: Vec<_> = super::node_events(&story, guide["code"], INDENTED)
+            .text()
+            .map(|(_, content)| &INDENTED[content.range])
+            .collect();
+        assert_eq!(text, ["let x = 1;"]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn export_round_trip() {
+        const SAMPLE: &str = "@bookmark{greet}Hi!\n@choice{end}Bye\n@choice{greet}Again\n\
+            @bookmark{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        let export = super::StoryExport::from((&guide, &story, SAMPLE));
+        let json = serde_json::to_string(&export).expect("serialized export");
+        let export_copy: super::StoryExport =
+            serde_json::from_str(&json).expect("deserialized export");
+        assert_eq!(export_copy, export);
+        let names: Vec<_> = export
+            .bookmarks
+            .iter()
+            .map(|bookmark| bookmark.name.as_str())
+            .collect();
+        assert_eq!(names, ["greet", "end"]);
+        let choices: Vec<_> = export
+            .choices
+            .iter()
+            .map(|choice| {
+                (
+                    choice.from.as_str(),
+                    choice.to.as_str(),
+                    &SAMPLE[choice.range.clone()],
+                )
+            })
+            .collect();
+        assert_eq!(
+            choices,
+            [("greet", "end", "Bye\n"), ("greet", "greet", "Again\n")]
+        );
+        let (guide_copy, story_copy) = export_copy.into_graph();
+        assert_eq!(guide_copy.len(), guide.len());
+        for (name, index) in &guide {
+            assert_eq!(guide_copy[*name], *index);
+        }
+        assert!(story_copy.node_weights().eq(story.node_weights()));
+        let edges = |story: &crate::Story| {
+            let mut edges: Vec<_> = story
+                .edge_indices()
+                .map(|edge| (story.edge_endpoints(edge), story[edge].clone()))
+                .collect();
+            edges.sort_by_key(|(_, choice)| choice.text.start);
+            edges
+        };
+        assert_eq!(edges(&story_copy), edges(&story));
+    }
+
+    #[test]
+    fn stale_text() {
+        const SAMPLE: &str = "@bookmark{greet}Привет!\n@choice{end}Пока\n@bookmark{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        let greet = guide["greet"];
+        let (edge, _) = super::story_choices(&story, greet).next().expect("choice");
+        assert_eq!(super::story_text(&story, greet, SAMPLE), Some("Привет!\n"));
+        assert_eq!(super::choice_text(&story, edge, SAMPLE), Some("Пока\n"));
+        for stale in [
+            "",
+            "@bookmark{greet}Hi!",
+            "@bookmark{greet}Приветствую всех!",
+        ] {
+            assert_eq!(super::choice_text(&story, edge, stale), None);
+            assert_eq!(super::edge_events(&story, edge, stale).count(), 0);
+        }
+        assert_eq!(
+            super::story_text(&story, greet, "@bookmark{greet}Hi!"),
+            None
+        );
+        assert_eq!(super::node_events(&story, greet, "").count(), 0);
+        assert_eq!(
+            super::story_text(&story, petgraph::graph::NodeIndex::new(2), SAMPLE),
+            None
+        );
+    }
+
+    #[test]
+    fn unreachable() {
+        const SAMPLE: &str = "@bookmark{dead}Never\n@choice{intro}Skip\n\
+            @bookmark{intro}Hi!\n@choice{intro}Again\n@choice{middle}On\n\
+            @bookmark{loop}Loop\n@choice{loop}Again\n\
+            @bookmark{middle}Mid\n@choice{end}On\n@bookmark{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        assert_eq!(
+            super::unreachable_from(&guide, &story, "intro", SAMPLE),
+            ["dead", "loop"]
+        );
+        assert_eq!(
+            super::unreachable_from(&guide, &story, "dead", SAMPLE),
+            ["loop"]
+        );
+        assert_eq!(
+            super::unreachable_from(&guide, &story, "missing", SAMPLE),
+            ["dead", "intro", "loop", "middle", "end"]
+        );
+    }
+
+    #[test]
+    fn dead_ends() {
+        const SAMPLE: &str = "@bookmark{intro}Hi!\n@choice{stuck}Wait\n@choice{end}Bye\n\
+            @bookmark{stuck}Oops\n@bookmark{loop}@choice{loop}Again\n\
+            @bookmark{end}The end @end\n@bookmark{fin}@end{credits}";
+        let (guide, story) = super::read([SAMPLE]);
+        assert_eq!(
+            super::dead_ends(&story),
+            [guide["stuck"], guide["end"], guide["fin"]]
+        );
+        assert_eq!(
+            super::dead_ends_with_terminator(&story, SAMPLE, "end"),
+            [guide["stuck"]]
+        );
+        assert_eq!(
+            super::dead_ends_with_terminator(&story, SAMPLE, "fin"),
+            super::dead_ends(&story)
+        );
+    }
+
+    #[test]
+    fn cycles() {
+        const SAMPLE: &str = "@bookmark{intro}Hi!\n@choice{ask}Ask\n\
+            @bookmark{ask}Huh?\n@choice{answer}Answer\n@choice{ask}Again\n\
+            @bookmark{answer}Yes.\n@choice{ask}More\n@choice{end}Bye\n\
+            @bookmark{end}@choice{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        let cycles = super::find_cycles(&story);
+        assert_eq!(
+            cycles,
+            [vec![guide["ask"], guide["answer"]], vec![guide["end"]]]
+        );
+        for cycle in &cycles {
+            for (index, node) in cycle.iter().enumerate() {
+                let next = cycle[(index + 1) % cycle.len()];
+                assert!(story.contains_edge(*node, next));
+            }
+        }
+        let (_, story) = super::read(["@bookmark{a}@choice{b}\n@bookmark{b}"]);
+        assert!(super::find_cycles(&story).is_empty());
+    }
+
+    #[test]
+    fn paths() {
+        const SAMPLE: &str = "@bookmark{intro}Hi!\n@choice{intro}Again\n@choice{hall}Walk\n\
+            @choice{door}Run\n@bookmark{hall}Hall\n@choice{intro}Back\n@choice{door}On\n\
+            @bookmark{door}Door\n@choice{secret}Open\n@bookmark{secret}Secret!";
+        let (guide, story) = super::read([SAMPLE]);
+        let texts = |path: &[(super::NodeIndex, super::EdgeIndex)]| -> Vec<_> {
+            path.iter()
+                .map(|(_, edge)| &SAMPLE[story[*edge].text.clone()])
+                .collect()
+        };
+        let path = super::path_between(&guide, &story, "intro", "secret")
+            .expect("bookmarks")
+            .expect("path");
+        assert_eq!(path[0].0, guide["intro"]);
+        assert_eq!(texts(&path), ["Run\n", "Open\n"]);
+        assert_eq!(
+            super::path_between(&guide, &story, "hall", "hall"),
+            Ok(Some(vec![]))
+        );
+        assert_eq!(
+            super::path_between(&guide, &story, "secret", "intro"),
+            Ok(None)
+        );
+        assert_eq!(
+            super::path_between(&guide, &story, "intro", "vault"),
+            Err(super::MissingBookmark("vault"))
+        );
+        let paths = super::all_paths(&guide, &story, "intro", "secret", 3).expect("bookmarks");
+        let paths: Vec<_> = paths.iter().map(|path| texts(path)).collect();
+        assert_eq!(
+            paths,
+            [vec!["Walk\n", "On\n", "Open\n"], vec!["Run\n", "Open\n"]]
+        );
+        let paths = super::all_paths(&guide, &story, "intro", "secret", 2).expect("bookmarks");
+        assert_eq!(paths.len(), 1);
+        assert_eq!(
+            super::all_paths(&guide, &story, "ghost", "secret", 2),
+            Err(super::MissingBookmark("ghost"))
+        );
+    }
+
+    #[test]
+    fn subgraphs() {
+        const SAMPLE: &str = "@bookmark{chapter-1}One\n@choice{chapter-2}Next\n\
+            @bookmark{chapter-2}Two\n@choice{a{1}}Left\n@choice{b}Right\n\
+            @bookmark{a{1}}A\n@choice{chapter-2}Back\n@bookmark{b}B\n@choice{end}On\n\
+            @bookmark{unused}Unused\n@bookmark{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        let (subguide, substory, crossing) = super::subgraph(&guide, &story, "chapter-2", SAMPLE);
+        assert_eq!(
+            crossing,
+            [super::CrossingChoice {
+                from_bookmark: "chapter-1",
+                target_name: "chapter-2",
+                edge: story
+                    .find_edge(guide["chapter-1"], guide["chapter-2"])
+                    .expect("choice"),
+            }]
+        );
+        let names = super::names_by_index(&subguide, &substory, SAMPLE);
+        assert_eq!(names, ["chapter-2", "a{1}", "b", "end"]);
+        for (name, index) in &subguide {
+            assert_eq!(substory[*index], story[guide[name]]);
+        }
+        assert_eq!(substory.edge_count(), 4);
+        let source = super::subgraph_source(&guide, &story, "chapter-2", SAMPLE);
+        assert_eq!(
+            source,
+            "@bookmark{chapter-2}Two\n@choice{{ a{1} }}Left\n@choice{b}Right\n\
+            @bookmark{{ a{1} }}A\n@choice{chapter-2}Back\n@bookmark{b}B\n@choice{end}On\n\
+            @bookmark{end}End."
+        );
+        let (read_guide, read_story) = super::read([source.as_str()]);
+        assert_eq!(read_guide.len(), subguide.len());
+        assert_eq!(read_story.edge_count(), substory.edge_count());
+        let (empty_guide, empty_story, crossing) =
+            super::subgraph(&guide, &story, "missing", SAMPLE);
+        assert!(empty_guide.is_empty() && crossing.is_empty());
+        assert_eq!(empty_story.node_count(), 0);
+    }
+
+    #[test]
+    fn merged() {
+        const COMMON: &str = "@bookmark{menu}Menu\n@choice{chapter-1}Play\n@bookmark{end}End.";
+        const CHAPTER: &str = "Intro.\n@choice{menu}Skipped\n@bookmark{chapter-1}One\n\
+            @choice{end}Quit\n@choice{epilogue}On\n@bookmark{end}Again";
+        let sources = [COMMON, CHAPTER];
+        let (guide, story, warnings) = super::merge(vec![(0, COMMON), (1, CHAPTER)]);
+        let text = |(source, range): &(usize, core::ops::Range<usize>)| -> &str {
+            &sources[*source][range.clone()]
+        };
+        assert_eq!(guide.len(), 3);
+        assert_eq!(text(&story[guide["menu"]].text), "Menu\n");
+        assert_eq!(text(&story[guide["end"]].text), "End.");
+        assert_eq!(text(&story[guide["chapter-1"]].text), "One\n");
+        let play = story
+            .find_edge(guide["menu"], guide["chapter-1"])
+            .expect("choice across sources");
+        assert_eq!(text(&story[play].text), "Play\n");
+        let quit = story
+            .find_edge(guide["chapter-1"], guide["end"])
+            .expect("choice across sources");
+        assert_eq!(text(&story[quit].text), "Quit\n");
+        assert_eq!(story.edge_count(), 2);
+        assert_eq!(
+            warnings,
+            [
+                super::MergeWarning::Source {
+                    source: 1,
+                    warning: super::ReadWarning::ChoiceOutsideBookmark {
+                        target_name: "menu",
+                        range: 7..20,
+                    },
+                },
+                super::MergeWarning::DuplicateBookmark {
+                    name: "end",
+                    first: (0, 43..57),
+                    duplicate: (1, 89..103),
+                },
+                super::MergeWarning::Source {
+                    source: 1,
+                    warning: super::ReadWarning::DanglingChoice {
+                        from_bookmark: "chapter-1",
+                        target_name: "epilogue",
+                        range: 69..86,
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn labels() {
+        const SAMPLE: &str =
+            "@bookmark{fork}Two paths.\n@choice{left | Take the left path}You turn left.\n\
+            @choice{right}You turn right.\n@choice{ left |}Back.\n\
+            @bookmark{left}Left.\n@bookmark{right}Right.";
+        let (guide, story) = super::read([SAMPLE]);
+        let choices: Vec<_> = super::story_choices(&story, guide["fork"])
+            .map(|(edge, _)| {
+                let (_, target) = story.edge_endpoints(edge).expect("endpoints");
+                (
+                    target,
+                    super::choice_label(&story, edge, SAMPLE),
+                    super::choice_text(&story, edge, SAMPLE),
+                )
+            })
+            .collect();
+        assert_eq!(
+            choices,
+            [
+                (
+                    guide["left"],
+                    Some("Take the left path"),
+                    Some("You turn left.\n")
+                ),
+                (guide["right"], None, Some("You turn right.\n")),
+                (guide["left"], None, Some("Back.\n")),
+            ]
+        );
+        let back = SAMPLE.find("@choice{ left |}").expect("back");
+        let (_, _, warnings) = super::read_checked([SAMPLE], super::ReadOptions::new());
+        assert_eq!(
+            warnings,
+            [super::ReadWarning::EmptyChoiceLabel {
+                target_name: "left",
+                range: back..back + "@choice{ left |}".len(),
+            }]
+        );
+        let owned = super::OwnedStory::from_ranges(&guide, &story, SAMPLE);
+        let labels: Vec<_> = owned
+            .edge_weights()
+            .map(|choice| choice.label.as_deref())
+            .collect();
+        assert_eq!(labels, [Some("Take the left path"), None, None]);
+    }
+
+    #[test]
+    fn conditions() {
+        const SAMPLE: &str = "@bookmark{hall}A door.\n@choice{vault ? has_key}Open it.\n\
+            @choice{vault | \"Knock? Again?\" ? knocks < 3 and not alarm}Knock.\n\
+            @choice{hall | Wait?}Wait.\n@bookmark{vault}Gold.";
+        let (guide, story, warnings) = super::read_checked([SAMPLE], super::ReadOptions::new());
+        assert_eq!(story.edge_count(), 3);
+        let wait = SAMPLE.find("@choice{hall").expect("wait");
+        assert_eq!(
+            warnings,
+            [super::ReadWarning::EmptyChoiceCondition {
+                target_name: "hall",
+                range: wait..wait + "@choice{hall | Wait?}".len(),
+            }]
+        );
+        let choices: Vec<_> = super::story_choices(&story, guide["hall"])
+            .map(|(edge, _)| {
+                let (_, target) = story.edge_endpoints(edge).expect("endpoints");
+                (
+                    target,
+                    super::choice_label(&story, edge, SAMPLE),
+                    super::choice_condition(&story, edge, SAMPLE),
+                )
+            })
+            .collect();
+        assert_eq!(
+            choices,
+            [
+                (guide["vault"], None, Some("has_key")),
+                (
+                    guide["vault"],
+                    Some("Knock? Again?"),
+                    Some("knocks < 3 and not alarm")
+                ),
+                (guide["hall"], Some("Wait"), None),
+            ]
+        );
+        #[cfg(feature = "std")]
+        {
+            let (_, streamed) = super::read_story_stream(SAMPLE.as_bytes()).expect("read");
+            let conditions: Vec<_> = streamed
+                .edge_weights()
+                .map(|choice| choice.condition.as_deref())
+                .collect();
+            assert_eq!(
+                conditions,
+                [Some("has_key"), Some("knocks < 3 and not alarm"), None]
+            );
+        }
+    }
+
+    #[test]
+    fn ignored_params() {
+        const SAMPLE: &str = "@bookmark{hall}A door.\n@choice{hall}(quietly) Leave.";
+        let (guide, story, warnings) = super::read_checked([SAMPLE], super::ReadOptions::new());
+        let leave = SAMPLE.find("@choice").expect("leave");
+        assert_eq!(
+            warnings,
+            [super::ReadWarning::IgnoredParams {
+                target_name: "hall",
+                range: leave..leave + "@choice{hall}(quietly)".len(),
+            }]
+        );
+        let (edge, _) = super::story_choices(&story, guide["hall"])
+            .next()
+            .expect("choice");
+        assert_eq!(super::choice_text(&story, edge, SAMPLE), Some(" Leave."));
+    }
+
+    #[test]
+    fn choice_weights() {
+        const SAMPLE: &str = "@bookmark{road}A fork.\n@choice{ambush * 3}Fight.\n\
+            @choice{camp}Rest.\n@choice{ambush*lots | Charge ? armed}Charge.\n\
+            @bookmark{ambush}Bandits!\n@bookmark{camp}Fire.";
+        let (guide, story, warnings) = super::read_checked([SAMPLE], super::ReadOptions::new());
+        let weights: Vec<_> = super::story_choices(&story, guide["road"])
+            .map(|(edge, choice)| {
+                let (_, target) = story.edge_endpoints(edge).expect("endpoints");
+                (target, choice.weight)
+            })
+            .collect();
+        assert_eq!(
+            weights,
+            [
+                (guide["ambush"], 3),
+                (guide["camp"], 1),
+                (guide["ambush"], 1)
+            ]
+        );
+        let lots = SAMPLE.find("lots").expect("weight");
+        assert_eq!(
+            warnings,
+            [super::ReadWarning::InvalidChoiceWeight {
+                target_name: "ambush",
+                weight: "lots",
+                range: lots..lots + "lots".len(),
+            }]
+        );
+        let errors = super::read_strict([SAMPLE]).expect_err("invalid weight");
+        assert_eq!(
+            errors[0].to_string(),
+            "4:16: choice of `ambush` has weight `lots`, which is not a whole number"
+        );
+    }
+
+    #[test]
+    fn meta() {
+        const SAMPLE: &str = "@bookmark{intro}\n@meta{author=Kai, mood=calm}\n\
+            @meta{ music = \"rain, soft\", mood=dark, draft}\nHi!\n@meta{late=1}\n\
+            @choice{end}Bye\n@bookmark{end}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        assert_eq!(
+            super::node_meta(&story, guide["intro"], SAMPLE),
+            [
+                ("author", Some("Kai")),
+                ("mood", Some("dark")),
+                ("music", Some("rain, soft")),
+                ("draft", None),
+            ]
+        );
+        assert_eq!(
+            super::story_text(&story, guide["intro"], SAMPLE),
+            Some("\nHi!\n@meta{late=1}\n")
+        );
+        assert!(super::node_meta(&story, guide["end"], SAMPLE).is_empty());
+        #[cfg(feature = "std")]
+        {
+            let (guide, streamed) = super::read_story_stream(SAMPLE.as_bytes()).expect("read");
+            let intro = &streamed[guide["intro"]];
+            assert_eq!(intro.meta.len(), 4);
+            assert_eq!(intro.meta[1], ("mood".to_owned(), Some("dark".to_owned())));
+            assert_eq!(intro.text, "\nHi!\n@meta{late=1}\n");
+        }
+    }
+
+    #[test]
+    fn tags() {
+        const SAMPLE: &str =
+            "@bookmark{glade}@tag{act1, forest}\n@meta{mood=calm}@tag{ forest,\"a, b\",}\n\
+            Trees.\n@tag{late}\n@bookmark{cabin}@tag{act1}Walls.\n@bookmark{end}@tag{ending}End.";
+        let (guide, story) = super::read([SAMPLE]);
+        assert_eq!(
+            super::node_tags(&story, guide["glade"], SAMPLE),
+            ["act1", "forest", "a, b"]
+        );
+        assert_eq!(
+            super::node_meta(&story, guide["glade"], SAMPLE),
+            [("mood", Some("calm"))]
+        );
+        assert_eq!(
+            super::bookmarks_with_tag(&guide, &story, SAMPLE, "act1"),
+            ["glade", "cabin"]
+        );
+        assert_eq!(
+            super::bookmarks_with_tag(&guide, &story, SAMPLE, "ending"),
+            ["end"]
+        );
+        assert!(super::bookmarks_with_tag(&guide, &story, SAMPLE, "late").is_empty());
+    }
+
+    #[test]
+    fn aliases() {
+        const SAMPLE: &str = "@bookmark{intro | start | beginning}Hi!\n@choice{end}Bye\n\
+            @bookmark{end | start | fin |}End.\n@choice{beginning}Again\n@choice{fin}Stay";
+        let (guide, story, warnings) = super::read_checked([SAMPLE], super::ReadOptions::new());
+        assert_eq!(story.node_count(), 2);
+        assert_eq!(guide.len(), 5);
+        assert_eq!(guide["start"], guide["intro"]);
+        assert_eq!(guide["beginning"], guide["intro"]);
+        assert_eq!(guide["fin"], guide["end"]);
+        let again = super::story_choices(&story, guide["end"])
+            .next()
+            .map(|(edge, _)| story.edge_endpoints(edge).expect("endpoints"));
+        assert_eq!(again, Some((guide["end"], guide["intro"])));
+        assert_eq!(
+            super::bookmark_names(&guide, &story, SAMPLE),
+            ["intro", "end"]
+        );
+        // nodes keep their names, so it doesn't matter where the keys of a guide are
+        let keys = ["fin", "beginning", "start", "end", "intro"].map(str::to_owned);
+        let copied: super::Guide = keys
+            .iter()
+            .map(|name| (name.as_str(), guide[name.as_str()]))
+            .collect();
+        assert_eq!(
+            super::bookmark_names(&copied, &story, SAMPLE),
+            ["intro", "end"]
+        );
+        assert_eq!(
+            super::bookmark_name(&story, guide["fin"], SAMPLE),
+            Some("end")
+        );
+        let end = SAMPLE.find("@bookmark{end").expect("end");
+        assert_eq!(
+            warnings,
+            [super::ReadWarning::TakenAlias {
+                alias: "start",
+                first_range: 0..SAMPLE.find("Hi!").expect("text"),
+                duplicate_range: end..SAMPLE.find("End.").expect("text"),
+            }]
+        );
+        #[cfg(feature = "std")]
+        {
+            let (guide, streamed) = super::read_story_stream(SAMPLE.as_bytes()).expect("read");
+            assert_eq!(streamed[guide["start"]].name, "intro");
+        }
+    }
+
+    #[test]
+    fn normalized_names() {
+        const SAMPLE: &str = "@bookmark{The Cave | Old_Mine}Dark.\n@choice{the-cave}Stay\n\
+            @choice{old  mine}Dig\n@choice{nowhere}Wander\n@bookmark{the_cave}Again?";
+        assert_eq!(super::normalize_name(" The  Cave_"), "the-cave");
+        assert_eq!(super::normalize_name("--Ёлка--Tree"), "ёлка-tree");
+        let options = super::ReadOptions::new().normalize_names(true);
+        let (guide, story, warnings) = super::read_checked([SAMPLE], options);
+        assert_eq!(story.node_count(), 1);
+        assert_eq!(story.edge_count(), 2);
+        assert!(guide.contains_key("The Cave") && guide.contains_key("Old_Mine"));
+        assert_eq!(super::bookmark_names(&guide, &story, SAMPLE), ["The Cave"]);
+        assert_eq!(
+            warnings,
+            [
+                super::ReadWarning::CollidingName {
+                    name: "the_cave",
+                    first_name: "The Cave",
+                    first_range: 0..30,
+                    duplicate_range: 103..122,
+                },
+                super::ReadWarning::DanglingChoice {
+                    from_bookmark: "The Cave",
+                    target_name: "nowhere",
+                    range: 80..96,
+                },
+            ]
+        );
+        // bookmarks are found by slugs as well as by names as written
+        for name in ["the-cave", "The Cave", "old mine"] {
+            assert_eq!(
+                super::path_between(&guide, &story, name, "Old_Mine"),
+                Ok(Some(Vec::new()))
+            );
+            assert!(super::unreachable_from(&guide, &story, name, SAMPLE).is_empty());
+            assert_eq!(
+                super::subgraph(&guide, &story, name, SAMPLE).1.node_count(),
+                1
+            );
+            assert!(crate::Playthrough::new(&guide, &story, name, SAMPLE).is_ok());
+        }
+        assert_eq!(
+            super::path_between(&guide, &story, "nowhere", "the-cave"),
+            Err(super::MissingBookmark("nowhere"))
+        );
+        let (_, story, _) = super::read_checked([SAMPLE], super::ReadOptions::new());
+        assert_eq!(story.node_count(), 2);
+        assert_eq!(story.edge_count(), 0);
+    }
+
+    #[test]
+    fn implicit_start() {
+        const SAMPLE: &str = "Once upon a time.\n@choice{cave}Go\n@bookmark{cave}Dark.";
+        let options = super::ReadOptions::new().implicit_start(Some("start"));
+        let (guide, story, warnings) = super::read_checked([SAMPLE], options);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(
+            super::bookmark_names(&guide, &story, SAMPLE),
+            ["start", "cave"]
+        );
+        assert_eq!(
+            super::story_text(&story, guide["start"], SAMPLE),
+            Some("Once upon a time.\n")
+        );
+        assert_eq!(story.neighbors(guide["start"]).next(), Some(guide["cave"]));
+        let (guide, story) = super::read_with_options(["\n @bookmark{cave}Dark."], options);
+        assert_eq!(super::bookmark_names(&guide, &story, SAMPLE), ["cave"]);
+        let (guide, story, warnings) = super::read_checked([SAMPLE], super::ReadOptions::new());
+        assert_eq!(super::bookmark_names(&guide, &story, SAMPLE), ["cave"]);
+        assert_eq!(warnings.len(), 1);
     }
 }
@@ -0,0 +1,134 @@
+use crate::{
+    choice_label, choice_text, graph::names_by_index, node_events, story_choices, Guide, Story,
+};
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+/// Writes `text` escaped as XML, with whitespace runs collapsed into single spaces
+fn push_escaped(out: &mut String, text: &str) {
+    for (word_index, word) in text.split_whitespace().enumerate() {
+        if word_index != 0 {
+            out.push(' ');
+        }
+        for ch in word.chars() {
+            match ch {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                '"' => out.push_str("&quot;"),
+                '\'' => out.push_str("&apos;"),
+                ch => out.push(ch),
+            }
+        }
+    }
+}
+
+fn write_graphml(out: &mut String, guide: &Guide, story: &Story, full: &str) -> fmt::Result {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    writeln!(
+        out,
+        r#"  <key id="name" for="node" attr.name="name" attr.type="string"/>"#
+    )?;
+    writeln!(
+        out,
+        r#"  <key id="wordcount" for="node" attr.name="wordcount" attr.type="int"/>"#
+    )?;
+    writeln!(
+        out,
+        r#"  <key id="label" for="edge" attr.name="label" attr.type="string"/>"#
+    )?;
+    writeln!(out, r#"  <graph id="story" edgedefault="directed">"#)?;
+    let names = names_by_index(guide, story, full);
+    for node in story.node_indices() {
+        let wordcount: usize = node_events(story, node, full)
+            .text()
+            .map(|(_, content)| content.slice.split_whitespace().count())
+            .sum();
+        writeln!(out, r#"    <node id="n{}">"#, node.index())?;
+        out.push_str(r#"      <data key="name">"#);
+        push_escaped(out, names[node.index()]);
+        writeln!(out, "</data>")?;
+        writeln!(out, r#"      <data key="wordcount">{wordcount}</data>"#)?;
+        writeln!(out, "    </node>")?;
+    }
+    for node in story.node_indices() {
+        for (edge, _) in story_choices(story, node) {
+            let Some((_, target)) = story.edge_endpoints(edge) else {
+                continue;
+            };
+            writeln!(
+                out,
+                r#"    <edge id="e{}" source="n{}" target="n{}">"#,
+                edge.index(),
+                node.index(),
+                target.index()
+            )?;
+            out.push_str(r#"      <data key="label">"#);
+            let text = choice_label(story, edge, full).or_else(|| choice_text(story, edge, full));
+            push_escaped(out, text.unwrap_or_default());
+            writeln!(out, "</data>")?;
+            writeln!(out, "    </edge>")?;
+        }
+    }
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</graphml>")
+}
+
+/// Writes a story read from `full` in `GraphML`, e.g. to lay it out in yEd.
+/// Nodes have `name` and `wordcount` of their text, and edges have `label`
+/// of choice labels, or of choice text for choices without one.
+#[must_use]
+pub fn to_graphml(guide: &Guide, story: &Story, full: &str) -> String {
+    let mut out = String::new();
+    // writing to a string never fails
+    let _ = write_graphml(&mut out, guide, story, full);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_graphml;
+
+    #[test]
+    fn expected() {
+        const SAMPLE: &str = "@bookmark{greet}\n– Hello, @name you!\n@choice{end}– Bye\n\
+            @bookmark{end}\nEnd.";
+        let (guide, story) = crate::read([SAMPLE]);
+        assert_eq!(
+            to_graphml(&guide, &story, SAMPLE),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="name" for="node" attr.name="name" attr.type="string"/>
+  <key id="wordcount" for="node" attr.name="wordcount" attr.type="int"/>
+  <key id="label" for="edge" attr.name="label" attr.type="string"/>
+  <graph id="story" edgedefault="directed">
+    <node id="n0">
+      <data key="name">greet</data>
+      <data key="wordcount">3</data>
+    </node>
+    <node id="n1">
+      <data key="name">end</data>
+      <data key="wordcount">1</data>
+    </node>
+    <edge id="e0" source="n0" target="n1">
+      <data key="label">– Bye</data>
+    </edge>
+  </graph>
+</graphml>
+"#
+        );
+    }
+
+    #[test]
+    fn escaped() {
+        const SAMPLE: &str = "@bookmark{Q&A <\"1\">}Hi!\n@choice{Q&A <\"1\">}a < b && c";
+        let (guide, story) = crate::read([SAMPLE]);
+        let graphml = to_graphml(&guide, &story, SAMPLE);
+        assert!(graphml.contains(r#"<data key="name">Q&amp;A &lt;&quot;1&quot;&gt;</data>"#));
+        assert!(graphml.contains(r#"<data key="label">a &lt; b &amp;&amp; c</data>"#));
+    }
+}
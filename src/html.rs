@@ -0,0 +1,424 @@
+use crate::render::{lines, Line, Piece};
+use crate::{Event, EventIter, Style};
+use alloc::{string::String, vec::Vec};
+
+/// Styles that are rendered within a line, along with their tags and classes,
+/// in the order they are opened
+const TAGS: [(Style, &str, &str); 5] = [
+    (Style::SCRATCH, "del", "scratch"),
+    (Style::BOLD, "strong", "bold"),
+    (Style::ITALIC, "em", "italic"),
+    (Style::UNDERLINE, "u", "underline"),
+    (Style::CODE, "code", "code"),
+];
+
+/// Schemes of urls that links may have, besides relative urls that have none
+const SAFE_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Styles that are rendered within a line
+const INLINE: Style = Style::SCRATCH
+    .union(Style::BOLD)
+    .union(Style::ITALIC)
+    .union(Style::UNDERLINE)
+    .union(Style::CODE);
+
+/// Options of rendering HTML, see [`to_html`]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct HtmlOptions<'a> {
+    class_prefix: Option<&'a str>,
+    line_breaks: bool,
+    keep_signals: bool,
+}
+
+impl<'a> HtmlOptions<'a> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            class_prefix: None,
+            line_breaks: true,
+            keep_signals: true,
+        }
+    }
+
+    /// Prefix of classes that elements get after what they stand for, e.g. `choco-bold`
+    /// for `<strong>` with `choco-`, or `None` for no classes, which is the default
+    #[must_use]
+    pub const fn class_prefix(self, class_prefix: Option<&'a str>) -> Self {
+        Self {
+            class_prefix,
+            ..self
+        }
+    }
+
+    /// Whether line breaks within a paragraph become `<br>`, which they do by default,
+    /// or are left as they are, so that lines flow together
+    #[must_use]
+    pub const fn line_breaks(self, line_breaks: bool) -> Self {
+        Self {
+            line_breaks,
+            ..self
+        }
+    }
+
+    /// Whether signals that aren't a part of the story, e.g. `@wave`, become empty
+    /// `<span data-signal="@wave">`, which they do by default, or are left out
+    #[must_use]
+    pub const fn keep_signals(self, keep_signals: bool) -> Self {
+        Self {
+            keep_signals,
+            ..self
+        }
+    }
+}
+
+impl<'a> Default for HtmlOptions<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adds `text` with `&`, `<`, `>` and `"` escaped, so that it's safe in text and attributes
+fn push_escaped(out: &mut String, text: &str) {
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(ch),
+        }
+    }
+}
+
+/// Adds the start tag of `tag` with `class` after the prefix of `options`, if there's one,
+/// and `attributes` as they are
+fn push_start(out: &mut String, tag: &str, class: &str, attributes: &str, options: HtmlOptions) {
+    out.push('<');
+    out.push_str(tag);
+    out.push_str(attributes);
+    if let Some(prefix) = options.class_prefix {
+        out.push_str(" class=\"");
+        push_escaped(out, prefix);
+        out.push_str(class);
+        out.push('"');
+    }
+    out.push('>');
+}
+
+/// Whether `url` is relative or has one of [`SAFE_SCHEMES`], rather than e.g. `javascript:`,
+/// skipping whitespace and control chars, as browsers do in `java\tscript:`
+fn is_safe_url(url: &str) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|ch| !ch.is_whitespace() && !ch.is_control())
+        .collect();
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => SAFE_SCHEMES
+            .iter()
+            .any(|safe| scheme.eq_ignore_ascii_case(safe)),
+        _ => true,
+    }
+}
+
+fn push_end(out: &mut String, tag: &str) {
+    out.push_str("</");
+    out.push_str(tag);
+    out.push('>');
+}
+
+fn push_styled(out: &mut String, style: Style, text: &str, options: HtmlOptions) {
+    for (styled, tag, class) in TAGS {
+        if style.contains(styled) {
+            push_start(out, tag, class, "", options);
+        }
+    }
+    push_escaped(out, text);
+    for (styled, tag, _) in TAGS.into_iter().rev() {
+        if style.contains(styled) {
+            push_end(out, tag);
+        }
+    }
+}
+
+/// Pieces of a line with their inline styles, where text of the same style is merged
+fn push_pieces(out: &mut String, pieces: &[(Style, Piece)], options: HtmlOptions) {
+    let mut run: Option<(Style, String)> = None;
+    for (style, piece) in pieces {
+        let style = *style & INLINE;
+        if let Piece::Text(text) = piece {
+            match &mut run {
+                Some((prev, prev_text)) if *prev == style => prev_text.push_str(text),
+                _ => {
+                    if let Some((prev, prev_text)) = run.take() {
+                        push_styled(out, prev, &prev_text, options);
+                    }
+                    run = Some((style, text.clone()));
+                }
+            }
+            continue;
+        }
+        if let Some((prev, prev_text)) = run.take() {
+            push_styled(out, prev, &prev_text, options);
+        }
+        let mut attributes = String::new();
+        match piece {
+            Piece::Link { text, url } if !is_safe_url(url) => {
+                push_styled(out, style, text, options);
+            }
+            Piece::Link { text, url } => {
+                attributes.push_str(" href=\"");
+                push_escaped(&mut attributes, url);
+                attributes.push('"');
+                push_start(out, "a", "link", &attributes, options);
+                push_styled(out, style, text, options);
+                push_end(out, "a");
+            }
+            Piece::Signal(written) => {
+                attributes.push_str(" data-signal=\"");
+                push_escaped(&mut attributes, written);
+                attributes.push('"');
+                push_start(out, "span", "signal", &attributes, options);
+                push_end(out, "span");
+            }
+            Piece::Text(_) => unreachable!("text is merged into runs"),
+        }
+    }
+    if let Some((style, text)) = run {
+        push_styled(out, style, &text, options);
+    }
+}
+
+/// Element that lines go into within a quote, if they are in one
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Block {
+    Paragraph,
+    Panel,
+    Heading(u8),
+}
+
+impl Block {
+    fn of(line: &Line) -> Self {
+        if line.style().contains(Style::PANEL) {
+            Self::Panel
+        } else {
+            line.heading_level().map_or(Self::Paragraph, Self::Heading)
+        }
+    }
+}
+
+/// Adds lines of a single block, each one in an element of its own if they are headings
+fn push_block(out: &mut String, block: Block, lines: &[&Line], options: HtmlOptions) {
+    let (tag, class, separator) = match block {
+        Block::Paragraph if options.line_breaks => ("p", "paragraph", "<br>\n"),
+        Block::Paragraph => ("p", "paragraph", "\n"),
+        Block::Panel => ("pre", "panel", "\n"),
+        Block::Heading(level) => {
+            let tag = ["h1", "h2", "h3"][usize::from(level) - 1];
+            for line in lines {
+                push_block_lines(out, tag, "heading", "", &[line], options);
+            }
+            return;
+        }
+    };
+    push_block_lines(out, tag, class, separator, lines, options);
+}
+
+fn push_block_lines(
+    out: &mut String,
+    tag: &str,
+    class: &str,
+    separator: &str,
+    lines: &[&Line],
+    options: HtmlOptions,
+) {
+    push_start(out, tag, class, "", options);
+    for (index, line) in lines.iter().enumerate() {
+        if index != 0 {
+            out.push_str(separator);
+        }
+        let mut body = String::new();
+        push_pieces(&mut body, &line.pieces, options);
+        out.push_str(body.trim());
+    }
+    push_end(out, tag);
+    out.push('\n');
+}
+
+/// Renders styled events of `full` as HTML, e.g. to preview a story elsewhere,
+/// where `full` tells if there's whitespace between the events, as it's trimmed off the text.
+///
+/// Scratched, bold, italic, underlined and code text go into `<del>`, `<strong>`, `<em>`, `<u>`
+/// and `<code>`, opened in this order, and links go into `<a>`, unless their urls have schemes
+/// other than `http`, `https` and `mailto`, e.g. `javascript:`, which leaves their text alone.
+/// Lines between paragraph breaks go into a single `<p>`, except for those of panels that follow
+/// one another, which go into a `<pre>`, and headings, each of which goes into `<h1>` to `<h3>`
+/// after its level.
+/// Lines of quotes that follow one another go into a single `<blockquote>` the same way.
+/// Signals the story is made of, e.g. `@bookmark`, are left out, and so are lines with nothing else.
+/// Text and attributes are escaped, so that none of the text is taken for markup.
+#[must_use]
+pub fn render_html<'a>(
+    events: impl IntoIterator<Item = Event<'a>>,
+    full: &str,
+    options: HtmlOptions,
+) -> String {
+    let lines = lines(events, full, options.keep_signals);
+    let mut sections: Vec<Vec<&Line>> = Vec::new();
+    let mut is_paragraph = true;
+    for line in &lines {
+        if line.is_blank() {
+            is_paragraph = true;
+            continue;
+        }
+        match sections.last_mut() {
+            Some(section) if !is_paragraph && !line.is_paragraph => section.push(line),
+            _ => sections.push(alloc::vec![line]),
+        }
+        is_paragraph = false;
+    }
+    let mut out = String::new();
+    for section in sections {
+        for quote in section.chunk_by(|prev, next| {
+            prev.style().contains(Style::QUOTE) == next.style().contains(Style::QUOTE)
+        }) {
+            let is_quote = quote[0].style().contains(Style::QUOTE);
+            if is_quote {
+                push_start(&mut out, "blockquote", "quote", "", options);
+                out.push('\n');
+            }
+            for block in quote.chunk_by(|prev, next| Block::of(prev) == Block::of(next)) {
+                push_block(&mut out, Block::of(block[0]), block, options);
+            }
+            if is_quote {
+                push_end(&mut out, "blockquote");
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// Renders `text` as HTML, see [`render_html`]
+#[must_use]
+pub fn to_html(text: &str, options: HtmlOptions) -> String {
+    render_html(EventIter::new(text), text, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_html, HtmlOptions};
+    use quickcheck::quickcheck;
+
+    const PIECES: [&str; 16] = [
+        "@",
+        "{",
+        "}",
+        "<",
+        ">",
+        "&",
+        "\"",
+        " ",
+        "\n",
+        "\n\n",
+        "text",
+        "@style{b}",
+        "@style{qp}",
+        "@style{1}",
+        "@wave",
+        "@bookmark{a}",
+    ];
+
+    const TAGS: [&str; 7] = ["p", "br", "strong", "blockquote", "pre", "h1", "span"];
+
+    #[test]
+    fn inline() {
+        const SAMPLE: &str = "@bookmark{greet}Hello, @style{b}@{dear} @style{bi}{friend}!\n\
+            @style{c}{let x = 1;} or @style{su}{gone} and @link{https://a.b}@{docs}";
+        assert_eq!(
+            to_html(SAMPLE, HtmlOptions::new()),
+            "<p>Hello, <strong>dear</strong> <strong><em>friend</em></strong>!<br>\n\
+            <code>let x = 1;</code> or <del><u>gone</u></del> and <a href=\"https://a.b\">docs</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn escaped() {
+        const SAMPLE: &str = "Tom & \"Jerry\" <script>\n@style{c}{a < b && c > d} \
+            @link{https://a.b/?q=\"x\"&y=<z>}@{<i>}";
+        assert_eq!(
+            to_html(SAMPLE, HtmlOptions::new()),
+            "<p>Tom &amp; &quot;Jerry&quot; &lt;script&gt;<br>\n\
+            <code>a &lt; b &amp;&amp; c &gt; d</code> \
+            <a href=\"https://a.b/?q=&quot;x&quot;&amp;y=&lt;z&gt;\">&lt;i&gt;</a></p>\n"
+        );
+        let options = HtmlOptions::new().class_prefix(Some("\"><x"));
+        assert_eq!(
+            to_html("@style{b}{Bold}", options),
+            "<p class=\"&quot;&gt;&lt;xparagraph\"><strong class=\"&quot;&gt;&lt;xbold\">Bold</strong></p>\n"
+        );
+    }
+
+    #[test]
+    fn unsafe_links() {
+        const SAMPLE: &str = "@link{javascript:alert(1)}@{a} @link{ Java\tScript:x}@{b} \
+            @link{/c?d=e:f}@{c} @link{MAILTO:x@y.z}@{d} @style{b}@link{data:text/html,x}@{e}";
+        assert_eq!(
+            to_html(SAMPLE, HtmlOptions::new()),
+            "<p>a b <a href=\"/c?d=e:f\">c</a> <a href=\"MAILTO:x@y.z\">d</a> <strong>e</strong></p>\n"
+        );
+    }
+
+    #[test]
+    fn blocks() {
+        const SAMPLE: &str = "@style{1}{Title}\nFirst\nSecond\n\n\
+            @style{q}{Quoted}\n@style{qb}{bold}\n@style{qp}{quoted panel}\n\
+            @style{p}{let a = 1;}\n@style{p}{let b = 2;}\nAfter\n@bookmark{next}\n@style{2}{End}";
+        assert_eq!(
+            to_html(SAMPLE, HtmlOptions::new()),
+            "<h1>Title</h1>\n<p>First<br>\nSecond</p>\n\
+            <blockquote>\n<p>Quoted<br>\n<strong>bold</strong></p>\n<pre>quoted panel</pre>\n</blockquote>\n\
+            <pre>let a = 1;\nlet b = 2;</pre>\n<p>After</p>\n<h2>End</h2>\n"
+        );
+        let options = HtmlOptions::new().line_breaks(false);
+        assert_eq!(to_html("First\nSecond", options), "<p>First\nSecond</p>\n");
+    }
+
+    #[test]
+    fn classes_and_signals() {
+        const SAMPLE: &str = "Hi @wave{\"hand\"} @style{u}{there} @link{https://a.b}\n\
+            @style{p}{code}";
+        let options = HtmlOptions::new().class_prefix(Some("choco-"));
+        assert_eq!(
+            to_html(SAMPLE, options),
+            "<p class=\"choco-paragraph\">Hi \
+            <span data-signal=\"@wave{&quot;hand&quot;}\" class=\"choco-signal\"></span> \
+            <u class=\"choco-underline\">there</u> \
+            <a href=\"https://a.b\" class=\"choco-link\">https://a.b</a></p>\n\
+            <pre class=\"choco-panel\">code</pre>\n"
+        );
+        let options = HtmlOptions::new().keep_signals(false);
+        assert_eq!(
+            to_html(SAMPLE, options),
+            "<p>Hi <u>there</u> <a href=\"https://a.b\">https://a.b</a></p>\n<pre>code</pre>\n"
+        );
+    }
+
+    quickcheck! {
+        fn arbitrary_escaped(indices: Vec<u8>) -> bool {
+            let text: String = indices
+                .into_iter()
+                .map(|index| PIECES[usize::from(index) % PIECES.len()])
+                .collect();
+            let mut html = to_html(&text, HtmlOptions::new().keep_signals(false));
+            for tag in TAGS {
+                let start = format!("<{tag}>");
+                let end = format!("</{tag}>");
+                if html.matches(&start).count() != html.matches(&end).count() && tag != "br" {
+                    return false;
+                }
+                html = html.replace(&start, "").replace(&end, "");
+            }
+            !html.contains(['<', '>', '"'])
+        }
+    }
+}
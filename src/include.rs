@@ -0,0 +1,268 @@
+use crate::core::{Event, Iter, Signal};
+use crate::{read_checked, Guide, ReadOptions, ReadWarning, SourceId, Story};
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::ops::Range;
+
+/// Something off about an `include` signal, which is left out, see [`read_with_includes`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum IncludeWarning {
+    /// `@include` of a path that the resolver has no text for.
+    /// Range is the span of the signal in `source`.
+    Missing {
+        source: SourceId,
+        path: String,
+        range: Range<usize>,
+    },
+    /// `@include` of a path that is being included already, which would include itself forever.
+    /// Range is the span of the signal in `source`.
+    Cycle {
+        source: SourceId,
+        path: String,
+        range: Range<usize>,
+    },
+    /// `@include` with nothing but whitespace for the path, as in `@include{}`.
+    /// Range is the span of the signal in `source`.
+    EmptyPath {
+        source: SourceId,
+        range: Range<usize>,
+    },
+}
+
+/// Texts of a story split into several, gathered by following `include` signals,
+/// indexed by their [`SourceId`]s, with the root text being the first one,
+/// along with the text of the whole story that they make once spliced together
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Includes {
+    /// Paths of the texts, which is empty for the root one
+    paths: Vec<String>,
+    texts: Vec<String>,
+    /// Text of every source, with each included one in place of the signal that includes it
+    spliced: String,
+    /// Starts of the parts of `spliced`, each along with the source it's from and where it starts there
+    parts: Vec<(usize, SourceId, usize)>,
+    warnings: Vec<IncludeWarning>,
+    options: ReadOptions,
+}
+
+impl Includes {
+    /// Path that the text of `source` is included by, if there is such a text
+    #[must_use]
+    pub fn path(&self, source: SourceId) -> Option<&str> {
+        self.paths.get(source).map(String::as_str)
+    }
+
+    /// Text of `source`, if there is such a text
+    #[must_use]
+    pub fn text(&self, source: SourceId) -> Option<&str> {
+        self.texts.get(source).map(String::as_str)
+    }
+
+    /// Text of the whole story, with each included text in place of the first signal
+    /// that includes it, and `include` signals left out
+    #[must_use]
+    pub fn spliced(&self) -> &str {
+        &self.spliced
+    }
+
+    /// Source that `offset` of the [`Includes::spliced`] text is in, along with the offset there,
+    /// e.g. to tell which file a range of the story points to
+    #[must_use]
+    pub fn source(&self, offset: usize) -> Option<(SourceId, usize)> {
+        if offset > self.spliced.len() {
+            return None;
+        }
+        let index = self.parts.partition_point(|(start, ..)| *start <= offset);
+        let (start, source, source_start) = self.parts[index.checked_sub(1)?];
+        Some((source, source_start + offset - start))
+    }
+
+    /// Includes that are left out, in the order they are found
+    #[must_use]
+    pub fn warnings(&self) -> &[IncludeWarning] {
+        &self.warnings
+    }
+
+    /// Story read from the [`Includes::spliced`] text, see [`read_checked`]
+    #[must_use]
+    pub fn read(&self) -> (Guide<'_>, Story, Vec<ReadWarning<&str>>) {
+        read_checked([self.spliced.as_str()], self.options)
+    }
+
+    /// Adds the part of the text of `source` from `source_start` to `source_end` to the spliced text
+    fn push_part(&mut self, source: SourceId, source_start: usize, source_end: usize) {
+        self.parts.push((self.spliced.len(), source, source_start));
+        let text = &self.texts[source][source_start..source_end];
+        self.spliced.push_str(text);
+    }
+
+    /// Adds the text of `source` to the spliced text, along with the texts included by it,
+    /// and those included by them, where `stack` has sources that are being included
+    fn splice(
+        &mut self,
+        source: SourceId,
+        resolver: &mut impl FnMut(&str) -> Option<String>,
+        stack: &mut Vec<SourceId>,
+    ) {
+        stack.push(source);
+        let mut includes = Vec::new();
+        for event in Iter::new(&self.texts[source]) {
+            if let Event::Signal(Signal::Call {
+                prompt,
+                param,
+                span,
+                ..
+            }) = event
+            {
+                if self.options.is_prompt(prompt.slice, "include") {
+                    includes.push((param.trimmed().slice.to_owned(), span));
+                }
+            }
+        }
+        let mut part_start = 0;
+        for (path, range) in includes {
+            self.push_part(source, part_start, range.start);
+            part_start = range.end;
+            if path.is_empty() {
+                self.warnings
+                    .push(IncludeWarning::EmptyPath { source, range });
+                continue;
+            }
+            match self.paths.iter().position(|prev| *prev == path) {
+                Some(prev) if stack.contains(&prev) => {
+                    self.warnings.push(IncludeWarning::Cycle {
+                        source,
+                        path,
+                        range,
+                    });
+                }
+                // included elsewhere already, so that its bookmarks aren't duplicated
+                Some(_) => (),
+                None => match resolver(&path) {
+                    Some(text) => {
+                        self.paths.push(path);
+                        self.texts.push(text);
+                        self.splice(self.texts.len() - 1, resolver, stack);
+                    }
+                    None => self.warnings.push(IncludeWarning::Missing {
+                        source,
+                        path,
+                        range,
+                    }),
+                },
+            }
+        }
+        self.push_part(source, part_start, self.texts[source].len());
+        stack.pop();
+    }
+}
+
+/// Gathers texts of a story split into several, e.g. one file per chapter, starting from `root`,
+/// which may have `@include{chapters/forest.choco}` signals to add the text that `resolver` has for
+/// the path, and so on for the included texts. Each text goes in place of the first signal
+/// that includes it, however many times it is written, see [`Includes::spliced`].
+#[must_use]
+pub fn read_with_includes(root: &str, resolver: impl FnMut(&str) -> Option<String>) -> Includes {
+    read_with_includes_with_options(root, ReadOptions::new(), resolver)
+}
+
+/// Same as [`read_with_includes`], but with prompts compared as [`ReadOptions`] tell,
+/// which the story is read with as well
+#[must_use]
+pub fn read_with_includes_with_options(
+    root: &str,
+    options: ReadOptions,
+    mut resolver: impl FnMut(&str) -> Option<String>,
+) -> Includes {
+    let mut includes = Includes {
+        paths: alloc::vec![String::new()],
+        texts: alloc::vec![root.to_owned()],
+        options,
+        ..Includes::default()
+    };
+    includes.splice(0, &mut resolver, &mut Vec::new());
+    includes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_with_includes, read_with_includes_with_options, IncludeWarning};
+    use crate::ReadOptions;
+    use petgraph::graph::NodeIndex;
+
+    fn resolver(path: &str) -> Option<String> {
+        match path {
+            "forest.choco" => {
+                Some("@include{cave.choco}\n@bookmark{forest}Trees.\n@choice{cave}In")
+            }
+            "cave.choco" => {
+                Some("@include{forest.choco}@include{ lost.choco }\n@bookmark{cave}Dark.")
+            }
+            _ => None,
+        }
+        .map(str::to_owned)
+    }
+
+    #[test]
+    fn chapters() {
+        const ROOT: &str = "@include{forest.choco}@include{cave.choco}\n\
+            @bookmark{intro}Hi!\n@choice{forest}Walk";
+        let includes = read_with_includes(ROOT, resolver);
+        assert_eq!(includes.path(1), Some("forest.choco"));
+        assert_eq!(includes.path(2), Some("cave.choco"));
+        assert_eq!(includes.path(3), None);
+        assert_eq!(
+            includes.warnings(),
+            [
+                IncludeWarning::Cycle {
+                    source: 2,
+                    path: "forest.choco".to_owned(),
+                    range: 0..22,
+                },
+                IncludeWarning::Missing {
+                    source: 2,
+                    path: "lost.choco".to_owned(),
+                    range: 22..44,
+                },
+            ]
+        );
+        assert_eq!(
+            includes.spliced(),
+            "\n@bookmark{cave}Dark.\n@bookmark{forest}Trees.\n@choice{cave}In\n\
+            @bookmark{intro}Hi!\n@choice{forest}Walk"
+        );
+        let (guide, story, warnings) = includes.read();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(story.node_count(), 3);
+        assert_eq!(story.edge_count(), 2);
+        let source = |name| {
+            let (source, start) = includes
+                .source(story[guide[name]].text.start)
+                .expect("source");
+            (source, &includes.text(source).expect("text")[start..])
+        };
+        assert_eq!(source("cave"), (2, "Dark."));
+        assert_eq!(source("intro"), (0, "Hi!\n@choice{forest}Walk"));
+        assert_eq!(
+            story.neighbors(guide["forest"]).collect::<Vec<NodeIndex>>(),
+            [guide["cave"]]
+        );
+        assert_eq!(includes.source(includes.spliced().len() + 1), None);
+    }
+
+    #[test]
+    fn empty_path() {
+        let includes = read_with_includes("@include{ }Hi", |_| panic!("resolved"));
+        assert_eq!(
+            includes.warnings(),
+            [IncludeWarning::EmptyPath {
+                source: 0,
+                range: 0..11
+            }]
+        );
+        assert_eq!(includes.spliced(), "Hi");
+        let options = ReadOptions::new().case_insensitive_prompts(true);
+        let includes =
+            read_with_includes_with_options("@INCLUDE{a}!", options, |_| Some("Hi".to_owned()));
+        assert_eq!(includes.spliced(), "Hi!");
+    }
+}
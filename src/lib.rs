@@ -6,6 +6,13 @@
 //! - both prompt and parameter (e.g. `@bookmark{into}`)
 //! - or neither (e.g. `Pay attention! @`).
 //!
+//! A literal `@` followed by a non-whitespace character is written by doubling it (e.g. `email me @@home`).
+//! A prompt ends at whitespace, a bracket or another `@`, so signals may follow each other (e.g. `@wave@bookmark{into}`).
+//! Parameters may contain balanced brackets of the same kind (e.g. `@bookmark{act{1}}`).
+//! A backslash escapes the next character in a parameter, so it never counts as a bracket (e.g. `@note{see \}3}`).
+//! A doubled left bracket starts a verbatim parameter, running until a doubled right one with nothing inside interpreted (e.g. `@code{{ map[&k] }}`).
+//! Comments are never emitted: `@--` comments out the rest of the line, and `@--{...}` is an inline comment.
+//!
 //! Three signal prompts are taken by Choco. These are `bookmark`, `choice` and `style`.
 //!
 //! ### Branching
@@ -31,6 +38,10 @@
 //! @style{qbp}@{- Hello, you!}
 //! ```
 //!
+//! The text may as well go right after the style names, as in `@style{qbp}{- Hello, you!}`. Styles of signals written one after another add up, as in `@style{b}@style{i}@{Bold italic}`. Params written right after the text get the same style, as in `@style{q}@{line one}@{line two}`. Without a param, the style goes to the text right after the signal, up to the next signal or line break, as in `@style{b}Bold @wave regular`.
+//!
+//! Styles may also be kept on across lines, as in `@style{b+}`, until closed by `@style{b-}`, or by `@style{-}` for every one of them, where those left open end with the text.
+//!
 //! Style names are slightly opinionated, but you decide how to display a mix of them:
 //!
 //! | Char | Style       | Note                           |
@@ -41,13 +52,96 @@
 //! | b    | *Bold*      |                                |
 //! | i    | **Italic**  |                                |
 //! | s    | ~~Scratch~~ | i.e. strike-through            |
+//! | u    | Underline   |                                |
+//! | 1    | # Heading   | largest of levels 1 to 3 wins  |
+//! | 2    | ## Heading  |                                |
+//! | 3    | ### Heading |                                |
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
+mod bytes;
+mod compose;
 mod core;
+mod diff;
+mod document;
+#[cfg(feature = "export")]
+mod dot;
+mod edit;
+#[cfg(feature = "async")]
+mod event_stream;
+mod format;
 mod graph;
+#[cfg(feature = "export")]
+mod graphml;
+#[cfg(feature = "export")]
+mod html;
+mod include;
+mod lint;
+mod localize;
+#[cfg(feature = "export")]
+mod markdown;
+mod owned;
+mod parse;
+mod play;
+#[cfg(feature = "export")]
+mod render;
+mod stats;
+#[cfg(feature = "std")]
+mod stream;
 mod style;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod validate;
+mod visit;
+mod write;
 
 pub use petgraph;
 
-pub use core::{Signal, StrRange};
-pub use graph::{read, Guide, Story};
-pub use style::{event_iter, Event, EventIter, Style};
+pub use bytes::event_iter_bytes;
+pub use compose::compose;
+pub use core::{Diagnostic, Limit, LineIndex, ParseConfig, ParseLimits, Signal, Spacing, StrRange};
+pub use diff::{diff, StoryChange, StoryDiff};
+pub use document::Document;
+#[cfg(feature = "export")]
+pub use dot::{to_dot, to_dot_with_options, DotOptions, RankDir};
+pub use edit::{insert_bookmark, insert_choice, rename_bookmark, EditError, RenameError};
+#[cfg(feature = "async")]
+pub use event_stream::{stream_events, EventStream};
+pub use format::{format, FormatOptions};
+pub use graph::{
+    all_paths, bookmark_name, bookmark_names, bookmarks_with_tag, choice_condition, choice_label,
+    choice_text, dead_ends, dead_ends_with_terminator, edge_events, edge_events_with_config,
+    find_cycles, merge, node_events, node_events_with_config, node_meta, node_tags, normalize_name,
+    path_between, read, read_bytes, read_checked, read_ordered, read_stable,
+    read_stable_with_options, read_strict, read_with_options, story_choices, story_text, subgraph,
+    subgraph_source, unreachable_from, BookmarkWeight, ChoiceWeight, CrossingChoice,
+    ExportedBookmark, ExportedChoice, GraphError, Guide, MergeWarning, MissingBookmark, MultiGuide,
+    MultiStory, OwnedBookmark, OwnedChoice, OwnedGuide, OwnedStory, ReadOptions, ReadWarning,
+    SourceId, StableStory, Story, StoryExport,
+};
+#[cfg(feature = "std")]
+pub use graph::{read_story_stream, read_story_stream_with_options};
+#[cfg(feature = "export")]
+pub use graphml::to_graphml;
+#[cfg(feature = "export")]
+pub use html::{render_html, to_html, HtmlOptions};
+pub use include::{read_with_includes, read_with_includes_with_options, IncludeWarning, Includes};
+pub use lint::{lint, LintConfig, LintWarning, LINT_RULES};
+pub use localize::{apply_strings, extract_strings, ExtractedString};
+#[cfg(feature = "export")]
+pub use markdown::{render_markdown, to_markdown, to_markdown_with_options, MarkdownOptions};
+pub use owned::{OwnedDiagnostic, OwnedEvent, OwnedSignal, OwnedStrRange};
+pub use parse::{parse, parse_with, Parsed};
+pub use play::{ChoiceView, ChooseError, Playthrough, RestoreError, SaveState, Step};
+pub use stats::{stats, BookmarkStats, StoryStats};
+#[cfg(feature = "std")]
+pub use stream::{read_stream, ReadStream};
+pub use style::{
+    event_iter, event_iter_with, event_iter_with_options, raw_event_iter, Event, EventIter, Style,
+    StyleConfig, StyleConfigError, StyleParseError,
+};
+pub use validate::{validate, validate_from, Problem, Severity, ValidationReport};
+pub use visit::{visit, visit_events, Visitor};
+pub use write::write_events;
@@ -0,0 +1,257 @@
+use crate::core::{Event, Signal};
+use crate::graph::{read_inspecting, split_param};
+use crate::style::scope_change;
+use crate::{bookmark_names, story_choices, ReadOptions, Story, Style, StyleConfig};
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use core::ops::Range;
+
+/// Ids of every rule of [`lint`], in the order they are described at [`LintWarning::rule`]
+pub const LINT_RULES: [&str; 6] = [
+    "long-bookmark-name",
+    "empty-choice-text",
+    "unknown-style",
+    "multiple-headings",
+    "single-choice",
+    "long-line",
+];
+
+/// Which rules [`lint`] follows and their limits, where every rule is enabled by default
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct LintConfig {
+    disabled: Vec<String>,
+    max_name_chars: usize,
+    max_line_chars: usize,
+    styles: StyleConfig,
+}
+
+impl LintConfig {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            disabled: Vec::new(),
+            max_name_chars: 32,
+            max_line_chars: 100,
+            styles: StyleConfig::new(),
+        }
+    }
+
+    /// Leaves out warnings of the rule with `id`, see [`LINT_RULES`]
+    #[must_use]
+    pub fn disable(mut self, id: &str) -> Self {
+        self.disabled.push(id.to_owned());
+        self
+    }
+
+    /// How many chars a bookmark name may have, which is 32 by default
+    #[must_use]
+    pub fn max_name_chars(self, max_name_chars: usize) -> Self {
+        Self {
+            max_name_chars,
+            ..self
+        }
+    }
+
+    /// How many chars a line may have, not counting its terminator, which is 100 by default
+    #[must_use]
+    pub fn max_line_chars(self, max_line_chars: usize) -> Self {
+        Self {
+            max_line_chars,
+            ..self
+        }
+    }
+
+    /// Chars of custom styles, which `unknown-style` doesn't warn about
+    #[must_use]
+    pub fn styles(self, styles: StyleConfig) -> Self {
+        Self { styles, ..self }
+    }
+
+    /// Whether the rule with `id` is followed
+    #[must_use]
+    pub fn is_enabled(&self, id: &str) -> bool {
+        !self.disabled.iter().any(|disabled| disabled == id)
+    }
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Something that is read as it is, but is likely to be written better, see [`lint`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct LintWarning {
+    /// Id of the rule, which is one of
+    /// - `long-bookmark-name` for names with more chars than allowed,
+    /// - `empty-choice-text` for choices with neither text nor label,
+    /// - `unknown-style` for `style` params with chars that stand for no style,
+    /// - `multiple-headings` for `style` params with several heading levels, of which the largest is set,
+    /// - `single-choice` for bookmarks with one choice, which is no branching at all,
+    /// - `long-line` for lines with more chars than allowed.
+    pub rule: &'static str,
+    /// Name or param for the first, third and fourth rules, text of the choice or bookmark
+    /// for the second and fifth ones, and the chars past the limit for the last one
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+/// Warnings about the style of `text`, ordered by where they are in it, for what
+/// [`validate`](crate::validate) doesn't cover, since the story is read the same either way.
+/// Rules may be disabled one by one, see [`LintConfig`].
+#[must_use]
+pub fn lint(text: &str, config: &LintConfig) -> Vec<LintWarning> {
+    let options = ReadOptions::new();
+    let mut warnings = Vec::new();
+    let mut push = |rule, range, message| {
+        if config.is_enabled(rule) {
+            warnings.push(LintWarning {
+                rule,
+                range,
+                message,
+            });
+        }
+    };
+    let mut signals = Vec::new();
+    let (guide, story, _): (_, Story, _) = read_inspecting([text], options, |event| {
+        if let Event::Signal(Signal::Call {
+            prompt,
+            param,
+            extra_params,
+            ..
+        }) = event
+        {
+            signals.push((prompt.clone(), param.clone(), extra_params.is_empty()));
+        }
+    });
+    for (prompt, param, is_plain) in signals {
+        if options.is_prompt(prompt.slice, "bookmark") {
+            let name = split_param(&param, true).name.trimmed();
+            let chars = name.slice.chars().count();
+            if chars > config.max_name_chars {
+                let message = format!(
+                    "bookmark name `{}` has {chars} chars, more than {}",
+                    name.slice, config.max_name_chars
+                );
+                push("long-bookmark-name", name.range, message);
+            }
+        } else if options.is_prompt(prompt.slice, "style") {
+            // `+` and `-` that open or close a scope are no style chars
+            let chars = is_plain
+                .then(|| scope_change(&param))
+                .flatten()
+                .map_or(param.slice, |(chars, _)| chars.slice);
+            let unknown: String = chars
+                .chars()
+                .filter(|ch| !ch.is_whitespace() && config.styles.style_of(*ch).is_none())
+                .collect();
+            if !unknown.is_empty() {
+                let message = format!("style `{}` has unknown chars `{unknown}`", param.slice);
+                push("unknown-style", param.range.clone(), message);
+            }
+            let headings = chars
+                .chars()
+                .filter_map(Style::from_char)
+                .fold(Style::REGULAR, |headings, style| {
+                    headings | (style & Style::HEADINGS)
+                });
+            if headings.bits().count_ones() > 1 {
+                let message = format!("style `{}` has several heading levels", param.slice);
+                push("multiple-headings", param.range, message);
+            }
+        }
+    }
+    let names = bookmark_names(&guide, &story, text);
+    for node in story.node_indices() {
+        let name = names[node.index()];
+        let choices: Vec<_> = story_choices(&story, node).collect();
+        for (edge, choice) in &choices {
+            let is_empty = text
+                .get(choice.text.clone())
+                .is_some_and(|text| text.trim().is_empty());
+            if is_empty && choice.label.is_none() {
+                let Some((_, target)) = story.edge_endpoints(*edge) else {
+                    continue;
+                };
+                let target = names[target.index()];
+                let message = format!("choice of `{name}` leading to `{target}` has no text");
+                push("empty-choice-text", choice.text.clone(), message);
+            }
+        }
+        if choices.len() == 1 {
+            let message = format!("bookmark `{name}` has a single choice");
+            push("single-choice", story[node].text.clone(), message);
+        }
+    }
+    let mut start = 0;
+    for line in text.split_inclusive(['\n', '\r']) {
+        let content = line.trim_end_matches(['\n', '\r']);
+        if let Some((past, _)) = content.char_indices().nth(config.max_line_chars) {
+            let message = format!(
+                "line has {} chars, more than {}",
+                content.chars().count(),
+                config.max_line_chars
+            );
+            push("long-line", start + past..start + content.len(), message);
+        }
+        start += line.len();
+    }
+    warnings.sort_by_key(|warning| warning.range.start);
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, LintConfig, LINT_RULES};
+    use crate::{Style, StyleConfig};
+
+    const SAMPLE: &str = "@bookmark{a-very-long-name}Hi!\n@choice{end}\n\
+        @bookmark{end}@style{bx}{Bye}, it was a long trip\n@style{12}{End}";
+
+    #[test]
+    fn rules() {
+        let config = LintConfig::new().max_name_chars(8).max_line_chars(24);
+        let warnings = lint(SAMPLE, &config);
+        let summary: Vec<_> = warnings
+            .iter()
+            .map(|warning| (warning.rule, &SAMPLE[warning.range.clone()]))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("long-bookmark-name", "a-very-long-name"),
+                ("long-line", "me}Hi!"),
+                ("single-choice", "Hi!\n"),
+                ("empty-choice-text", "\n"),
+                ("unknown-style", "bx"),
+                ("long-line", "{Bye}, it was a long trip"),
+                ("multiple-headings", "12"),
+            ]
+        );
+        assert_eq!(warnings[4].message, "style `bx` has unknown chars `x`");
+        let config = LINT_RULES
+            .into_iter()
+            .filter(|rule| *rule != "unknown-style")
+            .fold(config, LintConfig::disable);
+        let warnings = lint(SAMPLE, &config);
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert_eq!(warnings[0].rule, "unknown-style");
+        let styles = StyleConfig::new().register('x', Style::CUSTOM_0).unwrap();
+        assert!(lint(SAMPLE, &config.styles(styles)).is_empty());
+        assert!(lint(
+            "@bookmark{a}A\n@choice{a}Again\n@choice{a | Loop}",
+            &LintConfig::new()
+        )
+        .is_empty());
+        let scopes = "@style{b+}Bold @style{i+}and italic@style{b-} @style{-}\n@style{x+}";
+        let warnings = lint(scopes, &LintConfig::new());
+        let summary: Vec<_> = warnings
+            .iter()
+            .map(|warning| (warning.rule, warning.message.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [("unknown-style", "style `x+` has unknown chars `x`")]
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{bookmark_names, edge_events, node_events, story_choices, Event, Guide, Story, Style};
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use core::ops::Range;
+use petgraph::graph::NodeIndex;
+
+/// Piece of text that players see, e.g. to be translated, see [`extract_strings`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtractedString {
+    /// `name/0` for pieces of bookmark text and `name/target/0` for those of choice text,
+    /// numbered in the order they are written, over every choice of `name` leading to `target`
+    pub id: String,
+    pub text: String,
+    /// Span of the text in the source, including escapes, e.g. both `@` of `@@`
+    pub range: Range<usize>,
+    pub style: Style,
+    /// Signals between the text and the one before it, as they are written, e.g. `@wave`
+    pub signals_before: Vec<String>,
+}
+
+impl ExtractedString {
+    /// Same string with `text` in place of its own
+    #[must_use]
+    pub fn with_text(self, text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..self
+        }
+    }
+}
+
+/// Adds pieces of text of `events` to `out`, with ids made of `prefix` and numbers from `count`
+fn push_strings<'a>(
+    out: &mut Vec<ExtractedString>,
+    events: impl Iterator<Item = Event<'a>>,
+    full: &str,
+    prefix: &str,
+    count: &mut usize,
+) {
+    let mut pending: Option<ExtractedString> = None;
+    let mut signals_before = Vec::new();
+    for event in events {
+        match event {
+            Event::Text { style, content, .. } => {
+                // text of an escape only spans the second `@`
+                let range = if content.slice == "@" && full[..content.range.start].ends_with('@') {
+                    content.range.start - 1..content.range.end
+                } else {
+                    content.range
+                };
+                match &mut pending {
+                    // pieces of separate params are apart, even if nothing is written between them
+                    Some(string) if string.style == style && string.range.end == range.start => {
+                        string.text.push_str(content.slice);
+                        string.range.end = range.end;
+                    }
+                    _ => {
+                        out.extend(pending.take());
+                        pending = Some(ExtractedString {
+                            id: format!("{prefix}/{count}"),
+                            text: content.slice.to_owned(),
+                            range,
+                            style,
+                            signals_before: core::mem::take(&mut signals_before),
+                        });
+                        *count += 1;
+                    }
+                }
+            }
+            Event::Signal(signal) => {
+                out.extend(pending.take());
+                signals_before.push(full[signal.span()].to_owned());
+            }
+            // text of a link is translated on its own, unless it's the url
+            Event::Link {
+                url, text, style, ..
+            } if text.range != url.range => {
+                out.extend(pending.take());
+                out.push(ExtractedString {
+                    id: format!("{prefix}/{count}"),
+                    text: text.slice.to_owned(),
+                    range: text.range,
+                    style,
+                    signals_before: core::mem::take(&mut signals_before),
+                });
+                *count += 1;
+            }
+            Event::Link { span, .. } => {
+                out.extend(pending.take());
+                signals_before.push(full[span].to_owned());
+            }
+            Event::Break(_) | Event::ParagraphBreak(_) => out.extend(pending.take()),
+            Event::Diagnostic(_) => (),
+        }
+    }
+    out.extend(pending);
+}
+
+/// Pieces of text of a story read from `full` that players see, split by signals, line breaks
+/// and changes of style, with ids that stay the same as long as bookmark names and choice targets do.
+/// Bookmarks go in the order of their indices, each followed by its choices.
+#[must_use]
+pub fn extract_strings(guide: &Guide, story: &Story, full: &str) -> Vec<ExtractedString> {
+    let names = bookmark_names(guide, story, full);
+    let mut strings = Vec::new();
+    for node in story.node_indices() {
+        let name = names[node.index()];
+        let mut count = 0;
+        push_strings(
+            &mut strings,
+            node_events(story, node, full),
+            full,
+            name,
+            &mut count,
+        );
+        let mut counts: Vec<(NodeIndex, usize)> = Vec::new();
+        for (edge, _) in story_choices(story, node) {
+            let Some((_, target)) = story.edge_endpoints(edge) else {
+                continue;
+            };
+            let index = counts
+                .iter()
+                .position(|(prev, _)| *prev == target)
+                .unwrap_or_else(|| {
+                    counts.push((target, 0));
+                    counts.len() - 1
+                });
+            push_strings(
+                &mut strings,
+                edge_events(story, edge, full),
+                full,
+                &format!("{name}/{}", names[target.index()]),
+                &mut counts[index].1,
+            );
+        }
+    }
+    strings
+}
+
+/// Writes `full` with the text of each of `strings` in place of its range, e.g. after translating it,
+/// with every `@` doubled, so that none of it is taken for a signal.
+/// Brackets and backslashes are written as they are, so they should be balanced
+/// in the text of styled params, e.g. `@style{b}{Bold}`.
+///
+/// # Panics
+///
+/// Panics if ranges of `strings` overlap or are out of `full`
+#[must_use]
+pub fn apply_strings(full: &str, strings: &[ExtractedString]) -> String {
+    let mut sorted: Vec<_> = strings.iter().collect();
+    sorted.sort_by_key(|string| string.range.start);
+    let mut out = String::with_capacity(full.len());
+    let mut end = 0;
+    for string in sorted {
+        out.push_str(&full[end..string.range.start]);
+        out.push_str(&string.text.replace('@', "@@"));
+        end = string.range.end;
+    }
+    out.push_str(&full[end..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_strings, extract_strings};
+    use crate::Style;
+
+    const SAMPLE: &str = "@bookmark{greet}Hello, @@home! @wave How\n@style{b}{are} you?\n\
+        @choice{end}Bye\n@choice{end}Later\n@bookmark{end}End.";
+
+    #[test]
+    fn ids() {
+        let (guide, story) = crate::read([SAMPLE]);
+        let strings = extract_strings(&guide, &story, SAMPLE);
+        let summary: Vec<_> = strings
+            .iter()
+            .map(|string| (string.id.as_str(), string.text.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("greet/0", "Hello, @home!"),
+                ("greet/1", " How"),
+                ("greet/2", "are"),
+                ("greet/3", "you?"),
+                ("greet/end/0", "Bye"),
+                ("greet/end/1", "Later"),
+                ("end/0", "End."),
+            ]
+        );
+        assert_eq!(strings[0].range, 16..30);
+        assert_eq!(strings[1].signals_before, ["@wave"]);
+        assert_eq!(strings[2].style, Style::BOLD);
+    }
+
+    #[test]
+    fn applied() {
+        let (guide, story) = crate::read([SAMPLE]);
+        let strings: Vec<_> = extract_strings(&guide, &story, SAMPLE)
+            .into_iter()
+            .map(|string| {
+                let text = match string.id.as_str() {
+                    "greet/0" => "Hallo, @Haus!",
+                    "greet/2" => "geht's",
+                    "greet/end/1" => "Später",
+                    _ => return string,
+                };
+                string.with_text(text)
+            })
+            .collect();
+        let translated = apply_strings(SAMPLE, &strings);
+        assert_eq!(
+            translated,
+            "@bookmark{greet}Hallo, @@Haus! @wave How\n@style{b}{geht's} you?\n\
+            @choice{end}Bye\n@choice{end}Später\n@bookmark{end}End."
+        );
+        let (guide, story) = crate::read([translated.as_str()]);
+        assert_eq!(
+            extract_strings(&guide, &story, &translated)[0].text,
+            "Hallo, @Haus!"
+        );
+    }
+
+    #[test]
+    fn identity() {
+        const ADJACENT: &str = "@bookmark{a}one @style{b}@{x}@{y} z\n@style{i}{p}{q}@@";
+        for full in [SAMPLE, ADJACENT] {
+            let (guide, story) = crate::read([full]);
+            let strings = extract_strings(&guide, &story, full);
+            assert_eq!(apply_strings(full, &strings), full);
+        }
+        let (guide, story) = crate::read([ADJACENT]);
+        let texts: Vec<_> = extract_strings(&guide, &story, ADJACENT)
+            .into_iter()
+            .map(|string| string.text)
+            .collect();
+        assert_eq!(texts, ["one", "x", "y", " z", "p", "q", "@"]);
+    }
+}
@@ -0,0 +1,414 @@
+use crate::render::{lines, Piece};
+use crate::{Event, EventIter, Style};
+use alloc::{string::String, vec::Vec};
+
+/// Styles that are rendered within a line, along with their delimiters and the tags
+/// they fall back to, in the order they are opened, while code always goes innermost
+const DELIMITERS: [(Style, &str, &str); 3] = [
+    (Style::SCRATCH, "~~", "del"),
+    (Style::BOLD, "**", "strong"),
+    (Style::ITALIC, "*", "em"),
+];
+
+/// Styles that are rendered within a line
+const INLINE: Style = Style::SCRATCH
+    .union(Style::BOLD)
+    .union(Style::ITALIC)
+    .union(Style::CODE);
+
+/// Options of rendering markdown, see [`render_markdown`]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MarkdownOptions {
+    keep_signals: bool,
+}
+
+impl MarkdownOptions {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { keep_signals: true }
+    }
+
+    /// Whether signals that aren't a part of the story, e.g. `@wave`, are written as they are,
+    /// which they are by default, or left out
+    #[must_use]
+    pub const fn keep_signals(self, keep_signals: bool) -> Self {
+        Self { keep_signals }
+    }
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adds `text` with a backslash before every char that could be taken for markup
+fn push_escaped(out: &mut String, text: &str) {
+    for ch in text.chars() {
+        if matches!(
+            ch,
+            '\\' | '`' | '*' | '_' | '~' | '[' | ']' | '<' | '>' | '#' | '&'
+        ) {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+}
+
+/// Adds a code span of `text`, with more backticks around it than there are in a row within it
+fn push_code(out: &mut String, text: &str) {
+    let longest = text
+        .split(|ch| ch != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest + 1);
+    let is_padded = text.starts_with('`') || text.ends_with('`');
+    out.push_str(&fence);
+    if is_padded {
+        out.push(' ');
+    }
+    out.push_str(text);
+    if is_padded {
+        out.push(' ');
+    }
+    out.push_str(&fence);
+}
+
+/// Whether `ch` counts as punctuation next to a delimiter, which leaves out no more than it should
+fn is_punctuation(ch: char) -> bool {
+    !ch.is_alphanumeric() && !ch.is_whitespace()
+}
+
+/// Whether a delimiter between `outside` and a char that `is_punctuation_inside` tells of
+/// opens or closes, which it doesn't between punctuation and a letter, as in `a**.b**`
+fn is_flanking(outside: Option<char>, is_punctuation_inside: bool) -> bool {
+    !is_punctuation_inside || outside.is_none_or(|ch| ch.is_whitespace() || is_punctuation(ch))
+}
+
+/// Adds `text` within the delimiters of `style`, leaving whitespace at either end outside of them,
+/// as delimiters next to whitespace don't count, where `next` is the char rendered after it.
+/// Delimiters that wouldn't count next to the chars around them give way to tags.
+fn push_styled(out: &mut String, style: Style, text: &str, next: Option<char>) {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        out.push_str(text);
+        return;
+    }
+    let start = text.len() - text.trim_start().len();
+    out.push_str(&text[..start]);
+    let rest = &text[start + trimmed.len()..];
+    let delimiters = DELIMITERS
+        .iter()
+        .filter(|(delimited, ..)| style.contains(*delimited))
+        .count();
+    // other delimiters or backticks are what an outer delimiter is next to
+    let is_nested = delimiters > 1 || style.contains(Style::CODE);
+    let is_punctuation_inside = |ch: Option<char>| is_nested || ch.is_some_and(is_punctuation);
+    let is_tagged = delimiters != 0
+        && !(is_flanking(
+            out.chars().next_back(),
+            is_punctuation_inside(trimmed.chars().next()),
+        ) && is_flanking(
+            rest.chars().next().or(next),
+            is_punctuation_inside(trimmed.chars().next_back()),
+        ));
+    push_delimited(out, style, is_tagged, |out| {
+        if style.contains(Style::CODE) {
+            push_code(out, trimmed);
+        } else {
+            push_escaped(out, trimmed);
+        }
+    });
+    out.push_str(rest);
+}
+
+fn push_delimited(
+    out: &mut String,
+    style: Style,
+    is_tagged: bool,
+    push_inner: impl FnOnce(&mut String),
+) {
+    for (delimited, delimiter, tag) in DELIMITERS {
+        if style.contains(delimited) {
+            if is_tagged {
+                out.push('<');
+                out.push_str(tag);
+                out.push('>');
+            } else {
+                out.push_str(delimiter);
+            }
+        }
+    }
+    push_inner(out);
+    for (delimited, delimiter, tag) in DELIMITERS.into_iter().rev() {
+        if style.contains(delimited) {
+            if is_tagged {
+                out.push_str("</");
+                out.push_str(tag);
+                out.push('>');
+            } else {
+                out.push_str(delimiter);
+            }
+        }
+    }
+}
+
+/// Adds a link destination, in angle brackets if it has chars that would end it otherwise
+fn push_url(out: &mut String, url: &str) {
+    if url.contains(|ch: char| ch.is_whitespace() || matches!(ch, '(' | ')' | '<' | '>')) {
+        out.push('<');
+        for ch in url.chars() {
+            if matches!(ch, '\\' | '<' | '>') {
+                out.push('\\');
+            }
+            out.push(ch);
+        }
+        out.push('>');
+    } else {
+        out.push_str(url);
+    }
+}
+
+/// Text of the same inline style, or a link, to be rendered on its own
+enum Run<'a> {
+    Text(String),
+    Link { text: &'a str, url: &'a str },
+}
+
+impl Run<'_> {
+    /// First char rendered for the run, where any delimiter stands for punctuation
+    fn first_char(&self, style: Style) -> Option<char> {
+        match self {
+            Self::Text(text) => text.chars().next().map(|ch| {
+                if style.is_empty() || ch.is_whitespace() {
+                    ch
+                } else {
+                    '*'
+                }
+            }),
+            Self::Link { .. } => Some('['),
+        }
+    }
+}
+
+/// Pieces of a line with their inline styles, where text of the same style is merged
+fn push_pieces(out: &mut String, pieces: &[(Style, Piece)]) {
+    let mut runs: Vec<(Style, Run)> = Vec::new();
+    for (style, piece) in pieces {
+        let style = *style & INLINE;
+        match piece {
+            Piece::Text(text) | Piece::Signal(text) => match runs.last_mut() {
+                Some((prev, Run::Text(prev_text))) if *prev == style => prev_text.push_str(text),
+                _ => runs.push((style, Run::Text(text.clone()))),
+            },
+            Piece::Link { text, url } => runs.push((style, Run::Link { text, url })),
+        }
+    }
+    for (index, (style, run)) in runs.iter().enumerate() {
+        let next = runs
+            .get(index + 1)
+            .and_then(|(style, run)| run.first_char(*style));
+        match run {
+            Run::Text(text) => push_styled(out, *style, text, next),
+            Run::Link { text, url } => {
+                out.push('[');
+                push_styled(out, *style, text, Some(']'));
+                out.push_str("](");
+                push_url(out, url);
+                out.push(')');
+            }
+        }
+    }
+}
+
+/// Adds `body` of a line, escaping what would start a list or underline a heading otherwise
+fn push_body(out: &mut String, body: &str) {
+    let digits = body.len()
+        - body
+            .trim_start_matches(|ch: char| ch.is_ascii_digit())
+            .len();
+    if body.starts_with(['-', '+', '=']) {
+        out.push('\\');
+    } else if digits > 0 && body[digits..].starts_with(['.', ')']) {
+        out.push_str(&body[..digits]);
+        out.push('\\');
+        out.push_str(&body[digits..]);
+        return;
+    }
+    out.push_str(body);
+}
+
+/// Renders styled events of `full` as `CommonMark`, e.g. to preview a story elsewhere,
+/// where `full` tells if there's whitespace between the events, as it's trimmed off the text.
+///
+/// Bold, italic, scratched and code text get `**`, `*`, `~~` and backticks, opened in this order,
+/// with whitespace at either end left out of them, and links become `[text](url)`.
+/// Where delimiters wouldn't count, as in `**foo.**bar`, `<del>`, `<strong>` and `<em>` go instead.
+/// Lines of quotes are prefixed with `> `, those of headings with as many `#` as their level,
+/// and lines of panels that follow one another go into a single fenced block.
+/// Line breaks are kept, and paragraph breaks become blank lines, as do lines with nothing to render.
+/// Signals the story is made of, e.g. `@bookmark`, are left out, and the rest is written as it is,
+/// unless [`MarkdownOptions::keep_signals`] tells otherwise.
+/// Markup chars of the text, e.g. `*` or `#`, are escaped.
+#[must_use]
+pub fn render_markdown<'a>(
+    events: impl IntoIterator<Item = Event<'a>>,
+    full: &str,
+    options: MarkdownOptions,
+) -> String {
+    let lines = lines(events, full, options.keep_signals);
+    let mut out = String::new();
+    let mut is_paragraph = false;
+    let mut was_quote = false;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = &lines[index];
+        index += 1;
+        is_paragraph |= line.is_paragraph;
+        if line.is_blank() {
+            is_paragraph = true;
+            continue;
+        }
+        let style = line.style();
+        let is_quote = style.contains(Style::QUOTE);
+        if !out.is_empty() {
+            // a line after a quote would go on with it otherwise
+            out.push_str(if is_paragraph || (was_quote && !is_quote) {
+                "\n\n"
+            } else {
+                "\n"
+            });
+        }
+        is_paragraph = false;
+        was_quote = is_quote;
+        let prefix = if is_quote { "> " } else { "" };
+        if style.contains(Style::PANEL) {
+            let mut plain = alloc::vec![line.plain()];
+            while let Some(next) = lines.get(index).filter(|next| {
+                !next.is_paragraph
+                    && !next.is_blank()
+                    && next.style().contains(Style::PANEL)
+                    && next.style().contains(Style::QUOTE) == is_quote
+            }) {
+                plain.push(next.plain());
+                index += 1;
+            }
+            let longest = plain
+                .iter()
+                .flat_map(|line| line.split(|ch| ch != '`'))
+                .map(str::len)
+                .max()
+                .unwrap_or_default();
+            let fence = "`".repeat(longest.max(2) + 1);
+            out.push_str(prefix);
+            out.push_str(&fence);
+            for line in plain {
+                out.push('\n');
+                out.push_str(prefix);
+                out.push_str(&line);
+            }
+            out.push('\n');
+            out.push_str(prefix);
+            out.push_str(&fence);
+            continue;
+        }
+        out.push_str(prefix);
+        if let Some(level) = line.heading_level() {
+            for _ in 0..level {
+                out.push('#');
+            }
+            out.push(' ');
+        }
+        let mut body = String::new();
+        push_pieces(&mut body, &line.pieces);
+        push_body(&mut out, body.trim());
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Same as [`to_markdown`], but with signals rendered as [`MarkdownOptions`] tell
+#[must_use]
+pub fn to_markdown_with_options(text: &str, options: MarkdownOptions) -> String {
+    render_markdown(EventIter::new(text), text, options)
+}
+
+/// Renders `text` as `CommonMark`, see [`render_markdown`]
+#[must_use]
+pub fn to_markdown(text: &str) -> String {
+    to_markdown_with_options(text, MarkdownOptions::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_markdown, to_markdown_with_options, MarkdownOptions};
+    use pulldown_cmark::{html, Options, Parser};
+
+    fn html(markdown: &str) -> String {
+        let mut out = String::new();
+        html::push_html(
+            &mut out,
+            Parser::new_ext(markdown, Options::ENABLE_STRIKETHROUGH),
+        );
+        out
+    }
+
+    #[test]
+    fn inline() {
+        const SAMPLE: &str = "@bookmark{greet}Hello, @style{b}@{dear} @style{bi}{friend}!\n\
+            @style{c}{let x = 1;} or @style{s}{2 * 3} and @link{https://a.b}@{docs}\n\
+            @style{b}{foo.}bar, x@style{i}{(y)} and @style{i}{z}.";
+        let markdown = to_markdown(SAMPLE);
+        assert_eq!(
+            markdown,
+            "Hello, **dear** ***friend***!\n`let x = 1;` or ~~2 \\* 3~~ and [docs](https://a.b)\n\
+            <strong>foo.</strong>bar, x<em>(y)</em> and *z*.\n"
+        );
+        assert_eq!(
+            html(&markdown),
+            "<p>Hello, <strong>dear</strong> <em><strong>friend</strong></em>!\n\
+            <code>let x = 1;</code> or <del>2 * 3</del> and <a href=\"https://a.b\">docs</a>\n\
+            <strong>foo.</strong>bar, x<em>(y)</em> and <em>z</em>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn blocks() {
+        const SAMPLE: &str = "@style{1}{Title}\n@style{q}{Quoted} @style{qb}{bold}\nPlain\n\n\
+            @style{p}{let a = 1;}\n@style{p}{let b = 2;}\n@bookmark{next}\nEnd";
+        let markdown = to_markdown(SAMPLE);
+        assert_eq!(
+            markdown,
+            "# Title\n> Quoted **bold**\n\nPlain\n\n```\nlet a = 1;\nlet b = 2;\n```\n\nEnd\n"
+        );
+        assert_eq!(
+            html(&markdown),
+            "<h1>Title</h1>\n<blockquote>\n<p>Quoted <strong>bold</strong></p>\n</blockquote>\n\
+            <p>Plain</p>\n<pre><code>let a = 1;\nlet b = 2;\n</code></pre>\n<p>End</p>\n"
+        );
+    }
+
+    #[test]
+    fn escaped() {
+        const SAMPLE: &str = "- not a list, # nor *emphasis* or [link]\n1. Neither @@ <tag>\n\
+            @style{c}{`ticks`} @link{https://a.b/c d}@{[x]}";
+        let markdown = to_markdown(SAMPLE);
+        assert_eq!(markdown, "\\- not a list, \\# nor \\*emphasis\\* or \\[link\\]\n1\\. Neither @ \\<tag\\>\n`` `ticks` `` [\\[x\\]](<https://a.b/c d>)\n");
+        assert_eq!(
+            html(&markdown),
+            "<p>- not a list, # nor *emphasis* or [link]\n1. Neither @ &lt;tag&gt;\n\
+            <code>`ticks`</code> <a href=\"https://a.b/c%20d\">[x]</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn signals() {
+        const SAMPLE: &str = "Hi @wave there @name{a}{b}!";
+        assert_eq!(to_markdown(SAMPLE), "Hi @wave there @name{a}{b}!\n");
+        let options = MarkdownOptions::new().keep_signals(false);
+        assert_eq!(to_markdown_with_options(SAMPLE, options), "Hi there!\n");
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{Diagnostic, Event, Limit, Signal, Spacing, StrRange, Style};
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::ops;
+
+/// Same as [`StrRange`], but owning its slice, so it can outlive the source text
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedStrRange {
+    /// original text sliced by `self.range`
+    pub slice: String,
+    /// byte-index range in original text
+    pub range: ops::Range<usize>,
+}
+
+/// Same as [`Signal`], but owning its slices
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedSignal {
+    Ping {
+        span: ops::Range<usize>,
+    },
+    Prompt {
+        prompt: OwnedStrRange,
+        span: ops::Range<usize>,
+    },
+    Param {
+        param: OwnedStrRange,
+        span: ops::Range<usize>,
+    },
+    Call {
+        prompt: OwnedStrRange,
+        param: OwnedStrRange,
+        extra_params: Vec<OwnedStrRange>,
+        span: ops::Range<usize>,
+    },
+}
+
+impl OwnedSignal {
+    /// Same as [`Signal::span`]
+    #[must_use]
+    pub fn span(&self) -> ops::Range<usize> {
+        Signal::from(self).span()
+    }
+}
+
+impl Default for OwnedSignal {
+    fn default() -> Self {
+        Signal::default().into()
+    }
+}
+
+/// Same as [`Diagnostic`], but owning its slices
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedDiagnostic {
+    UnclosedParam { bracket: OwnedStrRange },
+    LimitExceeded { limit: Limit, at: OwnedStrRange },
+    UnknownStyle { ch: OwnedStrRange },
+}
+
+/// Same as [`Event`], but owning its slices
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedEvent {
+    Signal(OwnedSignal),
+    Text {
+        style: Style,
+        content: OwnedStrRange,
+        leading: Spacing,
+    },
+    Break(OwnedStrRange),
+    ParagraphBreak(OwnedStrRange),
+    Diagnostic(OwnedDiagnostic),
+    Link {
+        url: OwnedStrRange,
+        text: OwnedStrRange,
+        style: Style,
+        span: ops::Range<usize>,
+    },
+}
+
+impl<'a> StrRange<'a> {
+    #[must_use]
+    pub fn into_owned(self) -> OwnedStrRange {
+        self.into()
+    }
+}
+
+impl<'a> Signal<'a> {
+    #[must_use]
+    pub fn into_owned(self) -> OwnedSignal {
+        self.into()
+    }
+}
+
+impl<'a> Diagnostic<'a> {
+    #[must_use]
+    pub fn into_owned(self) -> OwnedDiagnostic {
+        self.into()
+    }
+}
+
+impl<'a> Event<'a> {
+    #[must_use]
+    pub fn into_owned(self) -> OwnedEvent {
+        self.into()
+    }
+}
+
+impl<'a> From<StrRange<'a>> for OwnedStrRange {
+    fn from(str_range: StrRange<'a>) -> Self {
+        Self {
+            slice: str_range.slice.to_owned(),
+            range: str_range.range,
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedStrRange> for StrRange<'a> {
+    fn from(str_range: &'a OwnedStrRange) -> Self {
+        Self {
+            slice: &str_range.slice,
+            range: str_range.range.clone(),
+        }
+    }
+}
+
+impl<'a> From<Signal<'a>> for OwnedSignal {
+    fn from(signal: Signal<'a>) -> Self {
+        match signal {
+            Signal::Ping { span } => Self::Ping { span },
+            Signal::Prompt { prompt, span } => Self::Prompt {
+                prompt: prompt.into(),
+                span,
+            },
+            Signal::Param { param, span } => Self::Param {
+                param: param.into(),
+                span,
+            },
+            Signal::Call {
+                prompt,
+                param,
+                extra_params,
+                span,
+            } => Self::Call {
+                prompt: prompt.into(),
+                param: param.into(),
+                extra_params: extra_params.into_iter().map(Into::into).collect(),
+                span,
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedSignal> for Signal<'a> {
+    fn from(signal: &'a OwnedSignal) -> Self {
+        match signal {
+            OwnedSignal::Ping { span } => Self::Ping { span: span.clone() },
+            OwnedSignal::Prompt { prompt, span } => Self::Prompt {
+                prompt: prompt.into(),
+                span: span.clone(),
+            },
+            OwnedSignal::Param { param, span } => Self::Param {
+                param: param.into(),
+                span: span.clone(),
+            },
+            OwnedSignal::Call {
+                prompt,
+                param,
+                extra_params,
+                span,
+            } => Self::Call {
+                prompt: prompt.into(),
+                param: param.into(),
+                extra_params: extra_params.iter().map(Into::into).collect(),
+                span: span.clone(),
+            },
+        }
+    }
+}
+
+impl<'a> From<Diagnostic<'a>> for OwnedDiagnostic {
+    fn from(diagnostic: Diagnostic<'a>) -> Self {
+        match diagnostic {
+            Diagnostic::UnclosedParam { bracket } => Self::UnclosedParam {
+                bracket: bracket.into(),
+            },
+            Diagnostic::LimitExceeded { limit, at } => Self::LimitExceeded {
+                limit,
+                at: at.into(),
+            },
+            Diagnostic::UnknownStyle { ch } => Self::UnknownStyle { ch: ch.into() },
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedDiagnostic> for Diagnostic<'a> {
+    fn from(diagnostic: &'a OwnedDiagnostic) -> Self {
+        match diagnostic {
+            OwnedDiagnostic::UnclosedParam { bracket } => Self::UnclosedParam {
+                bracket: bracket.into(),
+            },
+            OwnedDiagnostic::LimitExceeded { limit, at } => Self::LimitExceeded {
+                limit: *limit,
+                at: at.into(),
+            },
+            OwnedDiagnostic::UnknownStyle { ch } => Self::UnknownStyle { ch: ch.into() },
+        }
+    }
+}
+
+impl<'a> From<Event<'a>> for OwnedEvent {
+    fn from(event: Event<'a>) -> Self {
+        match event {
+            Event::Signal(signal) => Self::Signal(signal.into()),
+            Event::Text {
+                style,
+                content,
+                leading,
+            } => Self::Text {
+                style,
+                content: content.into(),
+                leading,
+            },
+            Event::Break(line_break) => Self::Break(line_break.into()),
+            Event::ParagraphBreak(line_break) => Self::ParagraphBreak(line_break.into()),
+            Event::Diagnostic(diagnostic) => Self::Diagnostic(diagnostic.into()),
+            Event::Link {
+                url,
+                text,
+                style,
+                span,
+            } => Self::Link {
+                url: url.into(),
+                text: text.into(),
+                style,
+                span,
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedEvent> for Event<'a> {
+    fn from(event: &'a OwnedEvent) -> Self {
+        match event {
+            OwnedEvent::Signal(signal) => Self::Signal(signal.into()),
+            OwnedEvent::Text {
+                style,
+                content,
+                leading,
+            } => Self::Text {
+                style: *style,
+                content: content.into(),
+                leading: *leading,
+            },
+            OwnedEvent::Break(line_break) => Self::Break(line_break.into()),
+            OwnedEvent::ParagraphBreak(line_break) => Self::ParagraphBreak(line_break.into()),
+            OwnedEvent::Diagnostic(diagnostic) => Self::Diagnostic(diagnostic.into()),
+            OwnedEvent::Link {
+                url,
+                text,
+                style,
+                span,
+            } => Self::Link {
+                url: url.into(),
+                text: text.into(),
+                style: *style,
+                span: span.clone(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OwnedEvent;
+    use crate::{Event, EventIter};
+
+    #[test]
+    fn round_trip() {
+        let owned: Vec<OwnedEvent> = {
+            let text = String::from(
+                "@bookmark{start} Hello, @name!\n\n\
+                 @style{bi}@{Bold} and @@ escaped @{unclosed\r\n@-- comment\n@ bye",
+            );
+            let owned: Vec<OwnedEvent> = EventIter::new(&text).map(Event::into_owned).collect();
+            let borrowed: Vec<Event> = EventIter::new(&text).collect();
+            let converted: Vec<Event> = owned.iter().map(Event::from).collect();
+            assert_eq!(converted, borrowed);
+            owned
+        };
+        // the source text is dropped by now
+        let Some(OwnedEvent::Signal(signal)) = owned.first() else {
+            panic!("expected signal, got {:?}", owned.first());
+        };
+        assert_eq!(signal.clone(), crate::Signal::from(signal).into_owned());
+        assert!(owned
+            .iter()
+            .any(|event| matches!(event, OwnedEvent::ParagraphBreak(_))));
+        assert!(owned
+            .iter()
+            .any(|event| matches!(event, OwnedEvent::Diagnostic(_))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        const SAMPLE: &str = "@style{bi}@{Bold} text @{unclosed\n\n@bookmark{end}";
+        let borrowed: Vec<Event> = EventIter::new(SAMPLE).collect();
+        let json = serde_json::to_string(&borrowed).expect("serialized events");
+        let owned: Vec<OwnedEvent> = serde_json::from_str(&json).expect("deserialized events");
+        let expected: Vec<OwnedEvent> = borrowed.into_iter().map(Event::into_owned).collect();
+        assert_eq!(owned, expected);
+    }
+}
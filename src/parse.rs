@@ -0,0 +1,95 @@
+use crate::{Diagnostic, Event, EventIter, StyleConfig};
+use alloc::vec::Vec;
+
+/// Events of the whole text parsed at once, with diagnostics gathered aside,
+/// see [`parse`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Parsed<'a> {
+    events: Vec<Event<'a>>,
+    diagnostics: Vec<Diagnostic<'a>>,
+}
+
+impl<'a> Parsed<'a> {
+    /// Same events as of [`EventIter::new`], diagnostics included
+    #[must_use]
+    pub fn events(&self) -> &[Event<'a>] {
+        &self.events
+    }
+
+    /// Every diagnostic in the order of appearance
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic<'a>] {
+        &self.diagnostics
+    }
+
+    /// Whether there are no diagnostics
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+/// Go through the whole text at once, collecting events and diagnostics.
+/// Unlike [`EventIter::new`], this allocates, but keeps everything at hand.
+#[must_use]
+pub fn parse(text: &str) -> Parsed<'_> {
+    parse_with(text, &StyleConfig::new())
+}
+
+/// Same as [`parse`], but with custom styles of `styles`, and diagnostics
+/// of unknown style chars if it's strict, see [`StyleConfig::strict`]
+#[must_use]
+pub fn parse_with<'a>(text: &'a str, styles: &StyleConfig) -> Parsed<'a> {
+    let events: Vec<_> = EventIter::new(text).styles(*styles).collect();
+    let diagnostics = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Diagnostic(diagnostic) => Some(diagnostic.clone()),
+            _ => None,
+        })
+        .collect();
+    Parsed {
+        events,
+        diagnostics,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_with};
+    use crate::{Diagnostic, EventIter, StyleConfig};
+
+    #[test]
+    fn diagnostics() {
+        const SAMPLE: &str = "@bookmark{greet} Hi!\n@{oops\n@style(b)@{Bye} @x[";
+        let parsed = parse(SAMPLE);
+        assert!(!parsed.is_clean());
+        assert!(parsed
+            .events()
+            .iter()
+            .eq(&EventIter::new(SAMPLE).collect::<Vec<_>>()));
+        let messages: Vec<_> = parsed
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| (diagnostic.to_string(), &SAMPLE[diagnostic.range()]))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                ("unclosed `{`".to_owned(), "{"),
+                ("unclosed `[`".to_owned(), "["),
+            ]
+        );
+        assert!(matches!(
+            parsed.diagnostics()[0],
+            Diagnostic::UnclosedParam { .. }
+        ));
+        assert!(parse("@bookmark{greet} Hi!").is_clean());
+        assert!(parse("@style{x}{Hi}").is_clean());
+        let parsed = parse_with("@style{x}{Hi}", &StyleConfig::new().strict(true));
+        assert!(matches!(
+            parsed.diagnostics(),
+            [Diagnostic::UnknownStyle { ch }] if ch.slice == "x"
+        ));
+    }
+}
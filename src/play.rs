@@ -0,0 +1,569 @@
+use crate::graph::find_bookmark;
+use crate::{
+    bookmark_names, story_choices, story_text, ChoiceWeight, Guide, MissingBookmark, Story,
+};
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::{fmt, ops::Range};
+use petgraph::{
+    graph::{EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+};
+
+/// Choice that can be taken at the current bookmark of a [`Playthrough`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ChoiceView<'a> {
+    /// Index to pass to [`Playthrough::choose`], in the order choices are written
+    pub index: usize,
+    pub edge: EdgeIndex,
+    /// Name of the bookmark the choice leads to
+    pub target: &'a str,
+    /// Range of the choice text
+    pub text: Range<usize>,
+}
+
+/// Index given to [`Playthrough::choose`] that there's no choice at
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ChooseError {
+    pub index: usize,
+    /// Choices there are, so that the index should be less than this
+    pub choice_count: usize,
+}
+
+impl fmt::Display for ChooseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no choice at index {}, as there are {} of them",
+            self.index, self.choice_count
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ChooseError {}
+
+/// Choice taken by a [`Playthrough`], see [`Playthrough::history`]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Step {
+    /// Bookmark the choice is taken at
+    pub node: NodeIndex,
+    pub edge: EdgeIndex,
+}
+
+/// Steps that a [`Playthrough`] keeps unless told otherwise, see [`Playthrough::max_history`]
+const DEFAULT_MAX_HISTORY: usize = 100;
+
+/// Where a [`Playthrough`] is, with bookmarks kept by their names rather than indices,
+/// so that it can be restored after the story is read anew, e.g. from an edited text
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SaveState {
+    /// Name of the current bookmark
+    pub current: String,
+    /// Names of visited bookmarks along with how many times each was visited,
+    /// in the order of their indices when saved
+    pub visits: Vec<(String, u32)>,
+    /// Names of bookmarks that choices were taken at, along with indices of the choices
+    /// in the order they are written and how many times each was taken
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub taken: Vec<(String, usize, u32)>,
+    /// Names of bookmarks that choices were taken at, along with indices of the choices,
+    /// which are only saved by [`Playthrough::save_with_history`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub history: Option<Vec<(String, usize)>>,
+}
+
+/// Reason a [`SaveState`] can't be restored with a story
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum RestoreError {
+    /// Current bookmark that the story doesn't have
+    Missing { name: String },
+    /// Bookmark that the step of history before it doesn't lead to,
+    /// or the current one if the latest step doesn't
+    BrokenHistory { name: String },
+}
+
+impl fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { name } => write!(f, "saved bookmark `{name}` is not in the story"),
+            Self::BrokenHistory { name } => {
+                write!(f, "saved history doesn't lead to bookmark `{name}`")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RestoreError {}
+
+/// Bookmark a player is at, which moves on as choices are taken
+#[derive(Clone, Debug)]
+pub struct Playthrough<'a> {
+    story: &'a Story,
+    names: Vec<&'a str>,
+    current: NodeIndex,
+    /// Times each node was visited, counting the start
+    visits: Vec<u32>,
+    /// Times each edge was taken
+    taken: Vec<u32>,
+    /// Choices taken, from the earliest one that is kept
+    history: Vec<Step>,
+    max_history: usize,
+}
+
+impl<'a> Playthrough<'a> {
+    /// Playthrough at the `start` bookmark of a story read from `full`
+    ///
+    /// # Errors
+    ///
+    /// Fails if `start` isn't in `guide`
+    pub fn new<'b>(
+        guide: &Guide<'a>,
+        story: &'a Story,
+        start: &'b str,
+        full: &str,
+    ) -> Result<Self, MissingBookmark<'b>> {
+        let current = find_bookmark(guide, start).ok_or(MissingBookmark(start))?;
+        let mut visits = alloc::vec![0; story.node_count()];
+        visits[current.index()] = 1;
+        Ok(Self {
+            story,
+            names: bookmark_names(guide, story, full),
+            current,
+            visits,
+            taken: alloc::vec![0; story.edge_count()],
+            history: Vec::new(),
+            max_history: DEFAULT_MAX_HISTORY,
+        })
+    }
+
+    /// How many of the latest choices to keep, so that they can be undone with [`Playthrough::back`],
+    /// which is 100 by default, with earlier ones forgotten
+    #[must_use]
+    pub fn max_history(mut self, max_history: usize) -> Self {
+        let excess = self.history.len().saturating_sub(max_history);
+        self.history.drain(..excess);
+        self.max_history = max_history;
+        self
+    }
+
+    /// Playthrough where `state` was saved, with visits of bookmarks and choices that are gone left out,
+    /// along with the steps of history before any step of a bookmark or choice that is gone
+    ///
+    /// # Errors
+    ///
+    /// Fails if the current bookmark of `state` isn't in `guide`,
+    /// or if the steps of its history don't lead one to another and then to it
+    pub fn restore(
+        guide: &Guide<'a>,
+        story: &'a Story,
+        state: SaveState,
+        full: &str,
+    ) -> Result<Self, RestoreError> {
+        let current = guide
+            .get(state.current.as_str())
+            .copied()
+            .ok_or(RestoreError::Missing {
+                name: state.current,
+            })?;
+        let mut visits = alloc::vec![0; story.node_count()];
+        for (name, count) in state.visits {
+            if let Some(node) = guide.get(name.as_str()) {
+                visits[node.index()] = count;
+            }
+        }
+        let step = |name: String, index| {
+            let node = *guide.get(name.as_str())?;
+            let (edge, _) = story_choices(story, node).nth(index)?;
+            Some(Step { node, edge })
+        };
+        let mut taken = alloc::vec![0; story.edge_count()];
+        for (name, index, count) in state.taken {
+            if let Some(step) = step(name, index) {
+                taken[step.edge.index()] = count;
+            }
+        }
+        let mut history = Vec::new();
+        for (name, index) in state.history.unwrap_or_default() {
+            match step(name, index) {
+                Some(step) => history.push(step),
+                None => history.clear(),
+            }
+        }
+        let names = bookmark_names(guide, story, full);
+        // steps must chain to the current bookmark, and count as visits and choices taken,
+        // as saves from before choices were counted don't have them
+        let mut next = current;
+        for step in history.iter().rev() {
+            let target = story.edge_endpoints(step.edge).map(|(_, target)| target);
+            if target != Some(next) {
+                return Err(RestoreError::BrokenHistory {
+                    name: names[next.index()].to_owned(),
+                });
+            }
+            next = step.node;
+        }
+        let mut counted = (
+            alloc::vec![0; story.node_count()],
+            alloc::vec![0; story.edge_count()],
+        );
+        counted.0[next.index()] = 1;
+        for step in &history {
+            if let Some((_, target)) = story.edge_endpoints(step.edge) {
+                counted.0[target.index()] += 1;
+                counted.1[step.edge.index()] += 1;
+            }
+        }
+        for (count, least) in visits.iter_mut().zip(counted.0) {
+            *count = (*count).max(least);
+        }
+        for (count, least) in taken.iter_mut().zip(counted.1) {
+            *count = (*count).max(least);
+        }
+        Ok(Self {
+            story,
+            names,
+            current,
+            visits,
+            taken,
+            history,
+            max_history: DEFAULT_MAX_HISTORY,
+        }
+        .max_history(DEFAULT_MAX_HISTORY))
+    }
+
+    /// Name of the bookmark that `step` is taken at, along with the index of its choice
+    fn save_step(&self, step: Step) -> (String, usize) {
+        let index = story_choices(self.story, step.node)
+            .position(|(edge, _)| edge == step.edge)
+            .expect("choice of the step");
+        (self.names[step.node.index()].to_owned(), index)
+    }
+
+    /// Same as [`Playthrough::save`], but with history, so that choices can be undone once restored
+    #[must_use]
+    pub fn save_with_history(&self) -> SaveState {
+        let history = self
+            .history
+            .iter()
+            .map(|step| self.save_step(*step))
+            .collect();
+        SaveState {
+            history: Some(history),
+            ..self.save()
+        }
+    }
+
+    /// Where the playthrough is, without history to keep it small
+    #[must_use]
+    pub fn save(&self) -> SaveState {
+        SaveState {
+            current: self.names[self.current.index()].to_owned(),
+            visits: self
+                .visits
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count != 0)
+                .map(|(index, count)| (self.names[index].to_owned(), *count))
+                .collect(),
+            taken: self
+                .story
+                .edge_references()
+                .filter(|edge| self.taken[edge.id().index()] != 0)
+                .map(|edge| {
+                    let (node, edge) = (edge.source(), edge.id());
+                    let (name, index) = self.save_step(Step { node, edge });
+                    (name, index, self.taken[edge.index()])
+                })
+                .collect(),
+            history: None,
+        }
+    }
+
+    /// Choices taken, from the earliest one that is kept to the latest one
+    #[must_use]
+    pub fn history(&self) -> &[Step] {
+        &self.history
+    }
+
+    /// Undoes the latest choice kept in history, along with the visit it made,
+    /// so that a choice that may only be taken once is there again,
+    /// or returns `false` if there's none
+    pub fn back(&mut self) -> bool {
+        let Some(step) = self.history.pop() else {
+            return false;
+        };
+        let visits = &mut self.visits[self.current.index()];
+        *visits = visits.saturating_sub(1);
+        let taken = &mut self.taken[step.edge.index()];
+        *taken = taken.saturating_sub(1);
+        self.current = step.node;
+        true
+    }
+
+    /// Times the bookmark at `node` was visited, counting the start
+    #[must_use]
+    pub fn visits(&self, node: NodeIndex) -> u32 {
+        self.visits.get(node.index()).copied().unwrap_or(0)
+    }
+
+    #[must_use]
+    pub fn current(&self) -> NodeIndex {
+        self.current
+    }
+
+    /// Text of the current bookmark in `full`, which the story is read from
+    #[must_use]
+    pub fn text<'b>(&self, full: &'b str) -> &'b str {
+        story_text(self.story, self.current, full).unwrap_or_default()
+    }
+
+    /// Times the choice at `edge` was taken
+    #[must_use]
+    pub fn taken(&self, edge: EdgeIndex) -> u32 {
+        self.taken.get(edge.index()).copied().unwrap_or(0)
+    }
+
+    /// Bookmark that the choice at `edge` leads to
+    fn target(&self, edge: EdgeIndex) -> NodeIndex {
+        let (_, target) = self.story.edge_endpoints(edge).expect("edge of the story");
+        target
+    }
+
+    /// Choices of the current bookmark that can be taken, in the order they are written
+    fn available(&self) -> impl Iterator<Item = (EdgeIndex, &'a ChoiceWeight)> + '_ {
+        story_choices(self.story, self.current)
+            .filter(|(edge, weight)| !weight.once || self.taken[edge.index()] == 0)
+    }
+
+    /// Choices of the current bookmark in the order they are written, leaving out
+    /// those that may only be taken once and were taken, which are none at the end of the story
+    #[must_use]
+    pub fn choices(&self) -> Vec<ChoiceView<'a>> {
+        self.available()
+            .enumerate()
+            .map(|(index, (edge, weight))| ChoiceView {
+                index,
+                edge,
+                target: self.names[self.target(edge).index()],
+                text: weight.text.clone(),
+            })
+            .collect()
+    }
+
+    /// Moves on to the bookmark that the choice at `index` of [`Playthrough::choices`] leads to
+    ///
+    /// # Errors
+    ///
+    /// Fails if there's no choice at `index`, staying at the same bookmark
+    pub fn choose(&mut self, index: usize) -> Result<(), ChooseError> {
+        let choices: Vec<_> = self.available().collect();
+        let (edge, _) = choices.get(index).ok_or(ChooseError {
+            index,
+            choice_count: choices.len(),
+        })?;
+        let target = self.target(*edge);
+        if self.max_history != 0 {
+            if self.history.len() == self.max_history {
+                self.history.remove(0);
+            }
+            self.history.push(Step {
+                node: self.current,
+                edge: *edge,
+            });
+        }
+        self.current = target;
+        self.visits[target.index()] += 1;
+        self.taken[edge.index()] += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChooseError, Playthrough, RestoreError, SaveState, Step};
+    use crate::MissingBookmark;
+
+    const README: &str = "@bookmark{greet}\n– Hello, you!\n@choice{greet}– Come again?\n\
+        @choice{bye}– Hi!\n\n@bookmark{bye}\n– Well, farewell..\n";
+
+    #[test]
+    fn readme() {
+        let (guide, story) = crate::read([README]);
+        let mut playthrough = Playthrough::new(&guide, &story, "greet", README).expect("greet");
+        assert_eq!(playthrough.current(), guide["greet"]);
+        assert_eq!(playthrough.text(README), "\n– Hello, you!\n");
+        let choices = playthrough.choices();
+        let targets: Vec<_> = choices
+            .iter()
+            .map(|choice| (choice.index, choice.target, &README[choice.text.clone()]))
+            .collect();
+        assert_eq!(
+            targets,
+            [(0, "greet", "– Come again?\n"), (1, "bye", "– Hi!\n\n")]
+        );
+        playthrough.choose(0).expect("again");
+        assert_eq!(playthrough.current(), guide["greet"]);
+        playthrough.choose(1).expect("hi");
+        assert_eq!(playthrough.text(README), "\n– Well, farewell..");
+        assert!(playthrough.choices().is_empty());
+        assert_eq!(
+            playthrough.choose(0),
+            Err(ChooseError {
+                index: 0,
+                choice_count: 0,
+            })
+        );
+        assert_eq!(playthrough.current(), guide["bye"]);
+    }
+
+    #[test]
+    fn missing_start() {
+        let (guide, story) = crate::read([README]);
+        assert_eq!(
+            Playthrough::new(&guide, &story, "intro", README)
+                .map(|playthrough| playthrough.current()),
+            Err(MissingBookmark("intro"))
+        );
+    }
+
+    #[test]
+    fn saved() {
+        const EDITED: &str = "@bookmark{intro}Hey!\n@choice{bye}Hi\n@bookmark{bye}Bye.";
+        let (guide, story) = crate::read([README]);
+        let mut playthrough = Playthrough::new(&guide, &story, "greet", README).expect("greet");
+        playthrough.choose(0).expect("again");
+        playthrough.choose(1).expect("hi");
+        let state = playthrough.save();
+        assert_eq!(
+            state,
+            SaveState {
+                current: "bye".to_owned(),
+                visits: vec![("greet".to_owned(), 2), ("bye".to_owned(), 1)],
+                taken: vec![("greet".to_owned(), 0, 1), ("greet".to_owned(), 1, 1)],
+                history: None,
+            }
+        );
+        let (guide, story) = crate::read([EDITED]);
+        let restored = Playthrough::restore(&guide, &story, state.clone(), EDITED).expect("bye");
+        assert_eq!(restored.current(), guide["bye"]);
+        assert_eq!(restored.text(EDITED), "Bye.");
+        assert_eq!(restored.visits(guide["bye"]), 1);
+        assert_eq!(restored.visits(guide["intro"]), 0);
+        let (guide, story) = crate::read(["@bookmark{greet}Hi"]);
+        let error = Playthrough::restore(&guide, &story, state, EDITED).map(|_| ());
+        assert_eq!(
+            error,
+            Err(RestoreError::Missing {
+                name: "bye".to_owned()
+            })
+        );
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "saved bookmark `bye` is not in the story"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let (guide, story) = crate::read([README]);
+        let state = Playthrough::new(&guide, &story, "greet", README)
+            .expect("greet")
+            .save();
+        let json = serde_json::to_string(&state).expect("serialized state");
+        let state_copy: SaveState = serde_json::from_str(&json).expect("deserialized state");
+        assert_eq!(state_copy, state);
+    }
+
+    #[test]
+    fn back() {
+        let (guide, story) = crate::read([README]);
+        let (greet, bye) = (guide["greet"], guide["bye"]);
+        let mut playthrough = Playthrough::new(&guide, &story, "greet", README)
+            .expect("greet")
+            .max_history(2);
+        assert!(!playthrough.back());
+        for index in [0, 0, 1] {
+            playthrough.choose(index).expect("choice");
+        }
+        assert_eq!(playthrough.current(), bye);
+        assert_eq!(playthrough.history().len(), 2);
+        assert_eq!(playthrough.history()[1].node, greet);
+        assert_eq!(playthrough.visits(greet), 3);
+        let state = playthrough.save_with_history();
+        assert_eq!(
+            state.history,
+            Some(vec![("greet".to_owned(), 0), ("greet".to_owned(), 1)])
+        );
+        assert!(playthrough.back());
+        assert_eq!((playthrough.current(), playthrough.visits(bye)), (greet, 0));
+        assert!(playthrough.back());
+        assert_eq!(playthrough.visits(greet), 2);
+        assert!(!playthrough.back());
+        assert_eq!(playthrough.current(), greet);
+        let mut restored =
+            Playthrough::restore(&guide, &story, state.clone(), README).expect("bye");
+        let step = restored.history()[1];
+        assert_eq!(
+            step,
+            Step {
+                node: greet,
+                edge: playthrough.choices()[1].edge
+            }
+        );
+        assert!(restored.back() && restored.back() && !restored.back());
+        let old = SaveState {
+            taken: Vec::new(),
+            visits: Vec::new(),
+            ..state
+        };
+        let mut restored = Playthrough::restore(&guide, &story, old.clone(), README).expect("bye");
+        assert_eq!(restored.visits(greet), 2);
+        assert!(restored.back() && restored.back() && !restored.back());
+        assert_eq!((restored.current(), restored.visits(greet)), (greet, 1));
+        let broken = SaveState {
+            current: "greet".to_owned(),
+            ..old
+        };
+        let error = Playthrough::restore(&guide, &story, broken, README).map(|_| ());
+        assert_eq!(
+            error,
+            Err(RestoreError::BrokenHistory {
+                name: "greet".to_owned()
+            })
+        );
+        let mut forgetful = Playthrough::new(&guide, &story, "greet", README)
+            .expect("greet")
+            .max_history(0);
+        forgetful.choose(1).expect("hi");
+        assert!(forgetful.history().is_empty() && !forgetful.back());
+    }
+
+    fn targets<'a>(playthrough: &Playthrough<'a>) -> Vec<&'a str> {
+        let choices = playthrough.choices();
+        choices.iter().map(|choice| choice.target).collect()
+    }
+
+    #[test]
+    fn once() {
+        const SAMPLE: &str =
+            "@bookmark{inn}Noisy.\n@choice{gossip !once}Listen\n@choice{road}Leave\n\
+            @bookmark{gossip}Rumors.\n@choice{inn}Back\n@bookmark{road}Dust.";
+        let (guide, story) = crate::read([SAMPLE]);
+        let mut playthrough = Playthrough::new(&guide, &story, "inn", SAMPLE).expect("inn");
+        assert_eq!(targets(&playthrough), ["gossip", "road"]);
+        playthrough.choose(0).expect("listen");
+        playthrough.choose(0).expect("back");
+        assert_eq!(targets(&playthrough), ["road"]);
+        assert_eq!(playthrough.visits(guide["inn"]), 2);
+        let restored =
+            Playthrough::restore(&guide, &story, playthrough.save(), SAMPLE).expect("inn");
+        assert_eq!(targets(&restored), ["road"]);
+        assert!(playthrough.back());
+        assert!(playthrough.back());
+        assert_eq!(targets(&playthrough), ["gossip", "road"]);
+        assert_eq!(playthrough.visits(guide["gossip"]), 0);
+    }
+}
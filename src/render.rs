@@ -0,0 +1,169 @@
+use crate::{Event, Signal, Style};
+use alloc::{string::String, vec::Vec};
+
+/// Prompts of signals that the story is made of, which are never rendered
+const STORY_PROMPTS: [&str; 7] = [
+    "bookmark", "choice", "include", "link", "meta", "style", "tag",
+];
+
+/// Piece of a line that is rendered on its own
+pub(crate) enum Piece {
+    Text(String),
+    Link {
+        text: String,
+        url: String,
+    },
+    /// Signal that isn't a part of the story, as it is written, e.g. `@wave{hand}`
+    Signal(String),
+}
+
+/// Styled pieces of a line, along with whether a blank line goes before it
+#[derive(Default)]
+pub(crate) struct Line {
+    pub(crate) pieces: Vec<(Style, Piece)>,
+    pub(crate) is_paragraph: bool,
+}
+
+impl Line {
+    fn push_text(&mut self, style: Style, text: &str) {
+        match self.pieces.last_mut() {
+            Some((prev, Piece::Text(prev_text))) if *prev == style => prev_text.push_str(text),
+            _ => self.pieces.push((style, Piece::Text(text.into()))),
+        }
+    }
+
+    /// Adds a space, unless it's the start of the line or there's whitespace already
+    fn push_space(&mut self) {
+        let is_spaced = match self.pieces.last() {
+            Some((_, Piece::Text(text))) => text.ends_with(char::is_whitespace),
+            _ => false,
+        };
+        if !is_spaced && !self.is_blank() {
+            self.push_text(Style::REGULAR, " ");
+        }
+    }
+
+    /// Whether the line has something to render, but whitespace doesn't count
+    pub(crate) fn is_blank(&self) -> bool {
+        self.pieces.iter().all(|(_, piece)| match piece {
+            Piece::Text(text) => text.trim().is_empty(),
+            Piece::Link { .. } | Piece::Signal(_) => false,
+        })
+    }
+
+    pub(crate) fn style(&self) -> Style {
+        self.pieces
+            .iter()
+            .fold(Style::REGULAR, |style, (next, _)| style | *next)
+    }
+
+    pub(crate) fn heading_level(&self) -> Option<u8> {
+        self.pieces
+            .iter()
+            .find_map(|(style, _)| style.heading_level())
+    }
+
+    /// Text of the line without markup
+    pub(crate) fn plain(&self) -> String {
+        let mut plain = String::new();
+        for (_, piece) in &self.pieces {
+            match piece {
+                Piece::Text(text) | Piece::Link { text, .. } | Piece::Signal(text) => {
+                    plain.push_str(text);
+                }
+            }
+        }
+        plain.trim_end().into()
+    }
+}
+
+fn is_story_signal(signal: &Signal) -> bool {
+    matches!(signal, Signal::Call { prompt, .. } if STORY_PROMPTS.contains(&prompt.slice))
+}
+
+/// Signal as it is written, e.g. `@wave{hand}`
+fn written(signal: &Signal) -> String {
+    let mut out = String::from("@");
+    match signal {
+        Signal::Ping { .. } => (),
+        Signal::Prompt { prompt, .. } => out.push_str(prompt.slice),
+        Signal::Param { param, .. } => {
+            out.push('{');
+            out.push_str(param.slice);
+            out.push('}');
+        }
+        Signal::Call {
+            prompt,
+            param,
+            extra_params,
+            ..
+        } => {
+            out.push_str(prompt.slice);
+            for param in core::iter::once(param).chain(extra_params) {
+                out.push('{');
+                out.push_str(param.slice);
+                out.push('}');
+            }
+        }
+    }
+    out
+}
+
+/// Lines of styled events of `full` to be rendered, with signals that the story is made of
+/// left out, as well as the rest of them unless `keep_signals` is set
+pub(crate) fn lines<'a>(
+    events: impl IntoIterator<Item = Event<'a>>,
+    full: &str,
+    keep_signals: bool,
+) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut line = Line::default();
+    let mut prev_end = None;
+    for event in events {
+        let range = event.range();
+        // text before a signal is trimmed, so whitespace after it is put back as a space,
+        // where the brackets of a styled param aren't in its range
+        let is_spaced = prev_end
+            .and_then(|end| full.get(end..range.start))
+            .is_some_and(|gap| gap.trim_start_matches('}').starts_with(char::is_whitespace));
+        prev_end = Some(range.end);
+        match event {
+            Event::Text { style, content, .. } => {
+                if is_spaced && !content.slice.starts_with(char::is_whitespace) {
+                    line.push_space();
+                }
+                line.push_text(style, content.slice);
+            }
+            Event::Link {
+                url, text, style, ..
+            } => {
+                if is_spaced {
+                    line.push_space();
+                }
+                line.pieces.push((
+                    style,
+                    Piece::Link {
+                        text: text.slice.into(),
+                        url: url.slice.into(),
+                    },
+                ));
+            }
+            Event::Signal(signal) if keep_signals && !is_story_signal(&signal) => {
+                if is_spaced {
+                    line.push_space();
+                }
+                line.pieces
+                    .push((Style::REGULAR, Piece::Signal(written(&signal))));
+            }
+            Event::Break(_) => lines.push(core::mem::take(&mut line)),
+            Event::ParagraphBreak(_) => {
+                lines.push(core::mem::take(&mut line));
+                line.is_paragraph = true;
+            }
+            // whitespace before what is left out doesn't count, as in `Hello @wave!`
+            Event::Signal(_) | Event::Diagnostic(_) => (),
+        }
+    }
+    lines.push(line);
+    lines
+}
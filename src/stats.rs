@@ -0,0 +1,130 @@
+use crate::{node_events, Guide, Story};
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use petgraph::algo::{condensation, toposort};
+
+/// Counts of a single bookmark, see [`stats`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookmarkStats {
+    pub name: String,
+    /// Words of text, not counting signals and their params
+    pub words: usize,
+    /// Chars of text, not counting signals and their params, nor whitespace trimmed around them
+    pub chars: usize,
+    pub choices: usize,
+}
+
+/// Counts of a story and each of its bookmarks, see [`stats`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StoryStats {
+    /// Bookmarks in the order they are written
+    pub bookmarks: Vec<BookmarkStats>,
+    pub words: usize,
+    pub chars: usize,
+    pub choices: usize,
+    /// Most choices a single bookmark has
+    pub max_choices: usize,
+    /// Most choices in a row that don't lead back to where they were taken,
+    /// where bookmarks that lead to each other count as a single one
+    pub longest_path: usize,
+}
+
+/// Choices on the longest path through the groups of bookmarks that lead to each other
+fn longest_path(story: &Story) -> usize {
+    let groups = condensation(story.map(|_, _| (), |_, _| ()), true);
+    let order = toposort(&groups, None).expect("condensation is acyclic");
+    let mut lengths = alloc::vec![0; groups.node_count()];
+    for node in order.into_iter().rev() {
+        lengths[node.index()] = groups
+            .neighbors(node)
+            .map(|next| lengths[next.index()] + 1)
+            .max()
+            .unwrap_or(0);
+    }
+    lengths.into_iter().max().unwrap_or(0)
+}
+
+/// Counts words, chars and choices of a story read from `full`
+#[must_use]
+pub fn stats(guide: &Guide, story: &Story, full: &str) -> StoryStats {
+    let names = crate::graph::names_by_index(guide, story, full);
+    let bookmarks: Vec<_> = story
+        .node_indices()
+        .map(|node| {
+            let (words, chars) = node_events(story, node, full).text().fold(
+                (0, 0),
+                |(words, chars), (_, content)| {
+                    (
+                        words + content.slice.split_whitespace().count(),
+                        chars + content.slice.chars().count(),
+                    )
+                },
+            );
+            BookmarkStats {
+                name: names[node.index()].to_owned(),
+                words,
+                chars,
+                choices: story.edges(node).count(),
+            }
+        })
+        .collect();
+    StoryStats {
+        words: bookmarks.iter().map(|bookmark| bookmark.words).sum(),
+        chars: bookmarks.iter().map(|bookmark| bookmark.chars).sum(),
+        choices: story.edge_count(),
+        max_choices: bookmarks
+            .iter()
+            .map(|bookmark| bookmark.choices)
+            .max()
+            .unwrap_or(0),
+        longest_path: longest_path(story),
+        bookmarks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stats, BookmarkStats};
+
+    #[test]
+    fn counts() {
+        const SAMPLE: &str = "@bookmark{intro}@style{qbp}@{Hello, you!} @wave How are you?\n\
+            @choice{ask}Ask\n@choice{end}Leave\n@choice{intro}Again\n\
+            @bookmark{ask}Why?\n@choice{answer}Answer\n\
+            @bookmark{answer}Because.\n@choice{ask}Why?\n@choice{end}Fine\n@bookmark{end}The end.";
+        let (guide, story) = crate::read([SAMPLE]);
+        let story_stats = stats(&guide, &story, SAMPLE);
+        assert_eq!(
+            story_stats.bookmarks[0],
+            BookmarkStats {
+                name: "intro".to_owned(),
+                words: 5,
+                chars: "Hello, you! How are you?".len(),
+                choices: 3,
+            }
+        );
+        let words: Vec<_> = story_stats
+            .bookmarks
+            .iter()
+            .map(|bookmark| (bookmark.name.as_str(), bookmark.words))
+            .collect();
+        assert_eq!(words, [("intro", 5), ("ask", 1), ("answer", 1), ("end", 2)]);
+        assert_eq!(story_stats.words, 9);
+        assert_eq!(story_stats.choices, 6);
+        assert_eq!(story_stats.max_choices, 3);
+        assert_eq!(story_stats.longest_path, 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        const SAMPLE: &str = "@bookmark{greet}Hello, World!\n@choice{end}Hi!\n@bookmark{end}End.";
+        let (guide, story) = crate::read([SAMPLE]);
+        let story_stats = stats(&guide, &story, SAMPLE);
+        let json = serde_json::to_string(&story_stats).expect("serialized stats");
+        let stats_copy: super::StoryStats =
+            serde_json::from_str(&json).expect("deserialized stats");
+        assert_eq!(stats_copy, story_stats);
+    }
+}
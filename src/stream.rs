@@ -0,0 +1,102 @@
+use crate::{Event, EventIter, OwnedEvent, Style};
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+    iter::FusedIterator,
+    string::String,
+};
+
+/// Owned events of text read line by line, see [`read_stream`]
+#[derive(Debug)]
+pub struct ReadStream<R> {
+    reader: R,
+    line: String,
+    offset: usize,
+    /// Persistent style of scopes left open by lines read so far
+    scope: Style,
+    events: VecDeque<OwnedEvent>,
+    finished: bool,
+}
+
+impl<R: BufRead> ReadStream<R> {
+    #[must_use]
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            offset: 0,
+            scope: Style::REGULAR,
+            events: VecDeque::new(),
+            finished: false,
+        }
+    }
+}
+
+/// Go through text read line by line and parse signals out, without loading it whole.
+/// Ranges are relative to the start of the stream. Since lines are parsed one at a time,
+/// blank lines come as separate [`Event::Break`]s instead of an [`Event::ParagraphBreak`].
+/// Reading stops after the first error, which includes invalid UTF-8.
+#[must_use]
+pub fn read_stream<R: BufRead>(reader: R) -> ReadStream<R> {
+    ReadStream::new(reader)
+}
+
+impl<R: BufRead> Iterator for ReadStream<R> {
+    type Item = io::Result<OwnedEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.finished {
+                return None;
+            }
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => self.finished = true,
+                Ok(len) => {
+                    let mut events =
+                        EventIter::with_offset(&self.line, self.offset).in_scope(self.scope);
+                    self.events.extend(events.by_ref().map(Event::into_owned));
+                    self.scope = events.scope();
+                    self.offset += len;
+                }
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead> FusedIterator for ReadStream<R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::read_stream;
+    use crate::{Event, EventIter, OwnedEvent};
+    use std::io;
+
+    #[test]
+    fn same_as_whole() {
+        const SAMPLE: &str =
+            "@bookmark{greet} Hello, @name!\r\n@style{b}@{Bold} @@ home @-- note\n@style{i+}Italic\n@{open\nEnd.";
+        let streamed: Vec<_> = read_stream(SAMPLE.as_bytes())
+            .collect::<io::Result<_>>()
+            .expect("no errors");
+        let whole: Vec<OwnedEvent> = EventIter::new(SAMPLE).map(Event::into_owned).collect();
+        assert_eq!(streamed, whole);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut iter = read_stream(&b"Hello!\n\xff\nBye!"[..]);
+        assert!(matches!(iter.next(), Some(Ok(OwnedEvent::Text { .. }))));
+        assert!(matches!(iter.next(), Some(Ok(OwnedEvent::Break(_)))));
+        let error = iter.next().expect("error").expect_err("invalid data");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(iter.next().is_none());
+    }
+}
@@ -1,9 +1,19 @@
-use crate::core::{Event as CoreEvent, Iter as CoreIter, Signal, StrRange};
+use crate::{
+    core::{
+        Diagnostic, Event as CoreEvent, Iter as CoreIter, ParseConfig, Signal, Spacing, StrRange,
+    },
+    ReadOptions,
+};
+use alloc::{collections::VecDeque, vec::Vec};
 use bitflags::bitflags;
-use std::iter::Peekable;
+use core::{convert::Infallible, fmt, iter::FusedIterator, ops, ops::ControlFlow};
 
 bitflags! {
+    /// Mix of styles of text, each set by its char in a `style` param.
+    /// Custom styles have no chars of their own, and are only set by those
+    /// registered in a [`StyleConfig`], so renderers decide what they look like.
     #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Style: u16 {
         const REGULAR = 0b0000_0000_0000_0000;
         const PANEL = 0b0000_0000_0000_0001;
@@ -13,115 +23,1013 @@ bitflags! {
         const ITALIC = 0b0000_0000_0001_0000;
         const SCRATCH = 0b0000_0000_0010_0000;
         const UNDERLINE = 0b0000_0000_0100_0000;
+        const H1 = 0b0000_0000_1000_0000;
+        const H2 = 0b0000_0001_0000_0000;
+        const H3 = 0b0000_0010_0000_0000;
+        const CUSTOM_0 = 0b0000_0100_0000_0000;
+        const CUSTOM_1 = 0b0000_1000_0000_0000;
+        const CUSTOM_2 = 0b0001_0000_0000_0000;
+        const CUSTOM_3 = 0b0010_0000_0000_0000;
+        const CUSTOM_4 = 0b0100_0000_0000_0000;
+        const CUSTOM_5 = 0b1000_0000_0000_0000;
     }
 }
 
 impl Style {
-    fn from_param(param: &str) -> Self {
+    /// Heading levels, of which at most one is set
+    pub(crate) const HEADINGS: Self = Self::H1.union(Self::H2).union(Self::H3);
+
+    /// Level of the heading, from 1 to 3, or `None` if it's no heading
+    #[must_use]
+    pub fn heading_level(self) -> Option<u8> {
+        [Self::H1, Self::H2, Self::H3]
+            .into_iter()
+            .zip(1..)
+            .find_map(|(heading, level)| self.contains(heading).then_some(level))
+    }
+
+    /// Style that `ch` stands for in a `style` param, or `None` if it stands for none
+    pub(crate) fn from_char(ch: char) -> Option<Self> {
+        Some(match ch {
+            'p' => Style::PANEL,
+            'c' => Style::CODE,
+            'q' => Style::QUOTE,
+            'b' => Style::BOLD,
+            'i' => Style::ITALIC,
+            's' => Style::SCRATCH,
+            'u' => Style::UNDERLINE,
+            '1' => Style::H1,
+            '2' => Style::H2,
+            '3' => Style::H3,
+            _ => return None,
+        })
+    }
+
+    /// Style of a `style` param, which is lenient about chars that stand for no style,
+    /// along with their byte-indices within the param
+    fn from_param(param: &str, styles: &StyleConfig) -> (Self, Vec<(usize, char)>) {
         let mut style = Style::REGULAR;
-        for ch in param.chars() {
-            style |= match ch {
-                'p' => Style::PANEL,
-                'c' => Style::CODE,
-                'q' => Style::QUOTE,
-                'b' => Style::BOLD,
-                'i' => Style::ITALIC,
-                's' => Style::SCRATCH,
-                'u' => Style::UNDERLINE,
-                _ => Style::REGULAR,
+        let mut unknown = Vec::new();
+        for (index, ch) in param.char_indices() {
+            match styles.style_of(ch) {
+                Some(next) => style = style.stacked(next),
+                None => unknown.push((index, ch)),
             }
         }
-        style
+        (style, unknown)
+    }
+
+    /// Same style along with `other`, where the largest heading level of the two wins,
+    /// so that there's only one
+    fn stacked(self, other: Self) -> Self {
+        let heading = if (other & Self::HEADINGS).bits() < (self & Self::HEADINGS).bits() {
+            self & Self::HEADINGS
+        } else {
+            other & Self::HEADINGS
+        };
+        ((self | other) - Self::HEADINGS) | heading
+    }
+
+    /// Same style along with that of `scope`, except for the heading level, which is its own if any
+    fn in_scope(self, scope: Self) -> Self {
+        if self.intersects(Self::HEADINGS) {
+            (scope - Self::HEADINGS) | self
+        } else {
+            scope | self
+        }
+    }
+
+    /// Style of a `style` param, e.g. `bi` for bold italic text,
+    /// which fails if some of its chars stand for no built-in style
+    ///
+    /// # Errors
+    ///
+    /// Fails with every char that stands for no style, whitespace included
+    pub fn try_from_param(param: &str) -> Result<Self, StyleParseError> {
+        match Self::from_param(param, &StyleConfig::new()) {
+            (style, unknown) if unknown.is_empty() => Ok(style),
+            (style, unknown) => Err(StyleParseError { style, unknown }),
+        }
     }
 }
 
+/// Reason for [`Style::try_from_param`] to fail
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct StyleParseError {
+    /// Style of the chars that stand for one
+    pub style: Style,
+    /// Chars that stand for no style, along with their byte-indices within the param
+    pub unknown: Vec<(usize, char)>,
+}
+
+impl fmt::Display for StyleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown style chars")?;
+        for (position, (index, ch)) in self.unknown.iter().enumerate() {
+            let separator = if position == 0 { ": " } else { ", " };
+            write!(f, "{separator}`{ch}` at {index}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StyleParseError {}
+
+/// Index of the first custom style bit
+const CUSTOM_SHIFT: u32 = Style::CUSTOM_0.bits().trailing_zeros();
+
+/// Reason for [`StyleConfig::register`] to leave the config as it is
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum StyleConfigError {
+    /// Char stands for a built-in style already, e.g. `b`
+    BuiltIn { ch: char },
+    /// Style is not a single custom one, e.g. [`Style::CUSTOM_0`]
+    NotCustom { style: Style },
+}
+
+impl fmt::Display for StyleConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BuiltIn { ch } => write!(f, "`{ch}` stands for a built-in style"),
+            Self::NotCustom { style } => write!(f, "{style:?} is not a single custom style"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StyleConfigError {}
+
+/// Chars of custom styles in `style` params, e.g. `w` for a whisper, in addition to the built-in ones.
+/// It's [`Copy`], so that it may be kept and passed around freely.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct StyleConfig {
+    /// Chars of custom styles, in the order of their bits
+    custom: [Option<char>; 6],
+    strict: bool,
+}
+
+impl StyleConfig {
+    /// Config with nothing but built-in styles
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            custom: [None; 6],
+            strict: false,
+        }
+    }
+
+    /// Whether to follow `style` signals with [`Diagnostic::UnknownStyle`] for each char
+    /// that stands for no style, whitespace included, which is off by default
+    #[must_use]
+    pub const fn strict(self, strict: bool) -> Self {
+        Self { strict, ..self }
+    }
+
+    /// Same config with `ch` standing for `style`, which is one of custom styles,
+    /// e.g. [`Style::CUSTOM_0`], in place of the char it stood for before, if any
+    ///
+    /// # Errors
+    ///
+    /// Fails if `ch` stands for a built-in style, or if `style` is not a single custom one
+    pub fn register(mut self, ch: char, style: Style) -> Result<Self, StyleConfigError> {
+        if Style::from_char(ch).is_some() {
+            return Err(StyleConfigError::BuiltIn { ch });
+        }
+        let index = style.bits().trailing_zeros();
+        if style.bits().count_ones() != 1 || index < CUSTOM_SHIFT {
+            return Err(StyleConfigError::NotCustom { style });
+        }
+        for custom in &mut self.custom {
+            if *custom == Some(ch) {
+                *custom = None;
+            }
+        }
+        self.custom[(index - CUSTOM_SHIFT) as usize] = Some(ch);
+        Ok(self)
+    }
+
+    /// Style that `ch` stands for, either built-in or custom, or `None` if it stands for none
+    #[must_use]
+    pub fn style_of(&self, ch: char) -> Option<Style> {
+        Style::from_char(ch).or_else(|| {
+            let index = self.custom.iter().position(|custom| *custom == Some(ch))?;
+            Some(Style::from_bits_retain(
+                1 << (CUSTOM_SHIFT as usize + index),
+            ))
+        })
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Event<'a> {
     Signal(Signal<'a>),
-    Text { style: Style, content: StrRange<'a> },
-    Break,
+    Text {
+        style: Style,
+        content: StrRange<'a>,
+        /// Whether whitespace was trimmed off right before the content,
+        /// which is never the case for the params of `@style` signals
+        leading: Spacing,
+    },
+    /// Line terminator, which is either of `\n`, `\r\n` or lone `\r`
+    Break(StrRange<'a>),
+    /// Terminators of two or more lines in a row, with nothing but whitespace in between
+    ParagraphBreak(StrRange<'a>),
+    /// Follows the event it's about
+    Diagnostic(Diagnostic<'a>),
+    /// `@link{url}` along with the param after it as its text, as in `@link{url}@{the docs}`
+    /// or `@link{url}{the docs}`, or with the url as its text if there's no such param.
+    /// Link is styled as the `@style` signal right before it tells, as in `@style{b}@link{url}@{docs}`.
+    Link {
+        url: StrRange<'a>,
+        text: StrRange<'a>,
+        style: Style,
+        /// Whole link, from the `@`-char of the signal up to the end of its text param, if any
+        span: ops::Range<usize>,
+    },
 }
 
 impl<'a> Event<'a> {
     fn from_inner(event: CoreEvent<'a>) -> Self {
         match event {
             CoreEvent::Signal(sig) => Self::Signal(sig),
-            CoreEvent::Text(content) => Self::Text {
+            CoreEvent::Text { content, leading } => Self::Text {
                 style: Style::REGULAR,
                 content,
+                leading,
             },
-            CoreEvent::Break => Self::Break,
+            CoreEvent::Break(line_break) => Self::Break(line_break),
+            CoreEvent::ParagraphBreak(line_break) => Self::ParagraphBreak(line_break),
+            CoreEvent::Diagnostic(diagnostic) => Self::Diagnostic(diagnostic),
+        }
+    }
+
+    /// Byte-index range in original text: the whole [`Signal::span`] for signals and links,
+    /// trimmed content for text, terminators for breaks and the bracket for diagnostics
+    #[must_use]
+    pub fn range(&self) -> ops::Range<usize> {
+        match self {
+            Self::Signal(signal) => signal.span(),
+            Self::Link { span, .. } => span.clone(),
+            Self::Text { content, .. } => content.range.clone(),
+            Self::Break(line_break) | Self::ParagraphBreak(line_break) => line_break.range.clone(),
+            Self::Diagnostic(diagnostic) => diagnostic.range(),
         }
     }
+
+    /// Original text sliced by [`Event::range`], except for signals and links,
+    /// which only have slices of their params
+    #[must_use]
+    pub fn slice(&self) -> Option<&'a str> {
+        match self {
+            Self::Signal(_) | Self::Link { .. } => None,
+            Self::Text { content, .. } => Some(content.slice),
+            Self::Break(line_break) | Self::ParagraphBreak(line_break) => Some(line_break.slice),
+            Self::Diagnostic(
+                Diagnostic::UnclosedParam { bracket }
+                | Diagnostic::LimitExceeded { at: bracket, .. }
+                | Diagnostic::UnknownStyle { ch: bracket },
+            ) => Some(bracket.slice),
+        }
+    }
+
+    /// Compares variants, styles and slices, but not ranges,
+    /// e.g. to check that texts differing in whitespace parse the same
+    #[must_use]
+    pub fn eq_ignoring_ranges(&self, other: &Event) -> bool {
+        match (self, other) {
+            (Self::Signal(signal), Event::Signal(other)) => signal.eq_ignoring_ranges(other),
+            (
+                Self::Text {
+                    style,
+                    content,
+                    leading,
+                },
+                Event::Text {
+                    style: other_style,
+                    content: other_content,
+                    leading: other_leading,
+                },
+            ) => {
+                style == other_style
+                    && leading == other_leading
+                    && content.eq_ignoring_ranges(other_content)
+            }
+            (Self::Break(line_break), Event::Break(other))
+            | (Self::ParagraphBreak(line_break), Event::ParagraphBreak(other)) => {
+                line_break.eq_ignoring_ranges(other)
+            }
+            (Self::Diagnostic(diagnostic), Event::Diagnostic(other)) => {
+                diagnostic.eq_ignoring_ranges(other)
+            }
+            (
+                Self::Link {
+                    url, text, style, ..
+                },
+                Event::Link {
+                    url: other_url,
+                    text: other_text,
+                    style: other_style,
+                    ..
+                },
+            ) => {
+                style == other_style
+                    && url.eq_ignoring_ranges(other_url)
+                    && text.eq_ignoring_ranges(other_text)
+            }
+            _ => false,
+        }
+    }
+
+    /// Same event with every byte-index passed through `map`, e.g. to shift ranges
+    pub(crate) fn map_indices(self, map: impl Fn(usize) -> usize) -> Self {
+        let str_range = |str_range: StrRange<'a>| StrRange {
+            slice: str_range.slice,
+            range: map(str_range.range.start)..map(str_range.range.end),
+        };
+        let span = |span: ops::Range<usize>| map(span.start)..map(span.end);
+        match self {
+            Self::Signal(Signal::Ping { span: ping_span }) => Self::Signal(Signal::Ping {
+                span: span(ping_span),
+            }),
+            Self::Signal(Signal::Prompt {
+                prompt,
+                span: prompt_span,
+            }) => Self::Signal(Signal::Prompt {
+                prompt: str_range(prompt),
+                span: span(prompt_span),
+            }),
+            Self::Signal(Signal::Param {
+                param,
+                span: param_span,
+            }) => Self::Signal(Signal::Param {
+                param: str_range(param),
+                span: span(param_span),
+            }),
+            Self::Signal(Signal::Call {
+                prompt,
+                param,
+                extra_params,
+                span: call_span,
+            }) => Self::Signal(Signal::Call {
+                prompt: str_range(prompt),
+                param: str_range(param),
+                extra_params: extra_params.into_iter().map(str_range).collect(),
+                span: span(call_span),
+            }),
+            Self::Text {
+                style,
+                content,
+                leading,
+            } => Self::Text {
+                style,
+                content: str_range(content),
+                leading,
+            },
+            Self::Break(line_break) => Self::Break(str_range(line_break)),
+            Self::ParagraphBreak(line_break) => Self::ParagraphBreak(str_range(line_break)),
+            Self::Diagnostic(Diagnostic::UnclosedParam { bracket }) => {
+                Self::Diagnostic(Diagnostic::UnclosedParam {
+                    bracket: str_range(bracket),
+                })
+            }
+            Self::Diagnostic(Diagnostic::LimitExceeded { limit, at }) => {
+                Self::Diagnostic(Diagnostic::LimitExceeded {
+                    limit,
+                    at: str_range(at),
+                })
+            }
+            Self::Diagnostic(Diagnostic::UnknownStyle { ch }) => {
+                Self::Diagnostic(Diagnostic::UnknownStyle { ch: str_range(ch) })
+            }
+            Self::Link {
+                url,
+                text,
+                style,
+                span: link_span,
+            } => Self::Link {
+                url: str_range(url),
+                text: str_range(text),
+                style,
+                span: span(link_span),
+            },
+        }
+    }
+
+    /// Any signal, including [`Signal::Ping`], except for `@style` ones,
+    /// which are turned into [`Event::Text`]
+    #[must_use]
+    pub fn into_signal(self) -> Option<Signal<'a>> {
+        match self {
+            Self::Signal(signal) => Some(signal),
+            _ => None,
+        }
+    }
+
+    /// Styled text, including the params of `@style` signals and text of links, but excluding breaks
+    #[must_use]
+    pub fn into_text(self) -> Option<(Style, StrRange<'a>)> {
+        match self {
+            Self::Text { style, content, .. }
+            | Self::Link {
+                style,
+                text: content,
+                ..
+            } => Some((style, content)),
+            _ => None,
+        }
+    }
+}
+
+/// Parsing state of [`EventIter`], which passes the events of each step to a sink
+/// as they come, rather than buffering them, see [`visit`](crate::visit)
+#[derive(Clone, Debug)]
+struct Driver<'a> {
+    /// Event that came after a `@style` signal, but wasn't its param
+    peeked: Option<CoreEvent<'a>>,
+    /// Style of the params right after the last `@style` signal, which the next param gets too
+    run: Option<Style>,
+    /// Style of the text right after the last `@style` signal,
+    /// which the rest of it up to the next signal or break gets too
+    text_run: Option<Style>,
+    inner: CoreIter<'a>,
+    options: ReadOptions,
+    styles: StyleConfig,
+    /// Persistent style of scopes that are open, see [`scoped`]
+    scope: Style,
 }
 
 #[derive(Clone, Debug)]
 pub struct EventIter<'a> {
-    inner: Peekable<CoreIter<'a>>,
+    driver: Driver<'a>,
+    /// Events of the last step that are yet to come, e.g. diagnostics after styled text
+    pending: VecDeque<Event<'a>>,
 }
 
 impl<'a> EventIter<'a> {
+    fn from_inner(inner: CoreIter<'a>) -> Self {
+        Self {
+            driver: Driver {
+                peeked: None,
+                run: None,
+                text_run: None,
+                inner,
+                options: ReadOptions::new(),
+                styles: StyleConfig::new(),
+                scope: Style::REGULAR,
+            },
+            pending: VecDeque::new(),
+        }
+    }
+
     #[must_use]
     pub fn new(text: &'a str) -> Self {
-        Self {
-            inner: CoreIter::new(text).peekable(),
+        Self::from_inner(CoreIter::new(text))
+    }
+
+    /// Same as [`EventIter::new`], but without trimming whitespace off the text
+    #[must_use]
+    pub fn raw(text: &'a str) -> Self {
+        Self::from_inner(CoreIter::raw(text))
+    }
+
+    #[must_use]
+    pub fn with_config(text: &'a str, config: ParseConfig) -> Self {
+        Self::from_inner(CoreIter::with_config(text, config))
+    }
+
+    /// Same as [`EventIter::new`], but ranges are shifted by `base_offset`,
+    /// for `text` that is a part of some larger text
+    #[must_use]
+    pub fn with_offset(text: &'a str, base_offset: usize) -> Self {
+        Self::with_config_and_offset(text, ParseConfig::new(), base_offset)
+    }
+
+    /// Same as [`EventIter::with_offset`], but with `config` to parse by
+    #[must_use]
+    pub fn with_config_and_offset(text: &'a str, config: ParseConfig, base_offset: usize) -> Self {
+        Self::from_inner(CoreIter::with_config_and_offset(text, config, base_offset))
+    }
+
+    /// Same events, but with prompts of `@style` signals compared as [`ReadOptions`] tell
+    #[must_use]
+    pub fn read_options(mut self, options: ReadOptions) -> Self {
+        self.driver.options = options;
+        self
+    }
+
+    /// Same events, but with chars of `style` params standing for what `styles` tell
+    #[must_use]
+    pub fn styles(mut self, styles: StyleConfig) -> Self {
+        self.driver.styles = styles;
+        self
+    }
+
+    /// Skips events starting before `offset`, so that the next one starts at or after it,
+    /// e.g. to only show the part of text that is in view.
+    /// The lines before the one `offset` is on are jumped over without being parsed.
+    /// A `@style` signal that is skipped doesn't style the params after it,
+    /// nor does it open or close a scope, as in `@style{b+}`.
+    pub fn seek(&mut self, offset: usize) {
+        self.pending.retain(|event| event.range().start >= offset);
+        let driver = &mut self.driver;
+        if driver
+            .peeked
+            .as_ref()
+            .is_some_and(|peeked| peeked.start() >= offset)
+        {
+            return;
         }
+        driver.peeked = None;
+        driver.run = None;
+        driver.text_run = None;
+        driver.inner.seek(offset);
+    }
+
+    /// Only signals, see [`Event::into_signal`]
+    pub fn signals(self) -> impl Iterator<Item = Signal<'a>> {
+        self.filter_map(Event::into_signal)
+    }
+
+    /// Only text, see [`Event::into_text`]
+    pub fn text(self) -> impl Iterator<Item = (Style, StrRange<'a>)> {
+        self.filter_map(Event::into_text)
+    }
+}
+
+impl<'a> Default for EventIter<'a> {
+    /// Iterator over an empty string, which has no events
+    fn default() -> Self {
+        Self::new("")
     }
 }
 
 /// Go through text and parse signals out
 #[must_use]
-pub fn event_iter(text: &str) -> EventIter {
+pub fn event_iter(text: &str) -> EventIter<'_> {
     EventIter::new(text)
 }
 
+/// Same as [`event_iter`], but with prompts compared as [`ReadOptions`] tell
+#[must_use]
+pub fn event_iter_with_options(text: &str, options: ReadOptions) -> EventIter<'_> {
+    EventIter::new(text).read_options(options)
+}
+
+/// Same as [`event_iter`], but with custom styles of `styles`
+#[must_use]
+pub fn event_iter_with<'a>(text: &'a str, styles: &StyleConfig) -> EventIter<'a> {
+    EventIter::new(text).styles(*styles)
+}
+
+/// Go through text and parse signals out, keeping text ranges untrimmed
+#[must_use]
+pub fn raw_event_iter(text: &str) -> EventIter<'_> {
+    EventIter::raw(text)
+}
+
 fn event_to_param<'a>(event: &CoreEvent<'a>) -> Option<StrRange<'a>> {
     match event {
-        CoreEvent::Signal(Signal::Param(param)) => Some(param.clone()),
+        CoreEvent::Signal(Signal::Param { param, .. }) => Some(param.clone()),
         _ => None,
     }
 }
 
-fn event_to_style(event: &CoreEvent) -> Option<Style> {
+/// Url of a `@link` signal along with the text, if it goes in the same signal
+/// (e.g. `@link{url}{the docs}`), and the span of the signal
+fn event_to_link<'a>(
+    event: &CoreEvent<'a>,
+    options: ReadOptions,
+) -> Option<(StrRange<'a>, Option<StrRange<'a>>, ops::Range<usize>)> {
     match &event {
         CoreEvent::Signal(Signal::Call {
-            prompt: StrRange { slice: "style", .. },
+            prompt,
             param,
-        }) => Some(Style::from_param(param.slice)),
+            extra_params,
+            span,
+        }) if options.is_prompt(prompt.slice, "link") => {
+            Some((param.trimmed(), extra_params.first().cloned(), span.clone()))
+        }
         _ => None,
     }
 }
 
-impl<'a> Iterator for EventIter<'a> {
-    type Item = Event<'a>;
+/// Style chars of a `@style` param that opens a scope, as in `@style{b+}`, or closes it,
+/// as in `@style{b-}`, along with whether it's opened
+pub(crate) fn scope_change<'a>(param: &StrRange<'a>) -> Option<(StrRange<'a>, bool)> {
+    match param.strip_suffix("+") {
+        Some(chars) => Some((chars, true)),
+        None => param.strip_suffix("-").map(|chars| (chars, false)),
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let next = self.inner.next()?;
+/// Same `event` with the persistent style of `scope` in its own, or `scope` changed,
+/// if it's a `@style` signal that opens or closes a scope, where `@style{-}` closes every one
+pub(crate) fn scoped<'a>(
+    scope: &mut Style,
+    event: Event<'a>,
+    options: ReadOptions,
+    styles: &StyleConfig,
+) -> Event<'a> {
+    match event {
+        Event::Signal(Signal::Call {
+            ref prompt,
+            ref param,
+            ref extra_params,
+            ..
+        }) if extra_params.is_empty() && options.is_prompt(prompt.slice, "style") => {
+            if let Some((chars, is_opened)) = scope_change(param) {
+                let (style, _) = Style::from_param(chars.slice, styles);
+                *scope = if is_opened {
+                    style.in_scope(*scope)
+                } else if chars.slice.is_empty() {
+                    Style::REGULAR
+                } else {
+                    *scope - style
+                };
+            }
+            event
+        }
+        Event::Text {
+            style,
+            content,
+            leading,
+        } => Event::Text {
+            style: style.in_scope(*scope),
+            content,
+            leading,
+        },
+        Event::Link {
+            url,
+            text,
+            style,
+            span,
+        } => Event::Link {
+            url,
+            text,
+            style: style.in_scope(*scope),
+            span,
+        },
+        event => event,
+    }
+}
+
+/// Param of style chars along with the text, if it goes in the same signal (e.g. `@style{b}{Bold}`),
+/// where every param after the first one is styled, as in `@style{b}{one}{two}`
+fn event_to_style<'a, 'e>(
+    event: &'e CoreEvent<'a>,
+    options: ReadOptions,
+) -> Option<(StrRange<'a>, &'e [StrRange<'a>])> {
+    match &event {
+        CoreEvent::Signal(Signal::Call {
+            prompt,
+            param,
+            extra_params,
+            ..
+        }) if options.is_prompt(prompt.slice, "style") => Some((param.clone(), extra_params)),
+        _ => None,
+    }
+}
 
-        if let Some(style) = event_to_style(&next) {
-            let peek = self.inner.peek()?;
-            let param = event_to_param(peek)?;
-            self.inner.next();
-            Some(Event::Text {
+impl<'a> EventIter<'a> {
+    /// Same iterator, but with scopes of `scope` open from the start, see [`scoped`]
+    #[cfg(any(feature = "std", feature = "async"))]
+    pub(crate) fn in_scope(mut self, scope: Style) -> Self {
+        self.driver.scope = scope;
+        self
+    }
+
+    /// Persistent style of scopes that are open so far
+    #[cfg(any(feature = "std", feature = "async"))]
+    pub(crate) fn scope(&self) -> Style {
+        self.driver.scope
+    }
+
+    /// Passes the rest of the events to `sink` as they are parsed, which is what [`Iterator::next`]
+    /// is built on, though without buffering the events of a step that come after the first one
+    pub(crate) fn drive<B>(
+        &mut self,
+        sink: &mut impl FnMut(Event<'a>) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        while let Some(event) = self.pending.pop_front() {
+            sink(event)?;
+        }
+        while let Some(step) = self.driver.step(sink) {
+            step?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl<'a> Driver<'a> {
+    /// Link of a `@link` signal, taking the param after it as the text, if there's one
+    fn link(
+        &mut self,
+        (url, text, span): (StrRange<'a>, Option<StrRange<'a>>, ops::Range<usize>),
+        style: Style,
+    ) -> Event<'a> {
+        let (text, end) = if let Some(text) = text {
+            (text, span.end)
+        } else {
+            self.peeked = self.inner.next();
+            match self.peeked.take() {
+                Some(CoreEvent::Signal(Signal::Param {
+                    param,
+                    span: param_span,
+                })) => (param, param_span.end),
+                peeked => {
+                    self.peeked = peeked;
+                    (url.clone(), span.end)
+                }
+            }
+        };
+        Event::Link {
+            url,
+            text,
+            style,
+            span: span.start..end,
+        }
+    }
+
+    /// Passes `event` to `sink` with the persistent style of scopes in its own
+    fn emit<B>(
+        &mut self,
+        event: Event<'a>,
+        sink: &mut impl FnMut(Event<'a>) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        sink(scoped(&mut self.scope, event, self.options, &self.styles))
+    }
+
+    /// Style of a `style` param, leaving out chars that stand for no style
+    fn param_style(&self, chars: &str) -> Style {
+        chars
+            .chars()
+            .filter_map(|ch| self.styles.style_of(ch))
+            .fold(Style::REGULAR, Style::stacked)
+    }
+
+    /// Passes diagnostics of chars of a `style` param that stand for no style, if styles are strict
+    fn emit_unknown<B>(
+        &mut self,
+        chars: &StrRange<'a>,
+        sink: &mut impl FnMut(Event<'a>) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        if !self.styles.strict {
+            return ControlFlow::Continue(());
+        }
+        for (index, ch) in chars.slice.char_indices() {
+            if self.styles.style_of(ch).is_none() {
+                let ch = chars
+                    .slice(index..index + ch.len_utf8())
+                    .expect("char of the param");
+                self.emit(Event::Diagnostic(Diagnostic::UnknownStyle { ch }), sink)?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Passes diagnostics of stacked `@style` signals, which are `first`, then as many as `stacked`
+    /// of those `rest` starts with, the last of which is `last`, so that none are kept meanwhile
+    fn emit_stacked_unknown<B>(
+        &mut self,
+        first: &StrRange<'a>,
+        rest: CoreIter<'a>,
+        stacked: usize,
+        last: Option<&StrRange<'a>>,
+        sink: &mut impl FnMut(Event<'a>) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        if !self.styles.strict {
+            return ControlFlow::Continue(());
+        }
+        self.emit_unknown(first, sink)?;
+        for event in rest.take(stacked.saturating_sub(1)) {
+            if let Some((chars, _)) = event_to_style(&event, self.options) {
+                self.emit_unknown(&chars, sink)?;
+            }
+        }
+        match last {
+            Some(chars) => self.emit_unknown(chars, sink),
+            None => ControlFlow::Continue(()),
+        }
+    }
+
+    /// Passes the events of the next one of `inner` to `sink`, which are several for `@style`
+    /// signals that take params, links or text after them, or returns `None` if there are none left
+    fn step<B>(
+        &mut self,
+        sink: &mut impl FnMut(Event<'a>) -> ControlFlow<B>,
+    ) -> Option<ControlFlow<B>> {
+        let next = self.peeked.take().or_else(|| self.inner.next())?;
+        Some(self.step_from(next, sink))
+    }
+
+    fn step_from<B>(
+        &mut self,
+        next: CoreEvent<'a>,
+        sink: &mut impl FnMut(Event<'a>) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        if let Some(style) = self.run.take() {
+            if let Some(content) = event_to_param(&next) {
+                // params right after a styled one get its style too, as in `@style{q}@{one}@{two}`
+                self.run = Some(style);
+                let text = Event::Text {
+                    style,
+                    content,
+                    leading: Spacing::None,
+                };
+                return self.emit(text, sink);
+            }
+        }
+        let next = match (self.text_run.take(), next) {
+            // text is split by escapes, as in `@style{b}Hello @@you`
+            (Some(style), CoreEvent::Text { content, leading }) => {
+                self.text_run = Some(style);
+                let text = Event::Text {
+                    style,
+                    content,
+                    leading,
+                };
+                return self.emit(text, sink);
+            }
+            (_, next) => next,
+        };
+        let Some((chars, params)) = event_to_style(&next, self.options) else {
+            let event = match event_to_link(&next, self.options) {
+                Some(link) => self.link(link, Style::REGULAR),
+                None => Event::from_inner(next),
+            };
+            return self.emit(event, sink);
+        };
+        let has_params = !params.is_empty();
+        if let Some((chars, _)) = scope_change(&chars).filter(|_| !has_params) {
+            // scopes are opened and closed as the signal is emitted, and there's no text to style
+            self.emit(Event::from_inner(next), sink)?;
+            return self.emit_unknown(&chars, sink);
+        }
+        self.step_styled(next, &chars, has_params, sink)
+    }
+
+    /// Passes the events of `@style` signals that stack up from `next`, which has `chars`
+    fn step_styled<B>(
+        &mut self,
+        next: CoreEvent<'a>,
+        chars: &StrRange<'a>,
+        mut has_params: bool,
+        sink: &mut impl FnMut(Event<'a>) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let mut style = self.param_style(chars.slice);
+        // style signals without a param after them stack up,
+        // and their styles go together to the param or link after the last one.
+        // Those in between are parsed once more from `checkpoint` when needed, instead of being kept.
+        let checkpoint = self.inner.clone();
+        let mut stacked = 0;
+        let mut last: Option<(CoreEvent<'a>, StrRange<'a>)> = None;
+        let mut param = None;
+        while !has_params {
+            let Some(peeked) = self.inner.next() else {
+                break;
+            };
+            let last_chars = last.as_ref().map(|(_, chars)| chars.clone());
+            if let Some(link) = event_to_link(&peeked, self.options) {
+                let link = self.link(link, style);
+                self.emit(link, sink)?;
+                return self.emit_stacked_unknown(
+                    chars,
+                    checkpoint,
+                    stacked,
+                    last_chars.as_ref(),
+                    sink,
+                );
+            }
+            if let Some(content) = event_to_param(&peeked) {
+                param = Some(content);
+                break;
+            }
+            // text right after the signals takes the style, up to the next signal or break
+            if let CoreEvent::Text { content, leading } = peeked {
+                self.text_run = Some(style);
+                let text = Event::Text {
+                    style,
+                    content,
+                    leading,
+                };
+                self.emit(text, sink)?;
+                return self.emit_stacked_unknown(
+                    chars,
+                    checkpoint,
+                    stacked,
+                    last_chars.as_ref(),
+                    sink,
+                );
+            }
+            match event_to_style(&peeked, self.options) {
+                Some((peeked_chars, params))
+                    if !params.is_empty() || scope_change(&peeked_chars).is_none() =>
+                {
+                    style = style.stacked(self.param_style(peeked_chars.slice));
+                    has_params = !params.is_empty();
+                    stacked += 1;
+                    last = Some((peeked, peeked_chars));
+                }
+                _ => {
+                    // bailing out would let iteration resume on the next step
+                    self.peeked = Some(peeked);
+                    break;
+                }
+            }
+        }
+        let (last, last_chars) = last.unzip();
+        if let Some(content) = param {
+            self.run = Some(style);
+            let text = Event::Text {
                 style,
-                content: param,
-            })
+                content,
+                leading: Spacing::None,
+            };
+            self.emit(text, sink)?;
+        } else if has_params {
+            self.run = Some(style);
+            let source = last.as_ref().unwrap_or(&next);
+            if let Some((_, params)) = event_to_style(source, self.options) {
+                for content in params {
+                    let text = Event::Text {
+                        style,
+                        content: content.clone(),
+                        leading: Spacing::None,
+                    };
+                    self.emit(text, sink)?;
+                }
+            }
         } else {
-            Some(Event::from_inner(next))
+            // style signals without a param after them are passed through as they are
+            self.emit(Event::from_inner(next), sink)?;
+            for event in checkpoint.clone().take(stacked.saturating_sub(1)) {
+                self.emit(Event::from_inner(event), sink)?;
+            }
+            if let Some(last) = last {
+                self.emit(Event::from_inner(last), sink)?;
+            }
         }
+        self.emit_stacked_unknown(chars, checkpoint, stacked, last_chars.as_ref(), sink)
     }
 }
 
+impl<'a> Iterator for EventIter<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.pending.pop_front() {
+            return Some(event);
+        }
+        loop {
+            let mut first = None;
+            let pending = &mut self.pending;
+            let _ = self.driver.step(&mut |event| {
+                if first.is_none() {
+                    first = Some(event);
+                } else {
+                    pending.push_back(event);
+                }
+                ControlFlow::<Infallible>::Continue(())
+            })?;
+            if first.is_some() {
+                return first;
+            }
+        }
+    }
+
+    /// Any number of stacked `@style` signals may take the link or the param after them,
+    /// so there might be a single event for all of the signals and text underneath,
+    /// but never more than there are params
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let peeked = usize::from(self.driver.peeked.is_some());
+        let (lower, upper) = self.driver.inner.size_hint();
+        (
+            usize::from(lower + peeked > 0) + self.pending.len(),
+            // there may be a diagnostic for every char of a style param
+            upper
+                .filter(|_| !self.driver.styles.strict)
+                .and_then(|upper| upper.checked_add(peeked + self.pending.len())),
+        )
+    }
+}
+
+impl<'a> FusedIterator for EventIter<'a> {}
+
 #[cfg(test)]
 mod tests {
-    use super::{Event, EventIter, Style};
+    use super::{
+        event_iter_with, Diagnostic, Event, EventIter, Signal, Style, StyleConfig, StyleConfigError,
+    };
+    use crate::ReadOptions;
 
     #[test]
     fn style() {
         const SAMPLE: &str = "@style{bcqi}@{Hello}, world!";
         let mut iter = EventIter::new(SAMPLE);
         let next = iter.next().unwrap();
-        let Event::Text { style, content } = next else {
+        let Event::Text { style, content, .. } = next else {
             panic!("expected text");
         };
         assert_eq!(
@@ -130,10 +1038,433 @@ mod tests {
         );
         assert_eq!(content.slice, "Hello");
         let next = iter.next().unwrap();
-        let Event::Text { style, content } = next else {
+        let Event::Text { style, content, .. } = next else {
             panic!("expected text");
         };
         assert_eq!(style, Style::REGULAR);
         assert_eq!(content.slice, ", world!");
     }
+
+    #[test]
+    fn stacked() {
+        const SAMPLE: &str = "@style{b}@style{i}@style{u3}@{All} @style{1}@style{c}{Code} \
+            @style{s}@link{https://a.b}";
+        let styles: Vec<_> = EventIter::new(SAMPLE)
+            .filter_map(|event| match event {
+                Event::Text { style, content, .. } => Some((content.slice, style)),
+                Event::Link { text, style, .. } => Some((text.slice, style)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            styles,
+            [
+                (
+                    "All",
+                    Style::BOLD | Style::ITALIC | Style::UNDERLINE | Style::H3
+                ),
+                ("Code", Style::H1 | Style::CODE),
+                ("https://a.b", Style::SCRATCH),
+            ]
+        );
+        // stray styles without a param after them are passed through as signals
+        let events: Vec<_> = EventIter::new("@style{b}@style{i}@wave @style{u}@style{b+}")
+            .map(|event| event.range())
+            .collect();
+        assert_eq!(events, [0..9, 9..18, 18..23, 24..33, 33..43]);
+    }
+
+    #[test]
+    fn runs() {
+        const SAMPLE: &str = "@style{q}@{line one}@{line two} plain@{after}\n\
+            @style{b}{inline}@{too}\n@{next line}";
+        let styles: Vec<_> = EventIter::new(SAMPLE)
+            .text()
+            .map(|(style, text)| (text.slice, style))
+            .collect();
+        assert_eq!(
+            styles,
+            [
+                ("line one", Style::QUOTE),
+                ("line two", Style::QUOTE),
+                ("plain", Style::REGULAR),
+                ("inline", Style::BOLD),
+                ("too", Style::BOLD),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_text() {
+        const SAMPLE: &str = "@style{b}Hello, @@you @wave there\n\
+            @style{i}@{param} trailing\n@style{u}text@{param}";
+        let styles: Vec<_> = EventIter::new(SAMPLE)
+            .text()
+            .map(|(style, text)| (text.slice, style))
+            .collect();
+        // a param takes the style over the text after it, which is left as it is
+        assert_eq!(
+            styles,
+            [
+                ("Hello, ", Style::BOLD),
+                ("@", Style::BOLD),
+                ("you", Style::BOLD),
+                (" there", Style::REGULAR),
+                ("param", Style::ITALIC),
+                ("trailing", Style::REGULAR),
+                ("text", Style::UNDERLINE),
+            ]
+        );
+    }
+
+    #[test]
+    fn underline() {
+        const SAMPLE: &str = "@style{u}@{hi} @style{bu}{there}";
+        let styles: Vec<_> = EventIter::new(SAMPLE)
+            .filter_map(|event| match event {
+                Event::Text { style, .. } => Some(style),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(styles, [Style::UNDERLINE, Style::BOLD | Style::UNDERLINE]);
+    }
+
+    #[test]
+    fn headings() {
+        const SAMPLE: &str = "@style{1b}@{Chapter One}\n@style{3u2}{Aside}@style{2}{Part}";
+        let styles: Vec<_> = EventIter::new(SAMPLE)
+            .filter_map(|event| match event {
+                Event::Text { style, .. } => Some(style),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            styles,
+            [
+                Style::H1 | Style::BOLD,
+                Style::H3 | Style::UNDERLINE,
+                Style::H2
+            ]
+        );
+        let levels: Vec<_> = styles.into_iter().map(Style::heading_level).collect();
+        assert_eq!(levels, [Some(1), Some(3), Some(2)]);
+        assert_eq!(Style::BOLD.heading_level(), None);
+    }
+
+    #[test]
+    fn custom_styles() {
+        let styles = StyleConfig::new()
+            .register('w', Style::CUSTOM_0)
+            .and_then(|styles| styles.register('m', Style::CUSTOM_5))
+            .expect("custom chars");
+        let text: Vec<_> = event_iter_with("@style{wb}{psst} @style{m}{System}", &styles)
+            .text()
+            .map(|(style, _)| style)
+            .collect();
+        assert_eq!(text, [Style::CUSTOM_0 | Style::BOLD, Style::CUSTOM_5]);
+        let (style, _) = EventIter::new("@style{w}{psst}").text().next().unwrap();
+        assert_eq!(style, Style::REGULAR);
+        let moved = styles.register('w', Style::CUSTOM_2).unwrap();
+        assert_eq!(moved.style_of('w'), Some(Style::CUSTOM_2));
+        assert_eq!(
+            styles.register('b', Style::CUSTOM_1),
+            Err(StyleConfigError::BuiltIn { ch: 'b' })
+        );
+        for style in [
+            Style::BOLD,
+            Style::CUSTOM_0 | Style::CUSTOM_1,
+            Style::REGULAR,
+        ] {
+            assert_eq!(
+                styles.register('x', style),
+                Err(StyleConfigError::NotCustom { style })
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_styles() {
+        const SAMPLE: &str = "@style{bx}{Hi} @style{ë}\n@{there}";
+        assert_eq!(Style::try_from_param("bi"), Ok(Style::BOLD | Style::ITALIC));
+        let error = Style::try_from_param("qpb x").unwrap_err();
+        assert_eq!(error.style, Style::QUOTE | Style::PANEL | Style::BOLD);
+        assert_eq!(error.unknown, [(3, ' '), (4, 'x')]);
+        assert_eq!(error.to_string(), "unknown style chars: ` ` at 3, `x` at 4");
+        assert!(EventIter::new(SAMPLE).all(|event| !matches!(event, Event::Diagnostic(_))));
+        let styles = StyleConfig::new().strict(true);
+        let events: Vec<_> = event_iter_with(SAMPLE, &styles)
+            .map(|event| match event {
+                Event::Diagnostic(diagnostic) => (diagnostic.to_string(), diagnostic.range()),
+                event => (String::new(), event.range()),
+            })
+            .collect();
+        assert_eq!(
+            events,
+            [
+                (String::new(), 11..13),
+                ("unknown style `x`".to_owned(), 8..9),
+                (String::new(), 15..25),
+                ("unknown style `ë`".to_owned(), 22..24),
+                (String::new(), 25..26),
+                (String::new(), 26..34),
+            ]
+        );
+        let mut iter = event_iter_with(SAMPLE, &styles);
+        iter.next();
+        iter.seek(9);
+        assert!(!matches!(iter.next(), Some(Event::Diagnostic(_))));
+    }
+
+    #[test]
+    fn links() {
+        const SAMPLE: &str = "See @link{ https://example.com }@{the docs}, \
+            @link{https://a.b}{A} @style{bi}@link{https://c.d} or @link{https://e.f}\n@{next}";
+        let links: Vec<_> = EventIter::new(SAMPLE)
+            .filter_map(|event| match event {
+                Event::Link {
+                    url,
+                    text,
+                    style,
+                    span,
+                } => Some((url.slice, text.slice, style, &SAMPLE[span])),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            links,
+            [
+                (
+                    "https://example.com",
+                    "the docs",
+                    Style::REGULAR,
+                    "@link{ https://example.com }@{the docs}"
+                ),
+                ("https://a.b", "A", Style::REGULAR, "@link{https://a.b}{A}"),
+                (
+                    "https://c.d",
+                    "https://c.d",
+                    Style::BOLD | Style::ITALIC,
+                    "@link{https://c.d}"
+                ),
+                (
+                    "https://e.f",
+                    "https://e.f",
+                    Style::REGULAR,
+                    "@link{https://e.f}"
+                ),
+            ]
+        );
+        let text: Vec<_> = EventIter::new(SAMPLE)
+            .text()
+            .map(|(_, text)| text.slice)
+            .collect();
+        assert_eq!(
+            text,
+            [
+                "See",
+                "the docs",
+                ",",
+                "A",
+                "https://c.d",
+                " or",
+                "https://e.f"
+            ]
+        );
+    }
+
+    #[test]
+    fn scopes() {
+        const SAMPLE: &str = "@style{b+}Bold\n@style{i}{both} and\n\n@style{i+}@style{1}{Title}\
+            @style{b-}italic @style{-}regular @style{u+}@link{https://a.b}@{link}";
+        let styles: Vec<_> = EventIter::new(SAMPLE)
+            .text()
+            .map(|(style, text)| (text.slice, style))
+            .collect();
+        assert_eq!(
+            styles,
+            [
+                ("Bold", Style::BOLD),
+                ("both", Style::BOLD | Style::ITALIC),
+                ("and", Style::BOLD),
+                ("Title", Style::BOLD | Style::ITALIC | Style::H1),
+                ("italic", Style::ITALIC),
+                ("regular", Style::REGULAR),
+                ("link", Style::UNDERLINE),
+            ]
+        );
+        // scope signals are passed through, and never take the param after them
+        let signals: Vec<_> = EventIter::new("@style{b+}@{param}")
+            .map(|event| match event {
+                Event::Signal(signal) => signal.span(),
+                event => panic!("unexpected {event:?}"),
+            })
+            .collect();
+        assert_eq!(signals, [0..10, 10..18]);
+        let mut iter = event_iter_with("@style{bx+}", &StyleConfig::new().strict(true));
+        assert!(matches!(iter.next(), Some(Event::Signal(_))));
+        assert!(matches!(
+            iter.next(),
+            Some(Event::Diagnostic(Diagnostic::UnknownStyle { ch })) if ch.slice == "x"
+        ));
+    }
+
+    #[test]
+    fn signals() {
+        const SAMPLE: &str = "@ Hi, @name!\n@style{b}@{Bye} @bookmark{end}";
+        let signals: Vec<_> = EventIter::new(SAMPLE).signals().collect();
+        assert_eq!(signals.len(), 3, "{signals:?}");
+        assert!(matches!(signals[0], Signal::Ping { .. }));
+        assert!(matches!(signals[1], Signal::Prompt { .. }));
+        assert!(matches!(signals[2], Signal::Call { .. }));
+    }
+
+    #[test]
+    fn style_without_content() {
+        for (sample, span) in [
+            ("Hi @style{b}", 3..12),
+            ("@style{b}@wave Hi", 0..9),
+            ("@style{b}\n@{Hi}", 0..9),
+        ] {
+            let events: Vec<_> = EventIter::new(sample).collect();
+            assert!(
+                events.iter().any(|event| matches!(
+                    event,
+                    Event::Signal(Signal::Call { prompt, param, span: call, .. })
+                        if prompt.slice == "style" && param.slice == "b" && *call == span
+                )),
+                "{events:?}"
+            );
+            assert!(
+                events
+                    .into_iter()
+                    .filter_map(Event::into_text)
+                    .all(|(style, _)| style == Style::REGULAR),
+                "{sample:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn text() {
+        const SAMPLE: &str = "@ Hi, @name!\n@style{b}@{Bye} @bookmark{end}";
+        let text: Vec<_> = EventIter::new(SAMPLE)
+            .text()
+            .map(|(style, content)| (style, content.slice))
+            .collect();
+        assert_eq!(text, [(Style::REGULAR, "Hi,"), (Style::BOLD, "Bye")]);
+    }
+
+    #[test]
+    fn ranges() {
+        const SAMPLE: &str = "@style{b}@{Hi} @wave\r\n\n@{oops";
+        let events: Vec<_> = EventIter::new(SAMPLE).collect();
+        for event in &events {
+            if let Some(slice) = event.slice() {
+                assert_eq!(&SAMPLE[event.range()], slice);
+            }
+        }
+        let ranges: Vec<_> = events.iter().map(|event| &SAMPLE[event.range()]).collect();
+        assert_eq!(ranges, ["Hi", "@wave", "\r\n\n", "@{oops", "{"]);
+    }
+
+    #[test]
+    fn extra_params() {
+        const SAMPLE: &str = "@style{b}{Bold} and @style{i}@{Italic} @style{c}(code)[more]\n\
+            @style{u}{one}{two}@{three} four";
+        let text: Vec<_> = EventIter::new(SAMPLE)
+            .text()
+            .map(|(style, content)| (style, &SAMPLE[content.range]))
+            .collect();
+        assert_eq!(
+            text,
+            [
+                (Style::BOLD, "Bold"),
+                (Style::REGULAR, "and"),
+                (Style::ITALIC, "Italic"),
+                (Style::CODE, "code"),
+                (Style::CODE, "more"),
+                (Style::UNDERLINE, "one"),
+                (Style::UNDERLINE, "two"),
+                (Style::UNDERLINE, "three"),
+                (Style::REGULAR, "four"),
+            ]
+        );
+    }
+
+    #[test]
+    fn iterator_traits() {
+        const SAMPLE: &str = "@style{b}@{Hi} there, @name!\r\n\n@{oops\n@style{i}";
+        let events: Vec<_> = EventIter::new(SAMPLE).collect();
+        let mut iter = EventIter::new(SAMPLE);
+        for remaining in (0..=events.len()).rev() {
+            let (lower, upper) = iter.size_hint();
+            assert!(lower <= remaining, "{lower} > {remaining}");
+            assert!(
+                upper.is_some_and(|upper| upper >= remaining),
+                "{upper:?} < {remaining}"
+            );
+            let clone = iter.clone();
+            assert_eq!(
+                clone.collect::<Vec<_>>(),
+                events[events.len() - remaining..]
+            );
+            iter.next();
+        }
+        assert_eq!(iter.next(), None);
+        assert_eq!(EventIter::default().next(), None);
+        assert!(matches!(
+            events.last(),
+            Some(Event::Signal(Signal::Call { .. }))
+        ));
+    }
+
+    #[test]
+    fn case_insensitive_prompts() {
+        const SAMPLE: &str = "@Style{b}{Bold} @STYLE{I}{Regular} @style{i}{Italic}";
+        let text: Vec<_> = EventIter::new(SAMPLE)
+            .read_options(ReadOptions::new().case_insensitive_prompts(true))
+            .text()
+            .map(|(style, content)| (style, content.slice))
+            .collect();
+        assert_eq!(
+            text,
+            [
+                (Style::BOLD, "Bold"),
+                (Style::REGULAR, "Regular"),
+                (Style::ITALIC, "Italic"),
+            ]
+        );
+        let text: Vec<_> = EventIter::new(SAMPLE)
+            .text()
+            .map(|(style, content)| (style, content.slice))
+            .collect();
+        assert_eq!(text, [(Style::ITALIC, "Italic")]);
+    }
+
+    #[test]
+    fn seek() {
+        const SAMPLE: &str = "@style{b}@{Bold} @style{i}@wave Plain\n@style{c}@{Code} end";
+        let texts_from = |offset| {
+            let mut iter = EventIter::new(SAMPLE);
+            iter.seek(offset);
+            iter.map(|event| (event.slice(), event.range().start))
+                .collect::<Vec<_>>()
+        };
+        let code = SAMPLE.find("@style{c}").expect("code");
+        assert_eq!(
+            texts_from(code),
+            [(Some("Code"), code + 11), (Some("end"), code + 17)]
+        );
+        // the style signal is skipped, so its param is left as it is
+        assert_eq!(
+            texts_from(code + 1),
+            [(None, code + 9), (Some("end"), code + 17)]
+        );
+        let mut iter = EventIter::new(SAMPLE);
+        iter.next();
+        assert!(matches!(iter.next(), Some(Event::Signal(_))));
+        let wave = SAMPLE.find("@wave").expect("wave");
+        iter.seek(wave);
+        assert_eq!(iter.next().map(|event| event.range().start), Some(wave));
+    }
 }
@@ -0,0 +1,58 @@
+use crate::Event;
+
+/// Asserts that both iterators have events equal up to their ranges,
+/// see [`Event::eq_ignoring_ranges`] and [`assert_events_eq`](crate::assert_events_eq)
+///
+/// # Panics
+///
+/// Panics at the first pair of events that differ, or when either iterator ends first
+#[track_caller]
+pub fn assert_events_eq<'a, 'b>(
+    left: impl IntoIterator<Item = Event<'a>>,
+    right: impl IntoIterator<Item = Event<'b>>,
+) {
+    let mut left = left.into_iter();
+    let mut right = right.into_iter();
+    for index in 0.. {
+        match (left.next(), right.next()) {
+            (None, None) => return,
+            (Some(left), Some(right)) if left.eq_ignoring_ranges(&right) => (),
+            (left, right) => {
+                panic!("events differ at {index}\n  left: {left:?}\n right: {right:?}")
+            }
+        }
+    }
+}
+
+/// Same as [`assert_eq!`] for two iterators of events, but ignoring their ranges
+#[macro_export]
+macro_rules! assert_events_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_events_eq($left, $right)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::EventIter;
+
+    #[test]
+    fn ignoring_ranges() {
+        assert_events_eq!(
+            EventIter::new("Hello, @wave world!   \n\n@style{b}@{Bye}  "),
+            EventIter::new("Hello, @wave world!\n\n@style{b}@{Bye}"),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "events differ at 1")]
+    fn different_slices() {
+        assert_events_eq!(EventIter::new("Hi @wave"), EventIter::new("Hi @bow"));
+    }
+
+    #[test]
+    #[should_panic(expected = "events differ at 2")]
+    fn different_lengths() {
+        assert_events_eq!(EventIter::new("Hi @wave"), EventIter::new("Hi @wave @bow"));
+    }
+}
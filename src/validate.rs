@@ -0,0 +1,206 @@
+use crate::core::{Diagnostic, Event, LineIndex};
+use crate::graph::read_inspecting;
+use crate::{bookmark_names, story_text, unreachable_from, GraphError, ReadOptions, Story};
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt, ops::Range};
+
+/// How bad a [`Problem`] is, where only errors fail a [`ValidationReport`]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Severity {
+    /// Something that is read, but likely isn't what the author meant,
+    /// e.g. a bookmark without text or one that can't be reached
+    Warning,
+    /// Something that is left out of the story, e.g. a duplicate bookmark or a dangling choice
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// One of the problems of a [`ValidationReport`], with `position` being zero-based line and column
+/// of the range start, see [`LineIndex::position`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Problem {
+    pub severity: Severity,
+    pub message: String,
+    pub range: Range<usize>,
+    pub position: (usize, usize),
+}
+
+/// Everything off about a story, ordered by where it is in the text, see [`validate`]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ValidationReport {
+    pub problems: Vec<Problem>,
+}
+
+impl ValidationReport {
+    /// Whether there are no errors, while there may be warnings
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.problems
+            .iter()
+            .all(|problem| problem.severity != Severity::Error)
+    }
+}
+
+/// Lists problems one per line, as in `3:1: error: bookmark `a` is defined more than once`
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.problems {
+            let (line, column) = problem.position;
+            writeln!(
+                f,
+                "{}:{}: {}: {}",
+                line + 1,
+                column + 1,
+                problem.severity,
+                problem.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn severity(error: &GraphError) -> Severity {
+    match error {
+        GraphError::DuplicateBookmark { .. }
+        | GraphError::DanglingChoice { .. }
+        | GraphError::ChoiceOutsideBookmark { .. }
+        | GraphError::CollidingName { .. }
+        | GraphError::UnclosedParam { .. } => Severity::Error,
+        GraphError::EmptyBookmarkName { .. }
+        | GraphError::TakenAlias { .. }
+        | GraphError::InvalidChoiceWeight { .. }
+        | GraphError::EmptyChoiceCondition { .. }
+        | GraphError::EmptyChoiceLabel { .. }
+        | GraphError::IgnoredParams { .. } => Severity::Warning,
+    }
+}
+
+/// Problems of `text`, with bookmarks that can't be reached from `start`,
+/// or from the first bookmark if `is_first_start` is set
+fn report(text: &str, start: Option<&str>, is_first_start: bool) -> ValidationReport {
+    let mut unclosed = Vec::new();
+    let (guide, story, warnings): (_, Story, _) =
+        read_inspecting([text], ReadOptions::new(), |event| {
+            if let Event::Diagnostic(Diagnostic::UnclosedParam { bracket }) = event {
+                unclosed.push(bracket.clone());
+            }
+        });
+    let index = LineIndex::new(text);
+    let mut problems: Vec<_> = warnings
+        .into_iter()
+        .map(|warning| GraphError::from_warning(warning, &index))
+        .chain(
+            unclosed
+                .into_iter()
+                .map(|bracket| GraphError::UnclosedParam {
+                    position: index.position(bracket.range.start),
+                    bracket,
+                }),
+        )
+        .map(|error| Problem {
+            severity: severity(&error),
+            message: error.message(),
+            range: error.range(),
+            position: error.position(),
+        })
+        .collect();
+    let mut push = |severity, message, range: Range<usize>| {
+        problems.push(Problem {
+            severity,
+            message,
+            position: index.position(range.start),
+            range,
+        });
+    };
+    let names = bookmark_names(&guide, &story, text);
+    let start = if is_first_start {
+        names.first().copied()
+    } else {
+        start
+    };
+    for node in story.node_indices() {
+        if story_text(&story, node, text).is_some_and(|text| text.trim().is_empty()) {
+            let name = names[node.index()];
+            let message = format!("bookmark `{name}` has no text");
+            push(Severity::Warning, message, story[node].text.clone());
+        }
+    }
+    match start {
+        Some(start) if !guide.contains_key(start) => {
+            let message = format!("there's no start bookmark `{start}`");
+            push(Severity::Error, message, 0..0);
+        }
+        Some(start) => {
+            for name in unreachable_from(&guide, &story, start, text) {
+                let range = story[guide[name]].text.clone();
+                let message = format!("bookmark `{name}` can't be reached from `{start}`");
+                push(Severity::Warning, message, range);
+            }
+        }
+        None => (),
+    }
+    problems.sort_by_key(|problem| problem.range.start);
+    ValidationReport { problems }
+}
+
+/// Same as [`validate`], but with bookmarks that can't be reached from `start` instead of the first one,
+/// or none if `start` is `None`, and an error if there's no such bookmark
+#[must_use]
+pub fn validate_from(text: &str, start: Option<&str>) -> ValidationReport {
+    report(text, start, false)
+}
+
+/// Reads the story of `text` to list everything off about it, e.g. to check it in CI:
+/// errors for what is left out of the story, e.g. duplicate bookmarks, dangling choices,
+/// choices before the first bookmark and unclosed brackets, and warnings for the rest,
+/// e.g. bookmarks without text and those that can't be reached from the first bookmark.
+/// Positions are those of where the problem is, e.g. the start of the bookmark text.
+#[must_use]
+pub fn validate(text: &str) -> ValidationReport {
+    report(text, None, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate, validate_from, Severity};
+
+    #[test]
+    fn clean() {
+        const README: &str = "@bookmark{greet}\n– Hello, you!\n@choice{greet}– Come again?\n\
+            @choice{bye}– Hi!\n\n@bookmark{bye}\n– Well, farewell..\n";
+        let report = validate(README);
+        assert!(report.is_ok() && report.problems.is_empty(), "{report}");
+        assert_eq!(report.to_string(), "");
+    }
+
+    #[test]
+    fn problems() {
+        const SAMPLE: &str = "@choice{intro}Lost\n@bookmark{intro}Hi!\n@choice{nowhere}Go\n\
+            @bookmark{island}\n@bookmark{intro}Again\n@style{b";
+        let report = validate(SAMPLE);
+        assert!(!report.is_ok());
+        assert_eq!(
+            report.to_string(),
+            "1:1: error: choice of `intro` is before any bookmark\n\
+            3:1: error: choice under `intro` leads to missing bookmark `nowhere`\n\
+            4:18: warning: bookmark `island` has no text\n\
+            4:18: warning: bookmark `island` can't be reached from `intro`\n\
+            5:1: error: bookmark `intro` is defined more than once\n\
+            6:7: error: unclosed `{`\n"
+        );
+        let report = validate_from("@bookmark{a}A", Some("b"));
+        assert_eq!(report.problems.len(), 1);
+        assert_eq!(report.problems[0].severity, Severity::Error);
+        let report = validate_from("@bookmark{a}\n@bookmark{b}B", None);
+        assert!(report.is_ok());
+        assert_eq!(report.problems.len(), 1);
+    }
+}
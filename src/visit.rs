@@ -0,0 +1,236 @@
+use crate::{Diagnostic, Event, EventIter, Signal, StrRange, Style};
+use core::ops::{self, ControlFlow};
+
+/// Callbacks for [`visit`], each of which may stop visiting by returning [`ControlFlow::Break`]
+pub trait Visitor<'a> {
+    /// Value returned from [`visit`] when stopped early
+    type Break;
+
+    /// Styled text, same as [`Event::Text`]
+    fn text(
+        &mut self,
+        style: Style,
+        text: &'a str,
+        range: ops::Range<usize>,
+    ) -> ControlFlow<Self::Break> {
+        let _ = (style, text, range);
+        ControlFlow::Continue(())
+    }
+
+    fn signal(&mut self, signal: Signal<'a>) -> ControlFlow<Self::Break> {
+        let _ = signal;
+        ControlFlow::Continue(())
+    }
+
+    /// Line terminator, same as [`Event::Break`]
+    fn line_break(&mut self, terminator: StrRange<'a>) -> ControlFlow<Self::Break> {
+        let _ = terminator;
+        ControlFlow::Continue(())
+    }
+
+    /// Terminators of several lines in a row, same as [`Event::ParagraphBreak`].
+    /// Treated as a single [`Visitor::line_break`] unless overridden.
+    fn paragraph_break(&mut self, terminators: StrRange<'a>) -> ControlFlow<Self::Break> {
+        self.line_break(terminators)
+    }
+
+    fn diagnostic(&mut self, diagnostic: Diagnostic<'a>) -> ControlFlow<Self::Break> {
+        let _ = diagnostic;
+        ControlFlow::Continue(())
+    }
+
+    /// Link with its text, same as [`Event::Link`].
+    /// Treated as [`Visitor::text`] of the link text unless overridden.
+    fn link(
+        &mut self,
+        style: Style,
+        url: StrRange<'a>,
+        text: StrRange<'a>,
+    ) -> ControlFlow<Self::Break> {
+        let _ = url;
+        self.text(style, text.slice, text.range)
+    }
+}
+
+/// Go through text and pass whatever is parsed out to `visitor`, same as [`EventIter::new`] would
+pub fn visit<'a, V: Visitor<'a> + ?Sized>(text: &'a str, visitor: &mut V) -> ControlFlow<V::Break> {
+    visit_events(EventIter::new(text), visitor)
+}
+
+/// Pass the rest of `events` to `visitor`, e.g. those parsed with an offset or a config,
+/// or by [`node_events`](crate::node_events). Events are passed on as they are parsed,
+/// the same ones the iterator would give, so that none of them are buffered.
+pub fn visit_events<'a, V: Visitor<'a> + ?Sized>(
+    mut events: EventIter<'a>,
+    visitor: &mut V,
+) -> ControlFlow<V::Break> {
+    events.drive(&mut |event| match event {
+        Event::Signal(signal) => visitor.signal(signal),
+        Event::Text { style, content, .. } => visitor.text(style, content.slice, content.range),
+        Event::Break(terminator) => visitor.line_break(terminator),
+        Event::ParagraphBreak(terminators) => visitor.paragraph_break(terminators),
+        Event::Diagnostic(diagnostic) => visitor.diagnostic(diagnostic),
+        Event::Link {
+            url, text, style, ..
+        } => visitor.link(style, url, text),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{visit, visit_events, Visitor};
+    use crate::{Diagnostic, Event, EventIter, Signal, StrRange, Style, StyleConfig};
+    use core::ops::{self, ControlFlow};
+
+    #[derive(Default)]
+    struct Counter {
+        words: usize,
+        bold_words: usize,
+        signals: usize,
+        line_breaks: usize,
+    }
+
+    impl<'a> Visitor<'a> for Counter {
+        type Break = ();
+
+        fn text(&mut self, style: Style, text: &'a str, _: ops::Range<usize>) -> ControlFlow<()> {
+            let words = text.split_whitespace().count();
+            self.words += words;
+            if style.contains(Style::BOLD) {
+                self.bold_words += words;
+            }
+            ControlFlow::Continue(())
+        }
+
+        fn signal(&mut self, _: Signal<'a>) -> ControlFlow<()> {
+            self.signals += 1;
+            ControlFlow::Continue(())
+        }
+
+        fn line_break(&mut self, _: StrRange<'a>) -> ControlFlow<()> {
+            self.line_breaks += 1;
+            ControlFlow::Continue(())
+        }
+    }
+
+    /// Ranges of everything visited, along with what it is
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl<'a> Visitor<'a> for Recorder {
+        type Break = ();
+
+        fn text(&mut self, style: Style, _: &'a str, range: ops::Range<usize>) -> ControlFlow<()> {
+            self.0.push(format!("text {style:?} {range:?}"));
+            ControlFlow::Continue(())
+        }
+
+        fn signal(&mut self, signal: Signal<'a>) -> ControlFlow<()> {
+            self.0.push(format!("signal {:?}", signal.span()));
+            ControlFlow::Continue(())
+        }
+
+        fn line_break(&mut self, terminator: StrRange<'a>) -> ControlFlow<()> {
+            self.0.push(format!("break {:?}", terminator.range));
+            ControlFlow::Continue(())
+        }
+
+        fn diagnostic(&mut self, diagnostic: Diagnostic<'a>) -> ControlFlow<()> {
+            self.0.push(format!("diagnostic {:?}", diagnostic.range()));
+            ControlFlow::Continue(())
+        }
+    }
+
+    struct FindPrompt(&'static str);
+
+    impl<'a> Visitor<'a> for FindPrompt {
+        type Break = ops::Range<usize>;
+
+        fn signal(&mut self, signal: Signal<'a>) -> ControlFlow<Self::Break> {
+            match signal {
+                Signal::Prompt { prompt, span } | Signal::Call { prompt, span, .. }
+                    if prompt.slice == self.0 =>
+                {
+                    ControlFlow::Break(span)
+                }
+                _ => ControlFlow::Continue(()),
+            }
+        }
+    }
+
+    #[test]
+    fn counter() {
+        const SAMPLE: &str =
+            "@bookmark{greet} Hello there, @name!\n\n@style{b}@{Very bold} end\nBye.";
+        let mut counter = Counter::default();
+        assert_eq!(visit(SAMPLE, &mut counter), ControlFlow::Continue(()));
+        assert_eq!(counter.words, 6);
+        assert_eq!(counter.bold_words, 2);
+        assert_eq!(counter.signals, 2);
+        assert_eq!(counter.line_breaks, 2);
+    }
+
+    #[test]
+    fn early_exit() {
+        const SAMPLE: &str = "Hi @wave there\n@bookmark{x} @wave";
+        assert_eq!(
+            visit(SAMPLE, &mut FindPrompt("wave")),
+            ControlFlow::Break(3..8)
+        );
+        assert_eq!(
+            visit(SAMPLE, &mut FindPrompt("bookmark")),
+            ControlFlow::Break(15..27)
+        );
+        assert_eq!(
+            visit(SAMPLE, &mut FindPrompt("nope")),
+            ControlFlow::Continue(())
+        );
+    }
+
+    #[test]
+    fn events() {
+        const SAMPLE: &str = "Skipped.\nHi @wave there";
+        let offset = SAMPLE.find('\n').expect("line break") + 1;
+        let events = EventIter::with_offset(&SAMPLE[offset..], offset);
+        assert_eq!(
+            visit_events(events, &mut FindPrompt("wave")),
+            ControlFlow::Break(12..17)
+        );
+        assert_eq!(&SAMPLE[12..17], "@wave");
+    }
+
+    #[test]
+    fn same_as_iterator() {
+        const SAMPLE: &str = "@style{b}@style{qz}@{one}@{two} @style{i}@style{x}\n\
+            @style{bz}@link{url}{docs} @style{b+}bold@style{-} @style{i}@@text\n\n@style{y}";
+        for strict in [false, true] {
+            let styles = StyleConfig::new().strict(strict);
+            let events = || EventIter::new(SAMPLE).styles(styles);
+            let mut recorder = Recorder::default();
+            assert_eq!(
+                visit_events(events(), &mut recorder),
+                ControlFlow::Continue(())
+            );
+            let expected: Vec<_> = events()
+                .map(|event| match event {
+                    Event::Text { style, content, .. }
+                    | Event::Link {
+                        style,
+                        text: content,
+                        ..
+                    } => format!("text {style:?} {:?}", content.range),
+                    Event::Signal(signal) => format!("signal {:?}", signal.span()),
+                    Event::Break(terminator) | Event::ParagraphBreak(terminator) => {
+                        format!("break {:?}", terminator.range)
+                    }
+                    Event::Diagnostic(diagnostic) => format!("diagnostic {:?}", diagnostic.range()),
+                })
+                .collect();
+            assert_eq!(recorder.0, expected);
+            assert_eq!(
+                expected.iter().any(|event| event.starts_with("diagnostic")),
+                strict
+            );
+        }
+    }
+}
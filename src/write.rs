@@ -0,0 +1,115 @@
+use crate::{Event, Signal, StrRange};
+use alloc::string::String;
+
+fn push_range(out: &mut String, full: &str, cursor: &mut usize, str_range: &StrRange) {
+    if str_range.range.start < *cursor {
+        return;
+    }
+    out.push_str(&full[*cursor..str_range.range.start]);
+    out.push_str(str_range.slice);
+    *cursor = str_range.range.end;
+}
+
+/// Writes events back into source text, so that events of `full` reproduce it byte-for-byte.
+///
+/// Slices of events are written in place of their ranges, while the gaps in between
+/// (trimmed whitespace, `@`-chars, brackets and comments) are copied over from `full`.
+/// Hence, event slices may be replaced to edit the source, keeping the rest as it was.
+/// Diagnostics don't cover any text, so they are ignored.
+#[must_use]
+pub fn write_events<'a>(events: impl IntoIterator<Item = Event<'a>>, full: &str) -> String {
+    let mut out = String::with_capacity(full.len());
+    let mut cursor = 0;
+    for event in events {
+        match &event {
+            Event::Signal(Signal::Ping { .. }) | Event::Diagnostic(_) => (),
+            Event::Signal(
+                Signal::Prompt {
+                    prompt: str_range, ..
+                }
+                | Signal::Param {
+                    param: str_range, ..
+                },
+            )
+            | Event::Text {
+                content: str_range, ..
+            }
+            | Event::Break(str_range)
+            | Event::ParagraphBreak(str_range) => {
+                push_range(&mut out, full, &mut cursor, str_range);
+            }
+            Event::Link { url, text, .. } => {
+                push_range(&mut out, full, &mut cursor, url);
+                push_range(&mut out, full, &mut cursor, text);
+            }
+            Event::Signal(Signal::Call {
+                prompt,
+                param,
+                extra_params,
+                ..
+            }) => {
+                push_range(&mut out, full, &mut cursor, prompt);
+                push_range(&mut out, full, &mut cursor, param);
+                for param in extra_params {
+                    push_range(&mut out, full, &mut cursor, param);
+                }
+            }
+        }
+    }
+    out.push_str(&full[cursor..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_events;
+    use crate::{Event, EventIter, Signal, StrRange};
+    use quickcheck::quickcheck;
+
+    const PIECES: [&str; 18] = [
+        "@", "@@", "@--", "{", "}", "[", "]", "(", ")", " ", "\t", "\n", "\r\n", "\r", "style",
+        "bi", "hello", "ё",
+    ];
+
+    #[test]
+    fn round_trip() {
+        const SAMPLE: &str =
+            "  @bookmark{greet} Hello, @name!\r\n\n \n@style{bi}@{Bold} @@ home @-- note\n@{open";
+        assert_eq!(write_events(EventIter::new(SAMPLE), SAMPLE), SAMPLE);
+        assert_eq!(write_events(EventIter::raw(SAMPLE), SAMPLE), SAMPLE);
+    }
+
+    #[test]
+    fn edited() {
+        const SAMPLE: &str = "@bookmark{greet} Hello!";
+        let events = EventIter::new(SAMPLE).map(|event| match event {
+            Event::Signal(Signal::Call {
+                prompt,
+                param,
+                extra_params,
+                span,
+            }) => Event::Signal(Signal::Call {
+                prompt,
+                param: StrRange {
+                    slice: "welcome",
+                    range: param.range,
+                },
+                extra_params,
+                span,
+            }),
+            event => event,
+        });
+        assert_eq!(write_events(events, SAMPLE), "@bookmark{welcome} Hello!");
+    }
+
+    quickcheck! {
+        fn arbitrary_round_trip(indices: Vec<u8>) -> bool {
+            let text: String = indices
+                .into_iter()
+                .map(|index| PIECES[usize::from(index) % PIECES.len()])
+                .collect();
+            write_events(EventIter::new(&text), &text) == text
+                && write_events(EventIter::raw(&text), &text) == text
+        }
+    }
+}